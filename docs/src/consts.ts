@@ -51,6 +51,14 @@ export const SIDEBAR: Sidebar = {
       { text: "Mapper", link: "en/api/mapper" },
       { text: "Text Mapper", link: "en/api/text-mapper" },
       { text: "Chord Mapper", link: "en/api/chord-mapper" },
+      { text: "Button Chord Mapper", link: "en/api/button-chord-mapper" },
+      { text: "Gesture Mapper", link: "en/api/gesture-mapper" },
+      { text: "Axis Mapper", link: "en/api/axis-mapper" },
+      { text: "Abs Rel Mapper", link: "en/api/abs-rel-mapper" },
+      { text: "Pointer Mapper", link: "en/api/pointer-mapper" },
+      { text: "Scroll Mapper", link: "en/api/scroll-mapper" },
+      { text: "Touchpad Mapper", link: "en/api/touchpad-mapper" },
+      { text: "Tablet Mapper", link: "en/api/tablet-mapper" },
       { text: "Writer", link: "en/api/writer" },
       { text: "Virtual Writer", link: "en/api/virtual-writer" },
       { text: "Window", link: "en/api/window" },
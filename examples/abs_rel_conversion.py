@@ -0,0 +1,31 @@
+'''
+Use the right half of a touchscreen as a trackpad, and the mouse as a tablet for applications that only support
+absolute input.
+'''
+
+import map2
+
+touchscreen = map2.Reader(patterns=[ "/dev/input/by-id/example-touchscreen"])
+trackpad = map2.AbsRelMapper(
+    mode="abs_to_rel",
+    x_range={"min": 0, "max": 4095},
+    y_range={"min": 0, "max": 4095},
+    area={"left": 0.5},
+    sensitivity=0.5,
+)
+pointer = map2.Writer(capabilities={"rel": True, "buttons": True})
+
+map2.link([touchscreen, trackpad, pointer])
+
+mouse = map2.Reader(patterns=[ "/dev/input/by-id/example-mouse"])
+cursor = map2.AbsRelMapper(
+    mode="rel_to_abs",
+    x_range={"min": 0, "max": 1920},
+    y_range={"min": 0, "max": 1080},
+)
+tablet = map2.Writer(capabilities={"buttons": True, "abs": {
+    "X": {"min": 0, "max": 1920},
+    "Y": {"min": 0, "max": 1080},
+}})
+
+map2.link([mouse, cursor, tablet])
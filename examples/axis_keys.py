@@ -0,0 +1,19 @@
+'''
+Moves through menus with the left stick of a gamepad and steers with the keyboard.
+'''
+
+import map2
+
+reader = map2.Reader(patterns=["/dev/input/by-id/example-gamepad", "/dev/input/by-id/example-keyboard"])
+axes = map2.AxisMapper()
+writer = map2.Writer(capabilities={"keys": True, "abs": {
+    "RX": {"value": 128, "min": 0, "max": 255},
+}})
+
+map2.link([reader, axes, writer])
+
+# press "a" or "d" once the stick is pushed halfway to either side
+axes.map_axis_to_keys("ABS_X", negative="a", positive="d", threshold=0.5)
+
+# "j" and "l" push the right stick all the way, holding both keeps it centered
+axes.map_keys_to_axis("ABS_RX", negative="j", positive="l")
@@ -0,0 +1,27 @@
+'''
+Processes the axes of a gamepad in Rust: a radial deadzone on the sticks, a response curve and inverted pedals.
+'''
+
+import map2
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example-gamepad"])
+axes = map2.AxisMapper()
+writer = map2.Writer(capabilities={"buttons": True, "abs": {
+    "X":   {"value": 0,   "min": -100, "max": 100},
+    "RX":  {"value": 128, "min": 0,    "max": 255},
+    "RY":  {"value": 128, "min": 0,    "max": 255},
+    "GAS": {"value": 0,   "min": 0,    "max": 255},
+    "BRAKE": {"value": 0, "min": 0,    "max": 255},
+}})
+
+map2.link([reader, axes, writer])
+
+# ignore small movements around the center and rescale to [-100, 100]
+axes.map("X", deadzone=0.1, output_range={"min": -100, "max": 100})
+
+# the right stick ignores small movements in any direction
+axes.map_stick("RX", "RY", deadzone=0.2)
+
+# pedals rest at their minimum, the gas pedal responds slowly at first
+axes.map("GAS", centered=False, curve=2, input_range={"min": 0, "max": 1023}, output_range={"min": 0, "max": 255})
+axes.map("BRAKE", centered=False, invert=True)
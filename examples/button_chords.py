@@ -0,0 +1,14 @@
+'''
+Emulate a middle click by pressing the left and right buttons together, dragging with the left button keeps
+working.
+'''
+
+import map2
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example-mouse"])
+chords = map2.ButtonChordMapper(timeout_ms=50, drag_distance=3)
+writer = map2.Writer(capabilities={"rel": True, "buttons": True})
+
+map2.link([reader, chords, writer])
+
+chords.map(["btn_left", "btn_right"], "btn_middle")
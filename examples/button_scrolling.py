@@ -0,0 +1,11 @@
+'''
+Scroll by moving a trackball while holding the side button, clicking the side button still works.
+'''
+
+import map2
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example-trackball"])
+pointer = map2.PointerMapper(scroll_button="btn_side", scroll_speed=10, scroll_lock="dominant")
+writer = map2.Writer(capabilities={"rel": True, "hi_res_scroll": True, "buttons": True})
+
+map2.link([reader, pointer, writer])
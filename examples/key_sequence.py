@@ -0,0 +1,10 @@
+import map2
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example"])
+mapper = map2.Mapper(leader="space")
+writer = map2.Writer(clone_from = "/dev/input/by-id/example")
+
+map2.link([reader, mapper, writer])
+
+mapper.map_sequence(["leader", "g", "s"], "ok", timeout_ms=50)
+mapper.map_sequence(["leader", "g"], "g!", timeout_ms=50)
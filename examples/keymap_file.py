@@ -0,0 +1,34 @@
+import map2
+import os
+import tempfile
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example"])
+mapper = map2.Mapper()
+writer = map2.Writer(clone_from = "/dev/input/by-id/example")
+
+map2.link([reader, mapper, writer])
+
+mapper.map_key("a", "b", repeat=False)
+mapper.map("^c", "hello")
+mapper.map("*x", "y")
+mapper.nop("z")
+mapper.map("q", "w", taps=2)
+mapper.map_sequence(["g", "s"], "git status", timeout_ms=500)
+mapper.map_turbo("t", "u", rate_hz=20)
+
+def greet(key, state):
+    return "hi"
+
+mapper.map("d", greet)
+
+# "€" is typed with right alt on a german layout
+altgr_mapper = map2.Mapper(layout="de")
+altgr_mapper.map("€", "x")
+altgr_mapper.map("e", "y")
+
+keymap_dir = tempfile.mkdtemp()
+json_path = os.path.join(keymap_dir, "keymap.json")
+toml_path = os.path.join(keymap_dir, "keymap.toml")
+altgr_path = os.path.join(keymap_dir, "altgr.toml")
+
+empty = map2.Mapper().snapshot()
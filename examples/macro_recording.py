@@ -0,0 +1,27 @@
+import map2
+import os
+import tempfile
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example"])
+mapper = map2.Mapper()
+writer = map2.Writer(clone_from = "/dev/input/by-id/example")
+
+map2.link([reader, mapper, writer])
+
+mapper.map("a", "b")
+
+# "f1" starts and stops recording, "f2" replays the recording right away
+mapper.map_record("f1")
+mapper.map_replay("f2", timing="compress")
+
+# a second register with the original timing
+mapper.map_record("f3", register=1)
+mapper.map_replay("f4", register=1)
+
+# macros only exist in a Mapper, other mappers refuse to load them
+chord_mapper = map2.ChordMapper()
+text_mapper = map2.TextMapper()
+
+macro_keymap_path = os.path.join(tempfile.mkdtemp(), "macros.toml")
+with open(macro_keymap_path, "w") as file:
+  file.write('version = 1\n\n[[mappings]]\nfrom = "f1"\nkind = "record"\n')
@@ -0,0 +1,22 @@
+'''
+Hold the right button and draw a stroke to navigate, i.e. left for going back and right for going forward.
+Right clicks without a stroke keep working.
+'''
+
+import map2
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example-mouse"])
+gestures = map2.GestureMapper(button="btn_right", threshold=30)
+writer = map2.Writer(capabilities={"rel": True, "buttons": True, "keys": True})
+
+map2.link([reader, gestures, writer])
+
+gestures.map("left", "{leftalt down}{left}{leftalt up}")
+gestures.map("right", "{leftalt down}{right}{leftalt up}")
+
+counter = 0
+
+def increment():
+  global counter
+  counter += 1
+gestures.map("down then right", increment)
@@ -0,0 +1,25 @@
+'''
+Move the mouse using the 'w', 'a', 's', 'd' directional keys and scroll using 'j' and 'k'.
+'''
+
+import map2
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example"])
+mapper = map2.Mapper()
+writer = map2.Writer(capabilities={"rel": True, "buttons": True})
+
+map2.link([reader, mapper, writer])
+
+# move 5 units every step, speeding up by 100 units per second held, up to 30 units per step
+mapper.map_motion("w", axis="y", speed=-5, accel=100, max_speed=30)
+mapper.map_motion("a", axis="x", speed=-5, accel=100, max_speed=30)
+mapper.map_motion("s", axis="y", speed=5, accel=100, max_speed=30)
+mapper.map_motion("d", axis="x", speed=5, accel=100, max_speed=30)
+
+# scroll down and up
+mapper.map_scroll("j", speed=-1)
+mapper.map_scroll("k", speed=1)
+
+# constant speed, used for testing
+mapper.map_motion("i", axis="y", speed=-10)
+mapper.map_motion("l", axis="x", speed=10)
@@ -0,0 +1,25 @@
+'''
+Use the top left quarter of a pen tablet for the whole screen, soften the pressure and turn the stylus button into a
+right click, or a middle click while using the eraser.
+'''
+
+import map2
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example-tablet"])
+tablet = map2.TabletMapper(
+    x_range={"min": 0, "max": 1000},
+    y_range={"min": 0, "max": 1000},
+    area={"right": 0.5, "bottom": 0.5},
+    pressure_range={"min": 0, "max": 1000},
+    pressure_curve=2,
+)
+writer = map2.Writer(capabilities={"tablet": True, "buttons": True, "abs": {
+    "X": {"min": 0, "max": 1000, "resolution": 10},
+    "Y": {"min": 0, "max": 1000, "resolution": 10},
+    "PRESSURE": {"min": 0, "max": 1000},
+}})
+
+map2.link([reader, tablet, writer])
+
+tablet.map_button("btn_stylus", "btn_right")
+tablet.map_button("btn_stylus", "btn_middle", tool="rubber")
@@ -0,0 +1,18 @@
+'''
+Slows down a mouse, accelerates fast motion and rotates a trackball mounted sideways, without calling into python
+for every event.
+'''
+
+import map2
+
+mouse = map2.Reader(patterns=[ "/dev/input/by-id/example-mouse"])
+trackball = map2.Reader(patterns=[ "/dev/input/by-id/example-trackball"])
+
+# every device gets its own settings
+mouse_pointer = map2.PointerMapper(sensitivity=0.5, profile="adaptive", accel=0.25, threshold=4, max_gain=3)
+trackball_pointer = map2.PointerMapper(rotation=90, invert_y=True)
+
+writer = map2.Writer(capabilities={"rel": True, "buttons": True})
+
+map2.link([mouse, mouse_pointer, writer])
+map2.link([trackball, trackball_pointer, writer])
@@ -0,0 +1,22 @@
+'''
+Slows down and inverts the scrolling of a mouse with a high-resolution wheel, and converts the scrolling of
+a trackball without one, so both scroll smoothly.
+'''
+
+import map2
+
+mouse = map2.Reader(patterns=[ "/dev/input/by-id/example-mouse"])
+trackball = map2.Reader(patterns=[ "/dev/input/by-id/example-trackball"])
+
+# every device gets its own settings
+mouse_scroll = map2.ScrollMapper(scale=0.5, invert=True)
+trackball_scroll = map2.ScrollMapper(output="hi_res")
+
+writer = map2.Writer(capabilities={"rel": True, "hi_res_scroll": True, "buttons": True})
+
+map2.link([mouse, mouse_scroll, writer])
+map2.link([trackball, trackball_scroll, writer])
+
+# a wheel that isn't converted, the writer only adds high-resolution events that are missing
+wheel = map2.Reader(patterns=[ "/dev/input/by-id/example-wheel"])
+map2.link([wheel, writer])
@@ -0,0 +1,16 @@
+import map2
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example"])
+mapper = map2.Mapper()
+writer = map2.Writer(clone_from = "/dev/input/by-id/example")
+
+map2.link([reader, mapper, writer])
+
+mapper.map("a", "b")
+mapper.map("c", "d")
+base = mapper.snapshot()
+
+mapper.map("a", "x")
+mapper.map("e", "f")
+mapper.unmap("c")
+app = mapper.snapshot()
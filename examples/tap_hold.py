@@ -0,0 +1,14 @@
+import map2
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example"])
+mapper = map2.Mapper()
+writer = map2.Writer(clone_from = "/dev/input/by-id/example")
+
+map2.link([reader, mapper, writer])
+
+# double tapping "a" types "x", triple tapping types "y"
+mapper.map("a", "x", taps=2, window_ms=50)
+mapper.map("a", "y", taps=3, window_ms=50)
+
+# holding "b" presses "z" until "b" is released
+mapper.map("b", "z", hold_ms=50)
@@ -57,6 +57,11 @@ pub fn reader_send(py: Python, module: &PyModule, name: &str, ev: &EvdevInputEve
     target.call_method(py, "__test__write_ev", (ev,), None).unwrap();
 }
 
+pub fn set_active_window(py: Python, module: &PyModule, class: &str, title: &str) {
+    let map2 = module.getattr("map2").unwrap().to_object(py);
+    map2.call_method1(py, "__set_active_window", (class, title)).unwrap();
+}
+
 pub fn sleep(py: Python, millis: u64) {
     py.allow_threads(|| {
         thread::sleep(Duration::from_millis(millis));
@@ -0,0 +1,72 @@
+use crate::*;
+use pyo3::types::IntoPyDict;
+
+#[pyo3_asyncio::tokio::test]
+async fn abs_to_rel() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        // the first position of a contact doesn't move the pointer
+        reader_send_all(py, m, "touchscreen", &keys("{absolute X 3000}{absolute Y 1000}"));
+        sleep(py, 5);
+        assert_empty!(py, m, "pointer");
+
+        reader_send_all(py, m, "touchscreen", &keys("{absolute X 3100}{absolute Y 900}"));
+        sleep(py, 5);
+        assert_keys!(py, m, "pointer", "{relative X 50}{relative Y -50}");
+
+        // the left half is outside of the active area
+        reader_send_all(py, m, "touchscreen", &keys("{absolute X 1000}"));
+        sleep(py, 5);
+        assert_keys!(py, m, "pointer", "{relative X -526}");
+
+        // lifting the finger, the next contact starts over
+        reader_send_all(py, m, "touchscreen", &keys("{btn_touch up}{absolute X 4000}{absolute X 4010}"));
+        sleep(py, 5);
+        assert_keys!(py, m, "pointer", "{btn_touch up}{relative X 5}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn rel_to_abs() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        // the cursor starts in the middle
+        reader_send_all(py, m, "mouse", &keys("{relative X 10}{relative Y -20}"));
+        sleep(py, 5);
+        assert_keys!(py, m, "tablet", "{absolute X 970}{absolute Y 520}");
+
+        // the cursor stays within the range, unchanged positions aren't sent again
+        reader_send_all(py, m, "mouse", &keys("{relative X 5000}{relative X 100}{relative X -100}"));
+        sleep(py, 5);
+        assert_keys!(py, m, "tablet", "{absolute X 1920}{absolute X 1820}");
+
+        reader_send_all(py, m, "mouse", &keys("{btn_left down}{btn_left up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, "tablet", "{btn_left down}{btn_left up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn invalid_scaling() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let map2 = m.getattr("map2")?;
+
+        for (name, value) in [("sensitivity", f64::INFINITY), ("aspect", f64::INFINITY), ("aspect", f64::NAN)] {
+            let kwargs = [(name, value)].into_py_dict(py);
+            let err = map2.getattr("AbsRelMapper")?.call((), Some(kwargs)).unwrap_err();
+            assert!(err.to_string().contains(&format!("'{}' must be a positive number", name)));
+        }
+
+        Ok(())
+    })?;
+    Ok(())
+}
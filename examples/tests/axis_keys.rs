@@ -0,0 +1,55 @@
+use evdev_rs::enums::{EventCode, EV_ABS};
+
+use crate::*;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+fn abs(axis: EV_ABS, value: i32) -> EvdevInputEvent {
+    EvdevInputEvent::new(&Default::default(), &EventCode::EV_ABS(axis), value)
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn axis_to_keys() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{absolute X 255}{absolute X 200}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{d down}");
+
+        // released below the threshold minus the hysteresis, pressed again only above the threshold
+        reader_send_all(py, m, READER, &keys("{absolute X 170}{absolute X 180}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{d up}");
+
+        reader_send_all(py, m, READER, &keys("{absolute X 0}{absolute X 128}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{a down}{a up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn keys_to_axis() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{j down}{l down}{j up}{l up}"));
+        sleep(py, 5);
+        assert_eq!(
+            writer_read_all(py, m, WRITER),
+            vec![abs(ABS_RX, 0), abs(ABS_RX, 128), abs(ABS_RX, 255), abs(ABS_RX, 128)]
+        );
+
+        // other keys pass through
+        reader_send_all(py, m, READER, &keys("x"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "x");
+
+        Ok(())
+    })?;
+    Ok(())
+}
@@ -0,0 +1,88 @@
+use evdev_rs::enums::{EventCode, EV_ABS};
+
+use crate::*;
+use pyo3::types::IntoPyDict;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+fn abs(axis: EV_ABS, value: i32) -> EvdevInputEvent {
+    EvdevInputEvent::new(&Default::default(), &EventCode::EV_ABS(axis), value)
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn deadzone_and_range() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{absolute X 130}{absolute X 255}{absolute X 0}{absolute X 128}"));
+        // still within the deadzone, the value didn't change
+        reader_send_all(py, m, READER, &keys("{absolute X 129}"));
+        sleep(py, 5);
+        assert_eq!(
+            writer_read_all(py, m, WRITER),
+            vec![abs(ABS_X, 0), abs(ABS_X, 100), abs(ABS_X, -100), abs(ABS_X, 0)]
+        );
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn radial_deadzone() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{absolute RX 140}{absolute RY 140}"));
+        sleep(py, 5);
+        assert_eq!(writer_read_all(py, m, WRITER), vec![abs(ABS_RX, 128), abs(ABS_RY, 128)]);
+
+        // leaving the deadzone moves both axes
+        reader_send_all(py, m, READER, &keys("{absolute RX 255}"));
+        sleep(py, 5);
+        assert_eq!(writer_read_all(py, m, WRITER), vec![abs(ABS_RX, 254), abs(ABS_RY, 140)]);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn pedals() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{absolute GAS 512}{absolute BRAKE 0}{absolute BRAKE 255}"));
+        sleep(py, 5);
+        assert_eq!(writer_read_all(py, m, WRITER), vec![abs(ABS_GAS, 64), abs(ABS_BRAKE, 255), abs(ABS_BRAKE, 0)]);
+
+        // unmapped axes and keys pass through
+        reader_send_all(py, m, READER, &keys("{absolute Y 7}a"));
+        sleep(py, 5);
+        let mut expected = vec![abs(ABS_Y, 7)];
+        expected.extend(keys("a"));
+        assert_eq!(writer_read_all(py, m, WRITER), expected);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn invalid_options() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let axes = m.getattr("axes")?;
+
+        let kwargs = [("deadzone", 1.5)].into_py_dict(py);
+        let err = axes.call_method("map", ("Z",), Some(kwargs)).unwrap_err();
+        assert!(err.to_string().contains("'deadzone' must be a number within [0, 1)"));
+
+        let err = axes.call_method1("map", ("nope",)).unwrap_err();
+        assert!(err.to_string().contains("invalid axis 'nope'"));
+
+        Ok(())
+    })?;
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use crate::*;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+#[pyo3_asyncio::tokio::test]
+async fn chord() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{btn_left down}{btn_right down}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{btn_middle down}");
+
+        reader_send_all(py, m, READER, &keys("{btn_right up}{relative X 5}{btn_left up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{btn_middle up}{relative X 5}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn click() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{btn_left down}{relative X 1}{btn_left up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{btn_left down}{relative X 1}{btn_left up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn timeout() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{btn_left down}"));
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        // the other button comes too late for a chord
+        sleep(py, 80);
+        reader_send_all(py, m, READER, &keys("{btn_right down}{btn_right up}{btn_left up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{btn_left down}{btn_right down}{btn_right up}{btn_left up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn drag() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{btn_left down}{relative X 2}"));
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        // moving far enough starts the drag without waiting for the timeout
+        reader_send_all(py, m, READER, &keys("{relative Y 2}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{btn_left down}{relative X 2}{relative Y 2}");
+
+        reader_send_all(py, m, READER, &keys("{btn_right down}{btn_right up}{btn_left up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{btn_right down}{btn_right up}{btn_left up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
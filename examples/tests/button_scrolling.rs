@@ -0,0 +1,69 @@
+use evdev_rs::enums::{EventCode, EV_REL};
+
+use crate::*;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+fn rel(axis: EV_REL, value: i32) -> EvdevInputEvent {
+    EvdevInputEvent::new(&Default::default(), &EventCode::EV_REL(axis), value)
+}
+
+// a device frame, the motion followed by a sync
+fn frame(input: &str) -> Vec<EvdevInputEvent> {
+    let mut events = keys(input);
+    events.push(SYN_REPORT.clone());
+    events
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn click() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{btn_side down}"));
+        // too little motion to start scrolling
+        reader_send_all(py, m, READER, &frame("{relative X 1}"));
+        reader_send_all(py, m, READER, &keys("{btn_side up}"));
+        sleep(py, 5);
+        let mut expected = vec![SYN_REPORT.clone()];
+        expected.extend(keys("{btn_side}"));
+        assert_eq!(writer_read_all(py, m, WRITER), expected);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn scroll_while_held() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{btn_side down}"));
+        reader_send_all(py, m, READER, &frame("{relative Y 2}"));
+        reader_send_all(py, m, READER, &frame("{relative Y 10}"));
+        sleep(py, 5);
+        assert_eq!(
+            writer_read_all(py, m, WRITER),
+            vec![SYN_REPORT.clone(), rel(REL_WHEEL_HI_RES, -100), SYN_REPORT.clone()]
+        );
+
+        // locked onto the vertical axis, a full detent adds a regular wheel event
+        reader_send_all(py, m, READER, &frame("{relative X 3}{relative Y 5}"));
+        sleep(py, 5);
+        assert_eq!(
+            writer_read_all(py, m, WRITER),
+            vec![rel(REL_WHEEL_HI_RES, -50), rel(REL_WHEEL, -1), SYN_REPORT.clone()]
+        );
+
+        // the button isn't clicked after scrolling, the pointer moves again
+        reader_send_all(py, m, READER, &keys("{btn_side up}"));
+        reader_send_all(py, m, READER, &frame("{relative X 4}"));
+        sleep(py, 5);
+        assert_eq!(writer_read_all(py, m, WRITER), vec![rel(REL_X, 4), SYN_REPORT.clone()]);
+
+        Ok(())
+    })?;
+    Ok(())
+}
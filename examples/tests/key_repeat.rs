@@ -0,0 +1,65 @@
+use crate::*;
+use pyo3::types::IntoPyDict;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+#[pyo3_asyncio::tokio::test]
+async fn generated_repeat() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{a down}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{b down}");
+
+        // source repeats are dropped
+        reader_send_all(py, m, READER, &keys("{a repeat}"));
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        sleep(py, 60);
+        reader_send_all(py, m, READER, &keys("{a up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{b repeat}{b up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn repeat_disabled_per_mapping() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{c down}{c repeat}"));
+        sleep(py, 70);
+        reader_send_all(py, m, READER, &keys("{c up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{d down}{d up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn invalid_repeat() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        for (delay, rate) in
+            [(f64::NAN, 10.0), (f64::INFINITY, 10.0), (1e300, 10.0), (50.0, f64::NAN), (50.0, f64::INFINITY)]
+        {
+            let repeat = [("delay", delay), ("rate", rate)].into_py_dict(py);
+            let kwargs = [("repeat", repeat)].into_py_dict(py);
+            let err = mapper.call_method("map", ("e", "f"), Some(kwargs)).unwrap_err();
+            assert!(err.to_string().contains("'repeat' delay must be between 0 and 10000"));
+        }
+
+        Ok(())
+    })?;
+    Ok(())
+}
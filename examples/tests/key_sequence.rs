@@ -0,0 +1,54 @@
+use crate::*;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+#[pyo3_asyncio::tokio::test]
+async fn complete_sequence() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{space}gs"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "ok");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn prefix_sequence_fires_on_timeout() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{space}g"));
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+        sleep(py, 70);
+        assert_keys!(py, m, WRITER, "g!");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn incomplete_sequence_flushes() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        // non-matching key
+        reader_send_all(py, m, READER, &keys("{space}x"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{space}x");
+
+        // timeout
+        reader_send_all(py, m, READER, &keys("{space}"));
+        sleep(py, 75);
+        assert_keys!(py, m, WRITER, "{space}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use crate::*;
+use pyo3::types::IntoPyDict;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+// everything except user-functions survives the round trip
+fn assert_round_trip(py: Python, m: &PyModule, path: &str) -> PyResult<()> {
+    let mapper = m.getattr("mapper")?;
+    let path = m.getattr(path)?;
+    let before: Vec<&PyAny> = mapper.call_method0("mappings")?.extract()?;
+
+    mapper.call_method0("snapshot")?.call_method1("to_file", (path,))?;
+    mapper.call_method1("snapshot", (m.getattr("empty")?,))?;
+    assert_eq!(mapper.call_method0("mappings")?.len()?, 0);
+    mapper.call_method1("load", (path,))?;
+
+    let after: Vec<&PyAny> = mapper.call_method0("mappings")?.extract()?;
+    let expected: Vec<&PyAny> =
+        before.into_iter().filter(|x| x.get_item("kind").unwrap().extract::<String>().unwrap() != "callback").collect();
+    assert_eq!(after.len(), expected.len());
+    for (a, b) in after.iter().zip(expected.iter()) {
+        assert!(a.eq(b)?, "{} != {}", a, b);
+    }
+
+    reader_send_all(py, m, READER, &keys("a"));
+    sleep(py, 5);
+    assert_keys!(py, m, WRITER, "b");
+
+    reader_send_all(py, m, READER, &keys("{shift down}x{shift up}"));
+    sleep(py, 5);
+    assert_keys!(py, m, WRITER, "{shift down}y{shift up}");
+
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn json_round_trip() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        assert_round_trip(py, m, "json_path")
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn toml_round_trip() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        assert_round_trip(py, m, "toml_path")
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn invalid_keymap() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+        let path: String = m.getattr("json_path")?.extract()?;
+
+        std::fs::write(&path, r#"{ "version": 2, "mappings": [] }"#).unwrap();
+        let err = mapper.call_method1("load", (&path,)).unwrap_err();
+        assert!(err.to_string().contains("unsupported keymap version"));
+
+        std::fs::write(&path, r#"{ "version": 1, "mappings": [{ "from": "a", "kind": "chord", "to": "b" }] }"#)
+            .unwrap();
+        let err = mapper.call_method1("load", (&path,)).unwrap_err();
+        assert!(err.to_string().contains("entry 1:\nunknown kind 'chord'"));
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn right_alt_round_trip() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("altgr_mapper")?;
+        let path = m.getattr("altgr_path")?;
+
+        let before: Vec<&PyAny> = mapper.call_method0("mappings")?.extract()?;
+        let from: Vec<String> =
+            before.iter().map(|x| x.get_item("from").unwrap().extract::<String>().unwrap()).collect();
+        assert_eq!(from, vec!["e", "€"]);
+        let resolved = mapper.call_method1("resolve", ("€",))?;
+        assert_eq!(resolved.get_item("from")?.extract::<String>()?, "€");
+
+        // the right alt mapping doesn't replace the plain one when loaded back
+        mapper.call_method0("snapshot")?.call_method1("to_file", (path,))?;
+        let loaded = m.getattr("map2")?.getattr("Mapper")?.call((), Some([("layout", "de")].into_py_dict(py)))?;
+        loaded.call_method1("load", (path,))?;
+        let after: Vec<&PyAny> = loaded.call_method0("mappings")?.extract()?;
+        assert_eq!(after.len(), before.len());
+        for (a, b) in after.iter().zip(before.iter()) {
+            assert!(a.eq(b)?, "{} != {}", a, b);
+        }
+
+        Ok(())
+    })?;
+    Ok(())
+}
@@ -0,0 +1,163 @@
+use crate::*;
+use pyo3::types::IntoPyDict;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+#[pyo3_asyncio::tokio::test]
+async fn record_and_replay() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        reader_send_all(py, m, READER, &keys("{f1}"));
+        sleep(py, 5);
+        assert_eq!(mapper.call_method0("recording")?.extract::<Option<u32>>()?, Some(0));
+
+        reader_send_all(py, m, READER, &keys("ac{f1}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "bc");
+        assert_eq!(mapper.call_method0("recording")?.extract::<Option<u32>>()?, None);
+
+        reader_send_all(py, m, READER, &keys("{f2}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "bc");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn registers() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        reader_send_all(py, m, READER, &keys("{f3}x{f3}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "x");
+
+        reader_send_all(py, m, READER, &keys("{f4}"));
+        sleep(py, 20);
+        assert_keys!(py, m, WRITER, "x");
+
+        // nothing was recorded into the default register
+        assert!(!mapper.call_method0("replay")?.extract::<bool>()?);
+
+        mapper.call_method1("record", (2,))?;
+        reader_send_all(py, m, READER, &keys("a"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "b");
+        assert_eq!(mapper.call_method0("stop_recording")?.extract::<Option<u32>>()?, Some(2));
+
+        let kwargs = [("timing", "compress")].into_py_dict(py);
+        assert!(mapper.call_method("replay", (2,), Some(kwargs))?.extract::<bool>()?);
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "b");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn invalid_timing() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        let kwargs = [("timing", "fast")].into_py_dict(py);
+        let err = mapper.call_method("map_replay", ("f5",), Some(kwargs)).unwrap_err();
+        assert!(err.to_string().contains("'timing' must be one of: 'preserve', 'compress'"));
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn replays_arent_recorded() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+        let compress = [("timing", "compress")].into_py_dict(py);
+
+        mapper.call_method1("record", (3,))?;
+        reader_send_all(py, m, READER, &keys("a"));
+        sleep(py, 5);
+        mapper.call_method0("stop_recording")?;
+        assert_keys!(py, m, WRITER, "b");
+
+        mapper.call_method1("record", (4,))?;
+        reader_send_all(py, m, READER, &keys("c"));
+        sleep(py, 5);
+        mapper.call_method("replay", (3,), Some(compress))?;
+        sleep(py, 5);
+        mapper.call_method0("stop_recording")?;
+        assert_keys!(py, m, WRITER, "cb");
+
+        // only the typed key ended up in the register
+        mapper.call_method("replay", (4,), Some(compress))?;
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "c");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn cancelled_replay_releases_keys() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        mapper.call_method1("record", (5,))?;
+        reader_send_all(py, m, READER, &keys("{a down}"));
+        sleep(py, 50);
+        reader_send_all(py, m, READER, &keys("{a up}"));
+        sleep(py, 5);
+        mapper.call_method0("stop_recording")?;
+        assert_keys!(py, m, WRITER, "{b down}{b up}");
+
+        mapper.call_method1("replay", (5,))?;
+        sleep(py, 10);
+        assert_keys!(py, m, WRITER, "{b down}");
+
+        // restoring a snapshot cancels the replay
+        mapper.call_method1("snapshot", (mapper.call_method0("snapshot")?,))?;
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{b up}");
+        sleep(py, 60);
+        assert_empty!(py, m, WRITER);
+
+        // so does replaying again
+        mapper.call_method1("replay", (5,))?;
+        sleep(py, 10);
+        mapper.call_method1("replay", (5,))?;
+        sleep(py, 10);
+        assert_keys!(py, m, WRITER, "{b down}{b up}{b down}");
+        sleep(py, 60);
+        assert_keys!(py, m, WRITER, "{b up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn macros_rejected_outside_mapper() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let path = m.getattr("macro_keymap_path")?;
+
+        for name in ["chord_mapper", "text_mapper"] {
+            let err = m.getattr(name)?.call_method1("load", (path,)).unwrap_err();
+            assert!(err.to_string().contains("'record' mappings are only supported by a Mapper"));
+        }
+
+        Ok(())
+    })?;
+    Ok(())
+}
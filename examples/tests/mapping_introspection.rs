@@ -0,0 +1,75 @@
+use crate::*;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+fn info(mapping: &PyAny, key: &str) -> PyResult<Option<String>> {
+    mapping.get_item(key)?.extract()
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn list_mappings() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        let mappings: Vec<&PyAny> = mapper.call_method0("mappings")?.extract()?;
+        assert_eq!(mappings.len(), 5);
+
+        let a = mappings.iter().find(|x| info(x, "from").ok().flatten().as_deref() == Some("a")).unwrap();
+        assert_eq!(info(a, "kind")?.as_deref(), Some("sequence"));
+        assert_eq!(info(a, "to")?.as_deref(), Some("b"));
+
+        let d = mappings.iter().find(|x| info(x, "from").ok().flatten().as_deref() == Some("d")).unwrap();
+        assert_eq!(info(d, "kind")?.as_deref(), Some("callback"));
+        assert_eq!(info(d, "to")?.as_deref(), Some("greet"));
+
+        let seq = mappings.iter().find(|x| x.get_item("from").unwrap().extract::<Vec<String>>().is_ok()).unwrap();
+        assert_eq!(seq.get_item("from")?.extract::<Vec<String>>()?, vec!["g", "s"]);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn resolve_mapping() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        let c = mapper.call_method1("resolve", ("^c",))?;
+        assert_eq!(info(c, "to")?.as_deref(), Some("hello"));
+
+        let x = mapper.call_method1("resolve", ("+x",))?;
+        assert_eq!(info(x, "from")?.as_deref(), Some("*x"));
+        assert_eq!(info(x, "to")?.as_deref(), Some("y"));
+
+        assert!(mapper.call_method1("resolve", ("z",))?.is_none());
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn unmap() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        assert!(mapper.call_method1("unmap", ("a",))?.extract::<bool>()?);
+        assert!(!mapper.call_method1("unmap", ("a",))?.extract::<bool>()?);
+        assert!(mapper.call_method1("resolve", ("a",))?.is_none());
+
+        reader_send_all(py, m, READER, &keys("a"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "a");
+
+        assert!(mapper.call_method1("unmap", (vec!["g", "s"],))?.extract::<bool>()?);
+        assert_eq!(mapper.call_method0("mappings")?.len()?, 3);
+
+        Ok(())
+    })?;
+    Ok(())
+}
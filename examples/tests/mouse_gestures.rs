@@ -0,0 +1,92 @@
+use crate::*;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+#[pyo3_asyncio::tokio::test]
+async fn gesture() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{btn_right down}{relative X -20}{relative X -20}{relative Y 3}"));
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        reader_send_all(py, m, READER, &keys("{btn_right up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{leftalt down}{left}{leftalt up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn gesture_callback() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{btn_right down}{relative Y 40}{relative X 40}{btn_right up}"));
+        sleep(py, 55);
+        assert_empty!(py, m, WRITER);
+
+        let counter = m.getattr("counter").unwrap().extract::<i32>().unwrap();
+        assert_eq!(counter, 1);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn click() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{btn_right down}{relative X 5}{btn_right up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{btn_right down}{relative X 5}{btn_right up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn unknown_gesture() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        // no gesture is mapped to an upwards stroke, it's replayed as a drag
+        reader_send_all(py, m, READER, &keys("{btn_right down}{relative Y -40}{btn_right up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{btn_right down}{relative Y -40}{btn_right up}");
+
+        // other buttons aren't held back
+        reader_send_all(py, m, READER, &keys("{btn_right down}{btn_left down}{btn_left up}{btn_right up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{btn_left down}{btn_left up}{btn_right down}{btn_right up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn only_key_sequences() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let gestures = m.getattr("gestures")?;
+
+        let err = gestures.call_method1("map", ("left", "{toggle leftalt}")).unwrap_err();
+        assert!(err.to_string().contains("toggles are only supported in the mappings of a Mapper"));
+
+        // the previous mapping is kept
+        reader_send_all(py, m, READER, &keys("{btn_right down}{relative X -40}{btn_right up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{leftalt down}{left}{leftalt up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
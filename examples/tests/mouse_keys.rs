@@ -0,0 +1,113 @@
+use evdev_rs::enums::EventCode;
+
+use crate::*;
+use pyo3::types::IntoPyDict;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+fn rel(axis: evdev_rs::enums::EV_REL, value: i32) -> EvdevInputEvent {
+    EvdevInputEvent::new(&Default::default(), &EventCode::EV_REL(axis), value)
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn motion_while_held() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{i down}"));
+        // long enough for the first step and one more
+        sleep(py, 25);
+        reader_send_all(py, m, READER, &keys("{i up}"));
+        sleep(py, 5);
+        assert_eq!(writer_read_all(py, m, WRITER), vec![rel(REL_Y, -10), rel(REL_Y, -10)]);
+
+        // nothing is emitted after the release
+        sleep(py, 40);
+        assert_empty!(py, m, WRITER);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn diagonal_motion() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{i down}{l down}"));
+        sleep(py, 50);
+        reader_send_all(py, m, READER, &keys("{i up}{l up}"));
+        sleep(py, 5);
+
+        // both axes move by 10 / sqrt(2) per step, so diagonals aren't faster
+        let events = writer_read_all(py, m, WRITER);
+        assert!(events.contains(&rel(REL_X, 7)));
+        assert!(events.contains(&rel(REL_Y, -7)));
+        assert!(!events.contains(&rel(REL_X, 10)));
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn acceleration() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{d down}"));
+        sleep(py, 400);
+        reader_send_all(py, m, READER, &keys("{d up}"));
+        sleep(py, 5);
+
+        let events = writer_read_all(py, m, WRITER);
+        assert_eq!(events.first(), Some(&rel(REL_X, 5)));
+        // capped by max_speed
+        assert_eq!(events.last(), Some(&rel(REL_X, 30)));
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn scroll() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        reader_send_all(py, m, READER, &keys("{j down}"));
+        sleep(py, 5);
+        reader_send_all(py, m, READER, &keys("{j up}"));
+        sleep(py, 5);
+        assert_eq!(writer_read_all(py, m, WRITER), vec![rel(REL_WHEEL, -1)]);
+
+        let info = mapper.call_method1("resolve", ("j",))?;
+        assert_eq!(info.get_item("kind")?.extract::<String>()?, "motion");
+        assert_eq!(info.get_item("axis")?.extract::<String>()?, "wheel");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn invalid_speed() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        for (name, value) in [("speed", f64::INFINITY), ("accel", f64::INFINITY), ("max_speed", f64::NAN)] {
+            let kwargs =
+                [("axis", "x".to_object(py)), ("speed", 5.to_object(py)), (name, value.to_object(py))].into_py_dict(py);
+            let err = mapper.call_method("map_motion", ("u",), Some(kwargs)).unwrap_err();
+            assert!(err.to_string().contains(&format!("'{}' must be a finite number", name)));
+        }
+        assert!(mapper.call_method1("resolve", ("u",))?.is_none());
+
+        Ok(())
+    })?;
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use crate::*;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+#[pyo3_asyncio::tokio::test]
+async fn area() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{absolute X 250}{absolute Y 100}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{absolute X 500}{absolute Y 200}");
+
+        // positions outside of the area stay at its edge
+        reader_send_all(py, m, READER, &keys("{absolute X 800}{absolute Y 0}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{absolute X 1000}{absolute Y 0}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn pressure() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{absolute PRESSURE 500}{absolute PRESSURE 1000}{absolute TILT_X 10}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{absolute PRESSURE 250}{absolute PRESSURE 1000}{absolute TILT_X 10}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn buttons() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{btn_tool_pen down}{btn_stylus down}{btn_stylus up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{btn_tool_pen down}{btn_right down}{btn_right up}");
+
+        // the button is released the same way it was pressed, even after switching the tool
+        reader_send_all(
+            py,
+            m,
+            READER,
+            &keys("{btn_tool_pen up}{btn_tool_rubber down}{btn_stylus down}{btn_tool_rubber up}{btn_stylus up}"),
+        );
+        sleep(py, 5);
+        assert_keys!(
+            py,
+            m,
+            WRITER,
+            "{btn_tool_pen up}{btn_tool_rubber down}{btn_middle down}{btn_tool_rubber up}{btn_middle up}"
+        );
+
+        Ok(())
+    })?;
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use evdev_rs::enums::{EventCode, EV_REL};
+
+use crate::*;
+use pyo3::types::IntoPyDict;
+
+const MOUSE: &str = "mouse";
+const TRACKBALL: &str = "trackball";
+const WRITER: &str = "writer";
+
+fn rel(axis: EV_REL, value: i32) -> EvdevInputEvent {
+    EvdevInputEvent::new(&Default::default(), &EventCode::EV_REL(axis), value)
+}
+
+// a device frame, the motion followed by a sync
+fn frame(input: &str) -> Vec<EvdevInputEvent> {
+    let mut events = keys(input);
+    events.push(SYN_REPORT.clone());
+    events
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn sensitivity_and_acceleration() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        // slow motion is only scaled by the sensitivity
+        reader_send_all(py, m, MOUSE, &frame("{relative X 2}"));
+        // fast motion is accelerated, up to the maximum gain
+        reader_send_all(py, m, MOUSE, &frame("{relative X 12}"));
+        sleep(py, 5);
+        assert_eq!(
+            writer_read_all(py, m, WRITER),
+            vec![rel(REL_X, 1), SYN_REPORT.clone(), rel(REL_X, 18), SYN_REPORT.clone()]
+        );
+
+        // fractions of a unit are carried over
+        reader_send_all(py, m, MOUSE, &frame("{relative Y 1}"));
+        reader_send_all(py, m, MOUSE, &frame("{relative Y 1}"));
+        sleep(py, 5);
+        assert_eq!(writer_read_all(py, m, WRITER), vec![SYN_REPORT.clone(), rel(REL_Y, 1), SYN_REPORT.clone()]);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn rotation() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, TRACKBALL, &frame("{relative X 5}"));
+        reader_send_all(py, m, TRACKBALL, &frame("{relative Y 3}"));
+        sleep(py, 5);
+        assert_eq!(
+            writer_read_all(py, m, WRITER),
+            vec![rel(REL_Y, 5), SYN_REPORT.clone(), rel(REL_X, 3), SYN_REPORT.clone()]
+        );
+
+        // scrolling and buttons pass through
+        reader_send_all(py, m, TRACKBALL, &keys("{relative WHEEL 1}{btn_left}"));
+        sleep(py, 5);
+        assert_eq!(writer_read_all(py, m, WRITER), keys("{relative WHEEL 1}{btn_left}"));
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn configure() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let pointer = m.getattr("trackball_pointer")?;
+
+        pointer.call_method("configure", (), Some([("rotation", 0)].into_py_dict(py)))?;
+        let settings = pointer.call_method0("settings")?;
+        assert_eq!(settings.get_item("rotation")?.extract::<f64>()?, 0.0);
+        assert!(settings.get_item("invert_y")?.extract::<bool>()?);
+
+        reader_send_all(py, m, TRACKBALL, &frame("{relative Y 3}"));
+        sleep(py, 5);
+        assert_eq!(writer_read_all(py, m, WRITER), vec![rel(REL_Y, -3), SYN_REPORT.clone()]);
+
+        let err = pointer.call_method("configure", (), Some([("sensitivity", -1)].into_py_dict(py))).unwrap_err();
+        assert!(err.to_string().contains("'sensitivity' must be a positive number"));
+
+        let err = pointer.call_method("configure", (), Some([("accel", 1)].into_py_dict(py))).unwrap_err();
+        assert!(err.to_string().contains("require the 'adaptive' profile"));
+
+        for (name, value) in [("sensitivity", f64::NAN), ("rotation", f64::INFINITY), ("max_gain", f64::INFINITY)] {
+            let kwargs = [("profile", "adaptive".to_object(py)), (name, value.to_object(py))].into_py_dict(py);
+            let err = pointer.call_method("configure", (), Some(kwargs)).unwrap_err();
+            assert!(err.to_string().contains(&format!("'{}' must be a finite number", name)));
+        }
+        let settings = pointer.call_method0("settings")?;
+        assert_eq!(settings.get_item("rotation")?.extract::<f64>()?, 0.0);
+        assert_eq!(settings.get_item("profile")?.extract::<String>()?, "flat");
+
+        Ok(())
+    })?;
+    Ok(())
+}
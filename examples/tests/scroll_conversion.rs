@@ -0,0 +1,128 @@
+use evdev_rs::enums::{EventCode, EV_REL};
+
+use crate::*;
+use pyo3::types::IntoPyDict;
+
+const MOUSE: &str = "mouse";
+const TRACKBALL: &str = "trackball";
+const WHEEL: &str = "wheel";
+const WRITER: &str = "writer";
+
+fn rel(axis: EV_REL, value: i32) -> EvdevInputEvent {
+    EvdevInputEvent::new(&Default::default(), &EventCode::EV_REL(axis), value)
+}
+
+// a device frame, the scrolling followed by a sync
+fn frame(input: &str) -> Vec<EvdevInputEvent> {
+    let mut events = keys(input);
+    events.push(SYN_REPORT.clone());
+    events
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn hi_res_wheel() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, MOUSE, &frame("{relative WHEEL_HI_RES 60}"));
+        // the regular wheel event of a full detent is redundant
+        reader_send_all(py, m, MOUSE, &frame("{relative WHEEL_HI_RES 120}{relative WHEEL 1}"));
+        sleep(py, 5);
+        assert_eq!(
+            writer_read_all(py, m, WRITER),
+            vec![rel(REL_WHEEL_HI_RES, -30), SYN_REPORT.clone(), rel(REL_WHEEL_HI_RES, -60), SYN_REPORT.clone()]
+        );
+
+        // a detent is sent once the scaled scrolling adds up to one
+        reader_send_all(py, m, MOUSE, &frame("{relative WHEEL_HI_RES 120}{relative WHEEL 1}"));
+        sleep(py, 5);
+        assert_eq!(
+            writer_read_all(py, m, WRITER),
+            vec![rel(REL_WHEEL_HI_RES, -60), rel(REL_WHEEL, -1), SYN_REPORT.clone()]
+        );
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn discrete_wheel() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, TRACKBALL, &frame("{relative WHEEL 1}"));
+        reader_send_all(py, m, TRACKBALL, &frame("{relative HWHEEL -2}"));
+        sleep(py, 5);
+        assert_eq!(
+            writer_read_all(py, m, WRITER),
+            vec![rel(REL_WHEEL_HI_RES, 120), SYN_REPORT.clone(), rel(REL_HWHEEL_HI_RES, -240), SYN_REPORT.clone()]
+        );
+
+        let scroll = m.getattr("trackball_scroll")?;
+        scroll.call_method("configure", (), Some([("output", "discrete")].into_py_dict(py)))?;
+        assert_eq!(scroll.call_method0("settings")?.get_item("output")?.extract::<String>()?, "discrete");
+
+        let err = scroll.call_method("configure", (), Some([("scale", 0)].into_py_dict(py))).unwrap_err();
+        assert!(err.to_string().contains("'scale' must be a positive number"));
+        let err = scroll.call_method("configure", (), Some([("scale", f64::INFINITY)].into_py_dict(py))).unwrap_err();
+        assert!(err.to_string().contains("'scale' must be a positive number"));
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn writer_pairs_regular_wheel_events() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        m.getattr(WRITER)?.call_method1("send", ("{relative WHEEL -1}",))?;
+        sleep(py, 5);
+        assert_eq!(writer_read_all(py, m, WRITER), vec![rel(REL_WHEEL_HI_RES, -120), rel(REL_WHEEL, -1)]);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn writer_pairs_by_frame() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        // the high-resolution event comes after the regular one
+        reader_send_all(py, m, WHEEL, &frame("{relative WHEEL 1}{relative WHEEL_HI_RES 120}"));
+        sleep(py, 5);
+        assert_eq!(
+            writer_read_all(py, m, WRITER),
+            vec![rel(REL_WHEEL_HI_RES, 120), rel(REL_WHEEL, 1), SYN_REPORT.clone()]
+        );
+
+        // other events in between
+        reader_send_all(py, m, WHEEL, &frame("{relative WHEEL_HI_RES 120}{relative X 3}{relative WHEEL 1}"));
+        sleep(py, 5);
+        assert_eq!(
+            writer_read_all(py, m, WRITER),
+            vec![rel(REL_WHEEL_HI_RES, 120), rel(REL_X, 3), rel(REL_WHEEL, 1), SYN_REPORT.clone()]
+        );
+
+        // only the axis without a high-resolution event gets one
+        reader_send_all(py, m, WHEEL, &frame("{relative HWHEEL_HI_RES -120}{relative HWHEEL -1}{relative WHEEL 1}"));
+        sleep(py, 5);
+        assert_eq!(
+            writer_read_all(py, m, WRITER),
+            vec![
+                rel(REL_HWHEEL_HI_RES, -120),
+                rel(REL_HWHEEL, -1),
+                rel(REL_WHEEL_HI_RES, 120),
+                rel(REL_WHEEL, 1),
+                SYN_REPORT.clone()
+            ]
+        );
+
+        Ok(())
+    })?;
+    Ok(())
+}
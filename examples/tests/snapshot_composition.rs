@@ -0,0 +1,74 @@
+use crate::*;
+use pyo3::types::IntoPyDict;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+fn froms(entries: &PyAny) -> PyResult<Vec<String>> {
+    entries.iter()?.map(|entry| entry?.get_item("from")?.extract()).collect()
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn diff() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let base = m.getattr("base")?;
+        let app = m.getattr("app")?;
+
+        let diff = base.call_method1("diff", (app,))?;
+        assert_eq!(froms(diff.get_item("added")?)?, vec!["e"]);
+        assert_eq!(froms(diff.get_item("removed")?)?, vec!["c"]);
+        assert_eq!(froms(diff.get_item("changed")?)?, vec!["a"]);
+        assert_eq!(diff.get_item("changed")?.get_item(0)?.get_item("to")?.extract::<String>()?, "x");
+
+        let same = base.call_method1("diff", (base,))?;
+        assert_eq!(same.get_item("changed")?.len()?, 0);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn merge() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+        let base = m.getattr("base")?;
+        let app = m.getattr("app")?;
+
+        let err = base.call_method1("merge", (app,)).unwrap_err();
+        assert!(err.to_string().contains("conflicting mappings for 'a'"));
+
+        let kwargs = [("on_conflict", "keep")].into_py_dict(py);
+        let merged = base.call_method("merge", (app,), Some(kwargs))?;
+        mapper.call_method1("snapshot", (merged,))?;
+
+        reader_send_all(py, m, READER, &keys("ace"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "bdf");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn overlay() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+        let base = m.getattr("base")?;
+        let app = m.getattr("app")?;
+
+        let overlaid = base.call_method1("overlay", (app,))?;
+        mapper.call_method1("snapshot", (overlaid,))?;
+
+        reader_send_all(py, m, READER, &keys("ace"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "xdf");
+
+        Ok(())
+    })?;
+    Ok(())
+}
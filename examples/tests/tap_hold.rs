@@ -0,0 +1,67 @@
+use crate::*;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+#[pyo3_asyncio::tokio::test]
+async fn multi_tap() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("aa"));
+        sleep(py, 75);
+        assert_keys!(py, m, WRITER, "x");
+
+        // the highest tap count resolves without waiting for the window
+        reader_send_all(py, m, READER, &keys("aaa"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "y");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn incomplete_taps_fall_back() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("a"));
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+        sleep(py, 70);
+        assert_keys!(py, m, WRITER, "a");
+
+        // another key ends the pattern early and keeps the order
+        reader_send_all(py, m, READER, &keys("ac"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "ac");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn long_press() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{b down}"));
+        sleep(py, 75);
+        assert_keys!(py, m, WRITER, "{z down}");
+
+        reader_send_all(py, m, READER, &keys("{b up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{z up}");
+
+        // released before the hold time, emits the original key
+        reader_send_all(py, m, READER, &keys("b"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "b");
+
+        Ok(())
+    })?;
+    Ok(())
+}
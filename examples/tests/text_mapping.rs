@@ -84,3 +84,21 @@ async fn capital_leters() -> PyResult<()> {
     })?;
     Ok(())
 }
+
+#[pyo3_asyncio::tokio::test]
+async fn unmap_multi_byte_trigger() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("accented")?;
+
+        let info = mapper.call_method1("resolve", ("un café",))?;
+        assert_eq!(info.get_item("from")?.extract::<String>()?, "café");
+
+        assert!(mapper.call_method1("unmap", ("café",))?.extract::<bool>()?);
+        assert!(!mapper.call_method1("unmap", ("café",))?.extract::<bool>()?);
+        assert!(mapper.call_method1("resolve", ("un café",))?.is_none());
+
+        Ok(())
+    })?;
+    Ok(())
+}
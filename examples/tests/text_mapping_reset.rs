@@ -0,0 +1,134 @@
+use crate::*;
+use pyo3::types::IntoPyDict;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+const READER_WINDOW: &str = "reader_window";
+const WRITER_WINDOW: &str = "writer_window";
+
+fn expanded(typed: &str) -> String {
+    format!("{}{}bye", typed, "{backspace}".repeat(5))
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn resets_on_navigation_keys() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("hel{left}lo"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "hel{left}lo");
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn resets_on_clicks() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("hel{btn_left}lo"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "hel{btn_left}lo");
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn resets_on_shortcuts() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("hel{ctrl down}v{ctrl up}lo"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "hel{ctrl down}v{ctrl up}lo");
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        // shift doesn't make a shortcut
+        reader_send_all(py, m, READER, &keys("hello"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, &expanded("hello"));
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn resets_on_window_changes() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER_WINDOW, &keys("hel"));
+        sleep(py, 5);
+        set_active_window(py, m, "kitty", "bash");
+        sleep(py, 5);
+        reader_send_all(py, m, READER_WINDOW, &keys("lo"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER_WINDOW, "hello");
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER_WINDOW);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn ignores_window_changes_by_default() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("hel"));
+        sleep(py, 5);
+        set_active_window(py, m, "kitty", "bash");
+        sleep(py, 5);
+        reader_send_all(py, m, READER, &keys("lo"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, &expanded("hello"));
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn keeps_the_text_for_disabled_triggers() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER_WINDOW, &keys("hel{left}lo"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER_WINDOW, &expanded("hel{left}lo"));
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER_WINDOW);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn rejects_unknown_triggers() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let map2 = m.getattr("map2")?;
+        let kwargs = [("reset_on", vec!["scroll"])].into_py_dict(py);
+
+        assert!(map2.getattr("TextMapper")?.call((), Some(kwargs)).is_err());
+
+        Ok(())
+    })?;
+    Ok(())
+}
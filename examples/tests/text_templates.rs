@@ -0,0 +1,167 @@
+use crate::*;
+use pyo3::types::IntoPyDict;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+fn erased(typed: &str) -> String {
+    format!("{}{}", typed, "{backspace}".repeat(typed.chars().count()))
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn substitutes_capture_groups() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("bob@@"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, &format!("{}bob@example.com", erased("bob@@")));
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn passes_capture_groups_to_callbacks() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys(":3x"));
+        sleep(py, 100);
+        assert_keys!(py, m, WRITER, &format!("{}***", erased(":3x")));
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn places_the_cursor() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("bold!"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, &format!("{}<b></b>{}", erased("bold!"), "{left}".repeat(4)));
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn formats_the_date() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let year: String = py.import("time")?.call_method1("strftime", ("%Y",))?.extract()?;
+
+        reader_send_all(py, m, READER, &keys("year!"));
+        sleep(py, 20);
+        assert_keys!(py, m, WRITER, &format!("{}{}", erased("year!"), year));
+
+        reader_send_all(py, m, READER, &keys("usd!"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, &format!("{}$5", erased("usd!")));
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn writes_plain_text_as_is() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("price!"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, &format!("{}$5$ or $date$", erased("price!")));
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn lists_regex_mappings() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        let info = mapper.call_method1("resolve", ("to bob@@",))?;
+        assert_eq!(info.get_item("from")?.extract::<String>()?, r"(\w+)@@");
+        assert!(info.get_item("regex")?.extract::<bool>()?);
+
+        assert!(mapper.call_method1("unmap_regex", (r"(\w+)@@",))?.extract::<bool>()?);
+        assert!(mapper.call_method1("resolve", ("to bob@@",))?.is_none());
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn rejects_invalid_templates() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        let template = [("template", true)].into_py_dict(py);
+        assert!(mapper.call_method("map", ("x!", "$1$"), Some(template)).is_err());
+        assert!(mapper.call_method1("map_regex", (r"(\d+)!", "$2$")).is_err());
+        assert!(mapper.call_method("map", ("x!", "$|$a$|$"), Some(template)).is_err());
+        assert!(mapper.call_method1("map", ("x!", "$1$")).is_ok());
+        assert!(mapper.call_method1("map_regex", (r"(unclosed!", "x")).is_err());
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn keymap_round_trip() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+        let path = m.getattr("keymap_path")?;
+        let before: Vec<&PyAny> = mapper.call_method0("mappings")?.extract()?;
+
+        mapper.call_method0("snapshot")?.call_method1("to_file", (path,))?;
+        mapper.call_method1("snapshot", (m.getattr("empty")?,))?;
+        assert_eq!(mapper.call_method0("mappings")?.len()?, 0);
+        mapper.call_method1("load", (path,))?;
+
+        // everything except user-functions survives the round trip
+        let after: Vec<&PyAny> = mapper.call_method0("mappings")?.extract()?;
+        let expected: Vec<&PyAny> = before
+            .into_iter()
+            .filter(|x| x.get_item("kind").unwrap().extract::<String>().unwrap() != "callback")
+            .collect();
+        assert_eq!(after.len(), expected.len());
+        for (a, b) in after.iter().zip(expected.iter()) {
+            assert!(a.eq(b)?, "{} != {}", a, b);
+        }
+
+        reader_send_all(py, m, READER, &keys("bob@@"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, &format!("{}bob@example.com", erased("bob@@")));
+
+        reader_send_all(py, m, READER, &keys("usd!"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, &format!("{}$5", erased("usd!")));
+
+        reader_send_all(py, m, READER, &keys("bold!"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, &format!("{}<b></b>{}", erased("bold!"), "{left}".repeat(4)));
+
+        reader_send_all(py, m, READER, &keys("price!"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, &format!("{}$5$ or $date$", erased("price!")));
+
+        Ok(())
+    })?;
+    Ok(())
+}
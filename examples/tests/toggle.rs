@@ -0,0 +1,79 @@
+use crate::*;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+#[pyo3_asyncio::tokio::test]
+async fn toggle_latches() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        reader_send_all(py, m, READER, &keys("a"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{btn_left down}");
+        assert!(mapper.call_method1("is_latched", ("btn_left",))?.extract::<bool>()?);
+        assert_eq!(mapper.call_method0("latched")?.extract::<Vec<String>>()?, vec!["btn_left"]);
+
+        reader_send_all(py, m, READER, &keys("a"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{btn_left up}");
+        assert!(!mapper.call_method1("is_latched", ("btn_left",))?.extract::<bool>()?);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn toggle_in_sequence() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("b"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{shift down}x");
+
+        reader_send_all(py, m, READER, &keys("b"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{shift up}x");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn latched_released_on_unlink() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("a"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{btn_left down}");
+
+        m.getattr("mapper")?.call_method0("unlink_to_all")?;
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{btn_left up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn rejected_where_it_cant_run() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let map2 = m.getattr("map2")?;
+
+        let err = m.getattr("mapper")?.call_method1("send", ("{toggle shift}",)).unwrap_err();
+        assert!(err.to_string().contains("toggles are only supported in the mappings of a Mapper"));
+        assert!(m.getattr("writer")?.call_method1("send", ("{toggle shift}",)).is_err());
+        assert!(map2.getattr("TextMapper")?.call0()?.call_method1("map", ("abc", "{toggle shift}")).is_err());
+        assert!(map2.getattr("ChordMapper")?.call0()?.call_method1("map", (vec!["a", "b"], "{toggle shift}")).is_err());
+
+        Ok(())
+    })?;
+    Ok(())
+}
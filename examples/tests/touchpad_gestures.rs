@@ -0,0 +1,100 @@
+use crate::*;
+
+const READER: &str = "reader";
+const WRITER: &str = "keyboard";
+
+// a device frame, the contacts followed by a sync
+fn frame(input: &str) -> Vec<EvdevInputEvent> {
+    let mut events = keys(input);
+    events.push(SYN_REPORT.clone());
+    events
+}
+
+// touches with a finger per slot at the given positions
+fn touch(fingers: &[(i32, i32)]) -> Vec<EvdevInputEvent> {
+    let mut input = String::new();
+    for (slot, (x, y)) in fingers.iter().enumerate() {
+        input += &format!(
+            "{{absolute MT_SLOT {slot}}}{{absolute MT_TRACKING_ID {}}}\
+             {{absolute MT_POSITION_X {x}}}{{absolute MT_POSITION_Y {y}}}",
+            slot + 100
+        );
+    }
+    frame(&input)
+}
+
+fn motion(fingers: &[(i32, i32)]) -> Vec<EvdevInputEvent> {
+    let mut input = String::new();
+    for (slot, (x, y)) in fingers.iter().enumerate() {
+        input += &format!("{{absolute MT_SLOT {slot}}}{{absolute MT_POSITION_X {x}}}{{absolute MT_POSITION_Y {y}}}");
+    }
+    frame(&input)
+}
+
+fn lift(count: usize) -> Vec<EvdevInputEvent> {
+    let mut input = String::new();
+    for slot in 0..count {
+        input += &format!("{{absolute MT_SLOT {slot}}}{{absolute MT_TRACKING_ID -1}}");
+    }
+    frame(&input)
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn swipe() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &touch(&[(1000, 500), (1100, 500), (1200, 500)]));
+        reader_send_all(py, m, READER, &motion(&[(800, 510), (900, 510), (1000, 510)]));
+        reader_send_all(py, m, READER, &motion(&[(600, 520), (700, 520), (800, 520)]));
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        reader_send_all(py, m, READER, &lift(3));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{leftmeta down}{right}{leftmeta up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn pinch() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &touch(&[(900, 500), (1100, 500)]));
+        reader_send_all(py, m, READER, &motion(&[(700, 500), (1300, 500)]));
+        reader_send_all(py, m, READER, &lift(2));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{leftctrl down}{equal}{leftctrl up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn tap() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &touch(&[(1000, 500), (1100, 500), (1200, 500)]));
+        reader_send_all(py, m, READER, &lift(3));
+        sleep(py, 55);
+        assert_empty!(py, m, WRITER);
+
+        let counter = m.getattr("counter").unwrap().extract::<i32>().unwrap();
+        assert_eq!(counter, 1);
+
+        // unmapped gestures are ignored
+        reader_send_all(py, m, READER, &touch(&[(1000, 500), (1100, 500)]));
+        reader_send_all(py, m, READER, &lift(2));
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        Ok(())
+    })?;
+    Ok(())
+}
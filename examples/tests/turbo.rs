@@ -0,0 +1,58 @@
+use crate::*;
+use pyo3::types::IntoPyDict;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+#[pyo3_asyncio::tokio::test]
+async fn turbo_while_held() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{a down}"));
+        sleep(py, 75);
+        reader_send_all(py, m, READER, &keys("{a up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "aa");
+
+        // nothing is emitted after the release
+        sleep(py, 60);
+        assert_empty!(py, m, WRITER);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn turbo_target() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("{b down}"));
+        sleep(py, 25);
+        reader_send_all(py, m, READER, &keys("{b up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "c");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn invalid_rate() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        for rate_hz in [0.0, -1.0, f64::NAN, f64::INFINITY, 1e-320, 1e6] {
+            let kwargs = [("rate_hz", rate_hz)].into_py_dict(py);
+            let err = mapper.call_method("map_turbo", ("d",), Some(kwargs)).unwrap_err();
+            assert!(err.to_string().contains("'rate_hz' must be a positive number of at most 1000"));
+        }
+
+        Ok(())
+    })?;
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use crate::*;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+#[pyo3_asyncio::tokio::test]
+async fn wildcard_passes_modifiers_through() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("a"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "b");
+
+        reader_send_all(py, m, READER, &keys("{shift down}a{shift up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{shift down}b{shift up}");
+
+        reader_send_all(py, m, READER, &keys("{ctrl down}a{ctrl up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{ctrl down}b{ctrl up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn ignored_modifiers() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("c"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "d");
+
+        reader_send_all(py, m, READER, &keys("{shift down}c{shift up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{shift down}d{shift up}");
+
+        // ctrl is not ignored, so the key passes through untouched
+        reader_send_all(py, m, READER, &keys("{ctrl down}c{ctrl up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{ctrl down}c{ctrl up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn specific_mapping_wins() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("d"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "f");
+
+        reader_send_all(py, m, READER, &keys("{alt down}d{alt up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{alt down}f{alt up}");
+
+        // the exact "+d" mapping takes precedence over the wildcard
+        reader_send_all(py, m, READER, &keys("{shift down}d{shift up}"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "{shift down}{shift up}e{shift down}{shift up}");
+
+        Ok(())
+    })?;
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use crate::*;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+#[pyo3_asyncio::tokio::test]
+async fn window_condition() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        set_active_window(py, m, "kitty", "bash");
+        sleep(py, 5);
+        reader_send_all(py, m, READER, &keys("a"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "b");
+
+        set_active_window(py, m, "firefox", "map2 - GitHub");
+        sleep(py, 5);
+        reader_send_all(py, m, READER, &keys("a"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "c");
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn bound_snapshot() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        set_active_window(py, m, "kitty", "notes.md - vim");
+        sleep(py, 5);
+        reader_send_all(py, m, READER, &keys("x"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "z");
+
+        set_active_window(py, m, "kitty", "bash");
+        sleep(py, 5);
+        reader_send_all(py, m, READER, &keys("x"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "x");
+
+        Ok(())
+    })?;
+    Ok(())
+}
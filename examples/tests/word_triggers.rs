@@ -0,0 +1,98 @@
+use crate::*;
+
+const READER: &str = "reader";
+const WRITER: &str = "writer";
+
+fn erased(typed: &str) -> String {
+    let mut output = typed.to_owned();
+    for _ in 0..typed.chars().count() {
+        output.push_str("{backspace}");
+    }
+    output
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn fires_after_a_terminator() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("btw"));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "btw");
+
+        reader_send_all(py, m, READER, &keys(" "));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, &format!(" {}by the way ", "{backspace}".repeat(4)));
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn follows_the_typed_case() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("Btw."));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, &format!("{}By the way.", erased("Btw.")));
+
+        reader_send_all(py, m, READER, &keys("BTW "));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, &format!("{}BY THE WAY ", erased("BTW ")));
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn ignores_triggers_inside_words() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("abtw "));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, "abtw ");
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn rejects_triggers_longer_than_the_window() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+        let mapper = m.getattr("mapper")?;
+
+        assert!(mapper.call_method1("map", ("longer than eight", "x")).is_err());
+        assert!(mapper.call_method1("map", ("eight ch", "x")).is_ok());
+
+        Ok(())
+    })?;
+    Ok(())
+}
+
+#[pyo3_asyncio::tokio::test]
+async fn writes_the_terminator_before_placing_the_cursor() -> PyResult<()> {
+    Python::with_gil(|py| -> PyResult<()> {
+        let m = pytests::include_python!();
+
+        reader_send_all(py, m, READER, &keys("tag "));
+        sleep(py, 5);
+        assert_keys!(py, m, WRITER, &format!("{}<b></b> {}", erased("tag "), "{left}".repeat(5)));
+        sleep(py, 5);
+        assert_empty!(py, m, WRITER);
+
+        Ok(())
+    })?;
+    Ok(())
+}
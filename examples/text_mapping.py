@@ -16,3 +16,7 @@ def increment():
   global counter
   counter += 1
 mapper.map("Something", increment)
+
+# triggers can contain characters of the keyboard layout that take several bytes
+accented = map2.TextMapper(layout="fr")
+accented.map("café", "coffee")
@@ -0,0 +1,20 @@
+import map2
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example-keyboard", "/dev/input/by-id/example-mouse"])
+# the typed text is forgotten on navigation keys, clicks and shortcuts
+mapper = map2.TextMapper()
+writer = map2.Writer(clone_from = "/dev/input/by-id/example-keyboard")
+
+map2.link([reader, mapper, writer])
+
+mapper.map("hello", "bye")
+
+
+reader_window = map2.Reader(patterns=[ "/dev/input/by-id/example-keyboard"])
+# only forgets the typed text when switching windows
+mapper_window = map2.TextMapper(reset_on=["window"])
+writer_window = map2.Writer(clone_from = "/dev/input/by-id/example-keyboard")
+
+map2.link([reader_window, mapper_window, writer_window])
+
+mapper_window.map("hello", "bye")
@@ -0,0 +1,30 @@
+import map2
+import os
+import tempfile
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example"])
+mapper = map2.TextMapper()
+writer = map2.Writer(clone_from = "/dev/input/by-id/example")
+
+map2.link([reader, mapper, writer])
+
+# capture groups can be used in the output
+mapper.map_regex(r"(\w+)@@", "$1$@example.com")
+
+# callbacks receive the capture groups, ":3x" becomes "***"
+def repeat(count):
+  return "*" * int(count)
+mapper.map_regex(r":(\d+)x", repeat)
+
+# the cursor is placed between the tags
+mapper.map("bold!", "<b>$|$</b>", template=True)
+
+mapper.map("year!", "$date:%Y$", template=True)
+
+mapper.map("usd!", "$$5", template=True)
+
+# placeholders are only filled in for templates
+mapper.map("price!", "$5$ or $date$")
+
+keymap_path = os.path.join(tempfile.mkdtemp(), "keymap.toml")
+empty = map2.TextMapper().snapshot()
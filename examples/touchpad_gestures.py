@@ -0,0 +1,26 @@
+'''
+Switch workspaces with 3 finger swipes and zoom by pinching, independently of the compositor.
+The touchpad keeps working as usual through its own writer, the gestures are sent as keyboard shortcuts.
+'''
+
+import map2
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example-touchpad"])
+touchpad = map2.Writer(clone_from = "/dev/input/by-id/example-touchpad")
+gestures = map2.TouchpadMapper(passthrough=False, swipe_distance=300, pinch_distance=150)
+keyboard = map2.Writer(capabilities={"keys": True})
+
+map2.link([reader, touchpad])
+map2.link([reader, gestures, keyboard])
+
+gestures.map("3 finger swipe left", "{leftmeta down}{right}{leftmeta up}")
+gestures.map("3 finger swipe right", "{leftmeta down}{left}{leftmeta up}")
+gestures.map("2 finger pinch out", "{leftctrl down}{equal}{leftctrl up}")
+gestures.map("2 finger pinch in", "{leftctrl down}{minus}{leftctrl up}")
+
+counter = 0
+
+def increment():
+  global counter
+  counter += 1
+gestures.map("3 finger tap", increment)
@@ -1,5 +1,7 @@
 '''
 Move the mouse using the 'w', 'a', 's', 'd' directional keys.
+
+This implements mouse keys in python, `mapper.map_motion` does the same natively, see 'mouse_keys.py'.
 '''
 
 import map2
@@ -0,0 +1,17 @@
+import map2
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example"])
+mapper = map2.Mapper()
+writer = map2.Writer(clone_from = "/dev/input/by-id/example")
+
+map2.link([reader, mapper, writer])
+
+# "a" becomes "b" no matter which modifiers are held, held modifiers are passed through
+mapper.map("*a", "b")
+
+# "c" becomes "d" with or without shift held
+mapper.map("c", "d", ignore=["shift"])
+
+# more specific mappings still win over modifier-agnostic ones
+mapper.map("+d", "e")
+mapper.map("*d", "f")
@@ -0,0 +1,19 @@
+import map2
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example"])
+mapper = map2.Mapper()
+writer = map2.Writer(clone_from = "/dev/input/by-id/example")
+
+map2.link([reader, mapper, writer])
+
+mapper.map("a", "b")
+# only applies while firefox is focused
+mapper.map("a", "c", when={"class": "^firefox$"})
+
+default = mapper.snapshot()
+mapper.map("x", "z")
+editor = mapper.snapshot()
+mapper.snapshot(default)
+
+# replaces all mappings while a window titled "... - vim" is focused
+mapper.bind_snapshot(editor, when={"title": " - vim$"})
@@ -0,0 +1,12 @@
+import map2
+
+reader = map2.Reader(patterns=[ "/dev/input/by-id/example"])
+mapper = map2.TextMapper(case_sensitive=False, word=True, window_size=8)
+writer = map2.Writer(clone_from = "/dev/input/by-id/example")
+
+map2.link([reader, mapper, writer])
+
+mapper.map("btw", "by the way")
+
+# the space typed after the trigger ends up after the closing tag
+mapper.map("tag", "<b>$|$</b>", template=True)
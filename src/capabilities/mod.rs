@@ -58,9 +58,13 @@ pub struct Capabilities {
     #[serde(default)]
     pub rel: bool,
     #[serde(default)]
+    pub hi_res_scroll: bool,
+    #[serde(default)]
     pub abs: Abs,
     #[serde(default)]
     pub keys: bool,
     #[serde(default)]
     pub buttons: bool,
+    #[serde(default)]
+    pub tablet: bool,
 }
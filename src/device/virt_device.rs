@@ -51,7 +51,32 @@ impl DeviceCapabilities {
             ));
         }
     }
+    pub fn enable_hi_res_scroll(&mut self) {
+        for bit in ALL_HI_RES_REL {
+            self.bits.insert(EventCode::EV_REL(*bit));
+        }
+    }
+    pub fn has_hi_res_scroll(&self) -> bool {
+        self.bits.contains(&EventCode::EV_REL(REL_WHEEL_HI_RES))
+    }
+    pub fn enable_tablet(&mut self) {
+        for bit in [BTN_TOOL_PEN, BTN_TOOL_RUBBER, BTN_TOUCH, BTN_STYLUS, BTN_STYLUS2] {
+            self.bits.insert(EventCode::EV_KEY(bit));
+        }
+        // libinput needs a resolution to treat the device as a tablet
+        for (code, info) in [
+            (ABS_X, AbsInfo { value: 0, minimum: 0, maximum: 32767, fuzz: 0, flat: 0, resolution: 100 }),
+            (ABS_Y, AbsInfo { value: 0, minimum: 0, maximum: 32767, fuzz: 0, flat: 0, resolution: 100 }),
+            (ABS_PRESSURE, AbsInfo { value: 0, minimum: 0, maximum: 4095, fuzz: 0, flat: 0, resolution: 0 }),
+            (ABS_TILT_X, AbsInfo { value: 0, minimum: -64, maximum: 63, fuzz: 0, flat: 0, resolution: 57 }),
+            (ABS_TILT_Y, AbsInfo { value: 0, minimum: -64, maximum: 63, fuzz: 0, flat: 0, resolution: 57 }),
+        ] {
+            self.enable_abs(code, info);
+        }
+    }
     pub fn enable_abs(&mut self, code: EV_ABS, info: AbsInfo) {
+        // an axis has a single range, the last one wins
+        self.abs_bits.retain(|(existing, _)| *existing != EventCode::EV_ABS(code));
         self.abs_bits.insert((EventCode::EV_ABS(code), info));
     }
 }
@@ -160,3 +185,17 @@ pub(crate) fn clone_virtual_device(dev: &mut Device, existing_device_fd_path: &s
 
     Ok(())
 }
+
+pub(crate) fn has_hi_res_scroll(existing_device_fd_path: &str) -> Result<bool> {
+    let fd_file = fs::OpenOptions::new().read(true).open(existing_device_fd_path)?;
+    let device = Device::new_from_file(fd_file)?;
+
+    Ok(device.has(&EventCode::EV_REL(REL_WHEEL_HI_RES)))
+}
+
+pub(crate) fn abs_info(existing_device_fd_path: &str, code: EV_ABS) -> Result<Option<AbsInfo>> {
+    let fd_file = fs::OpenOptions::new().read(true).open(existing_device_fd_path)?;
+    let device = Device::new_from_file(fd_file)?;
+
+    Ok(device.abs_info(&EventCode::EV_ABS(code)))
+}
@@ -20,6 +20,17 @@ pub enum DeviceInitPolicy {
     CloneExistingDevice(String),
 }
 
+impl DeviceInitPolicy {
+    pub fn has_hi_res_scroll(&self) -> bool {
+        match self {
+            DeviceInitPolicy::NewDevice(_, capabilities) => capabilities.has_hi_res_scroll(),
+            DeviceInitPolicy::CloneExistingDevice(existing_device_fd_path) => {
+                virt_device::has_hi_res_scroll(existing_device_fd_path).unwrap_or(false)
+            }
+        }
+    }
+}
+
 pub fn init_virtual_output_device(init_policy: &DeviceInitPolicy) -> Result<VirtualOutputDevice> {
     let mut new_device = UninitDevice::new()
         .ok_or(anyhow!("failed to instantiate udev device: libevdev didn't return a device"))?
@@ -1,8 +1,12 @@
+use crate::window::ActiveWindowInfo;
 use crate::xkb_transformer_registry::TransformerParams;
 use crate::*;
 
 lazy_static! {
     pub static ref DEFAULT_TRANSFORMER_PARAMS: RwLock<TransformerParams> = RwLock::new(TransformerParams::default());
+    // the active window as last reported by a window listener
+    pub static ref ACTIVE_WINDOW: tokio::sync::watch::Sender<Option<ActiveWindowInfo>> =
+        tokio::sync::watch::channel(None).0;
 }
 
 #[cfg(feature = "integration")]
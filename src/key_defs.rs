@@ -560,6 +560,11 @@ pub static ALL_REL: &'static [evdev_rs::enums::EV_REL] = &[
     // REL_MAX,
 ];
 
+/// The value of a high-resolution wheel event for one detent of a regular wheel.
+pub const HI_RES_PER_DETENT: i32 = 120;
+
+pub static ALL_HI_RES_REL: &'static [evdev_rs::enums::EV_REL] = &[REL_WHEEL_HI_RES, REL_HWHEEL_HI_RES];
+
 pub static ALL_ABS: &'static [evdev_rs::enums::EV_ABS] = &[
     ABS_X,
     ABS_Y,
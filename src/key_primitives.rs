@@ -25,6 +25,26 @@ impl Key {
     pub fn to_input_ev(&self, state: i32) -> EvdevInputEvent {
         EvdevInputEvent::new(&Default::default(), &self.event_code, state)
     }
+
+    pub fn is_modifier(&self) -> bool {
+        matches!(
+            self.event_code,
+            EventCode::EV_KEY(
+                KEY_LEFTCTRL
+                    | KEY_RIGHTCTRL
+                    | KEY_LEFTALT
+                    | KEY_RIGHTALT
+                    | KEY_LEFTSHIFT
+                    | KEY_RIGHTSHIFT
+                    | KEY_LEFTMETA
+                    | KEY_RIGHTMETA
+            )
+        )
+    }
+
+    pub fn is_button(&self) -> bool {
+        matches!(self.event_code, EventCode::EV_KEY(key) if format!("{key:?}").starts_with("BTN_"))
+    }
 }
 
 impl From<evdev_rs::enums::EV_KEY> for Key {
@@ -65,6 +85,9 @@ impl KeyModifierFlags {
     pub fn new() -> Self {
         KeyModifierFlags { ctrl: false, shift: false, alt: false, right_alt: false, meta: false }
     }
+    pub fn all() -> Self {
+        KeyModifierFlags { ctrl: true, shift: true, alt: true, right_alt: true, meta: true }
+    }
     pub fn ctrl(&mut self) {
         self.ctrl = true;
     }
@@ -97,6 +120,22 @@ impl KeyModifierFlags {
             self.meta();
         }
     }
+    pub fn is_empty(&self) -> bool {
+        !self.ctrl && !self.shift && !self.alt && !self.right_alt && !self.meta
+    }
+    pub fn count(&self) -> usize {
+        [self.ctrl, self.shift, self.alt, self.right_alt, self.meta].iter().filter(|&&x| x).count()
+    }
+    /// Returns a copy with all flags that are set in `mask` cleared.
+    pub fn without(&self, mask: &KeyModifierFlags) -> Self {
+        KeyModifierFlags {
+            ctrl: self.ctrl && !mask.ctrl,
+            shift: self.shift && !mask.shift,
+            alt: self.alt && !mask.alt,
+            right_alt: self.right_alt && !mask.right_alt,
+            meta: self.meta && !mask.meta,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
@@ -0,0 +1,365 @@
+use super::*;
+use crate::capabilities::AbsInfo;
+use crate::device::virt_device;
+use crate::python::*;
+use crate::*;
+use evdev_rs::enums::EV_ABS;
+use pythonize::depythonize;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex;
+
+use ApplicationError::TooManyEvents;
+
+struct State {
+    prev: HashMap<Uuid, Arc<dyn LinkSrc>>,
+    next: HashMap<Uuid, Arc<dyn LinkDst>>,
+    mode: Mode,
+    sensitivity: f64,
+    // the active area of the absolute axes
+    x: AxisArea,
+    y: AxisArea,
+    // abs to rel: the last position of the contact, unknown until it touches
+    last: [Option<f64>; 2],
+    remainders: [f64; 2],
+    // rel to abs: the cursor position and the last values sent
+    position: [f64; 2],
+    sent: [Option<i32>; 2],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    // absolute positions move the pointer, like a trackpad
+    AbsToRel,
+    // relative motion moves a bounded absolute cursor
+    RelToAbs,
+}
+
+/// The part of an absolute axis that's used, in device units.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) struct AxisArea {
+    pub(super) min: f64,
+    pub(super) max: f64,
+}
+
+impl AxisArea {
+    pub(super) fn len(&self) -> f64 {
+        self.max - self.min
+    }
+
+    pub(super) fn center(&self) -> f64 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub(super) fn clamp(&self, value: f64) -> f64 {
+        value.clamp(self.min, self.max)
+    }
+
+    // shrinks the area around its center
+    fn crop(&self, len: f64) -> Self {
+        AxisArea { min: self.center() - len / 2.0, max: self.center() + len / 2.0 }
+    }
+
+    /// Maps a value within this area to the same relative position within another area.
+    pub(super) fn rescale(&self, value: f64, to: &AxisArea) -> f64 {
+        to.min + (self.clamp(value) - self.min) / self.len() * to.len()
+    }
+}
+
+// the range of an absolute axis, given as an option or read from the device
+pub(super) fn axis_range(options: &HashMap<&str, &PyAny>, name: &str, axis: EV_ABS) -> PyResult<AxisArea> {
+    let (min, max) = match (options.get(name), options.get("device")) {
+        (Some(range), _) => {
+            let info: AbsInfo = depythonize(range).map_err(|_| {
+                PyRuntimeError::new_err(format!("'{}' must be of type '{{ min: int, max: int }}'", name))
+            })?;
+            (info.minimum, info.maximum)
+        }
+        (None, Some(device)) => {
+            let device =
+                device.extract::<String>().map_err(|_| PyRuntimeError::new_err("'device' must be of type 'string'"))?;
+            let info = virt_device::abs_info(&device, axis)
+                .map_err(|err| PyRuntimeError::new_err(format!("failed to read the device '{}': {}", device, err)))?
+                .ok_or_else(|| PyRuntimeError::new_err(format!("the device '{}' has no axis '{:?}'", device, axis)))?;
+            (info.minimum, info.maximum)
+        }
+        (None, None) => return Err(PyRuntimeError::new_err(format!("'{}' or 'device' is required", name))),
+    };
+    if min >= max {
+        return Err(PyRuntimeError::new_err(format!("'{}' must have 'min' lower than 'max'", name)));
+    }
+    Ok(AxisArea { min: min as f64, max: max as f64 })
+}
+
+// the active area within the axis ranges, cropped to the given fractions and aspect ratio
+pub(super) fn active_area(options: &HashMap<&str, &PyAny>, x: AxisArea, y: AxisArea) -> PyResult<(AxisArea, AxisArea)> {
+    let (mut x, mut y) = (x, y);
+    if let Some(area) = options.get("area") {
+        let area: HashMap<String, f64> = area.extract().map_err(|_| {
+            PyRuntimeError::new_err(
+                "'area' must be of type '{ left: number, top: number, right: number, bottom: number }'",
+            )
+        })?;
+        let edge = |name: &str, default: f64| -> PyResult<f64> {
+            match area.get(name) {
+                Some(value) if (0.0..=1.0).contains(value) => Ok(*value),
+                Some(_) => Err(PyRuntimeError::new_err(format!("'area.{}' must be within [0, 1]", name))),
+                None => Ok(default),
+            }
+        };
+        let (left, top, right, bottom) =
+            (edge("left", 0.0)?, edge("top", 0.0)?, edge("right", 1.0)?, edge("bottom", 1.0)?);
+        if left >= right || top >= bottom {
+            return Err(PyRuntimeError::new_err(
+                "'area' must have 'left' lower than 'right' and 'top' lower than 'bottom'",
+            ));
+        }
+        x = AxisArea { min: x.min + left * x.len(), max: x.min + right * x.len() };
+        y = AxisArea { min: y.min + top * y.len(), max: y.min + bottom * y.len() };
+    }
+
+    if let Some(aspect) = options.get("aspect") {
+        let aspect = match aspect.extract::<f64>() {
+            Ok(aspect) if aspect > 0.0 && aspect.is_finite() => aspect,
+            _ => return Err(PyRuntimeError::new_err("'aspect' must be a positive number")),
+        };
+        if x.len() / y.len() > aspect {
+            x = x.crop(y.len() * aspect);
+        } else {
+            y = y.crop(x.len() / aspect);
+        }
+    }
+    Ok((x, y))
+}
+
+#[pyclass]
+pub struct AbsRelMapper {
+    pub id: Uuid,
+    pub link: Arc<AbsRelMapperLink>,
+    ev_tx: tokio::sync::mpsc::Sender<InputEvent>,
+    state: Arc<Mutex<State>>,
+}
+
+#[pymethods]
+impl AbsRelMapper {
+    #[new]
+    #[pyo3(signature = (**kwargs))]
+    pub fn new(kwargs: Option<&PyDict>) -> PyResult<Self> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let mode = match options.get("mode").map(|mode| mode.extract::<String>()) {
+            None => Mode::AbsToRel,
+            Some(Ok(mode)) if mode == "abs_to_rel" => Mode::AbsToRel,
+            Some(Ok(mode)) if mode == "rel_to_abs" => Mode::RelToAbs,
+            Some(_) => return Err(PyRuntimeError::new_err("'mode' must be one of: 'abs_to_rel', 'rel_to_abs'")),
+        };
+        let sensitivity = match options.get("sensitivity") {
+            Some(sensitivity) => match sensitivity.extract::<f64>() {
+                Ok(sensitivity) if sensitivity > 0.0 && sensitivity.is_finite() => sensitivity,
+                _ => return Err(PyRuntimeError::new_err("'sensitivity' must be a positive number")),
+            },
+            None => 1.0,
+        };
+        let x = axis_range(&options, "x_range", ABS_X)?;
+        let y = axis_range(&options, "y_range", ABS_Y)?;
+        let (x, y) = active_area(&options, x, y)?;
+
+        let id = Uuid::new_v4();
+        let (ev_tx, mut ev_rx) = tokio::sync::mpsc::channel(64);
+        let state = Arc::new(Mutex::new(State {
+            prev: Default::default(),
+            next: Default::default(),
+            mode,
+            sensitivity,
+            x,
+            y,
+            last: [None; 2],
+            remainders: [0.0; 2],
+            // the cursor starts in the middle
+            position: [x.center(), y.center()],
+            sent: [None; 2],
+        }));
+        let link = Arc::new(AbsRelMapperLink { id, ev_tx: ev_tx.clone(), state: state.clone() });
+
+        {
+            let state = state.clone();
+            get_runtime().spawn(async move {
+                loop {
+                    let ev = ev_rx.recv().await;
+                    match ev {
+                        Some(ev) => handle(state.clone(), ev).await,
+                        None => return,
+                    }
+                }
+            });
+        }
+
+        Ok(Self { id, link, ev_tx, state })
+    }
+
+    pub fn link_to(&mut self, target: &PyAny) -> PyResult<()> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.link_from(self.link.clone());
+        self.link.link_to(target);
+        Ok(())
+    }
+
+    pub fn unlink_to(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.unlink_from(&self.id);
+        self.link.unlink_to(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_to_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.next.values_mut() {
+            l.unlink_from(&self.id);
+        }
+        state.next.clear();
+    }
+
+    pub fn unlink_from(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_src(target).ok_or_else(|| PyRuntimeError::new_err("expected a source node"))?;
+        target.unlink_to(&self.id);
+        self.link.unlink_from(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_from_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.prev.values_mut() {
+            l.unlink_to(&self.id);
+        }
+        state.prev.clear();
+    }
+
+    pub fn unlink_all(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+
+    pub fn send(&mut self, val: String) -> PyResult<()> {
+        let actions = parse_key_sequence_without_toggles(val.as_str(), None)
+            .map_err(|err| ApplicationError::KeySequenceParse(err.to_string()).into_py())?
+            .to_key_actions();
+        for action in actions {
+            self.ev_tx.try_send(InputEvent::Raw(action.to_input_ev())).expect(&TooManyEvents.to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for AbsRelMapper {
+    fn drop(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+}
+
+#[derive(Clone)]
+pub struct AbsRelMapperLink {
+    id: Uuid,
+    ev_tx: Sender<InputEvent>,
+    state: Arc<Mutex<State>>,
+}
+
+impl LinkSrc for AbsRelMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_to(&self, node: Arc<dyn LinkDst>) -> Result<()> {
+        self.state.blocking_lock().next.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_to(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().next.remove(id).is_some())
+    }
+}
+
+impl LinkDst for AbsRelMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_from(&self, node: Arc<dyn LinkSrc>) -> Result<()> {
+        self.state.blocking_lock().prev.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_from(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().prev.remove(id).is_some())
+    }
+    fn send(&self, ev: InputEvent) -> Result<()> {
+        self.ev_tx.try_send(ev).map_err(|err| ApplicationError::TooManyEvents.into_py())?;
+        Ok(())
+    }
+}
+
+impl State {
+    fn area(&self, idx: usize) -> AxisArea {
+        if idx == 0 {
+            self.x
+        } else {
+            self.y
+        }
+    }
+
+    // moves the pointer by the change of an absolute position
+    fn abs_to_rel(&mut self, idx: usize, ev: &EvdevInputEvent) {
+        let position = self.area(idx).clamp(ev.value as f64);
+        let last = self.last[idx].replace(position);
+        let last = match last {
+            Some(last) => last,
+            // a new contact, the pointer doesn't jump to it
+            None => return,
+        };
+
+        let value = (position - last) * self.sensitivity + self.remainders[idx];
+        self.remainders[idx] = value.fract();
+        if value.trunc() != 0.0 {
+            let axis = if idx == 0 { REL_X } else { REL_Y };
+            self.next.send_all(InputEvent::Raw(EvdevInputEvent {
+                event_code: EventCode::EV_REL(axis),
+                value: value.trunc() as i32,
+                time: ev.time,
+            }));
+        }
+    }
+
+    // moves the absolute cursor, it stays within the active area
+    fn rel_to_abs(&mut self, idx: usize, ev: &EvdevInputEvent) {
+        self.position[idx] = self.area(idx).clamp(self.position[idx] + ev.value as f64 * self.sensitivity);
+        let value = self.position[idx].round() as i32;
+        if self.sent[idx] == Some(value) {
+            return;
+        }
+        self.sent[idx] = Some(value);
+        let axis = if idx == 0 { ABS_X } else { ABS_Y };
+        self.next.send_all(InputEvent::Raw(EvdevInputEvent {
+            event_code: EventCode::EV_ABS(axis),
+            value,
+            time: ev.time,
+        }));
+    }
+}
+
+async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
+    let mut state = _state.lock().await;
+
+    let ev = match &raw_ev {
+        InputEvent::Raw(ev) => ev,
+    };
+
+    match (state.mode, ev.event_code) {
+        (Mode::AbsToRel, EventCode::EV_ABS(ABS_X)) => state.abs_to_rel(0, ev),
+        (Mode::AbsToRel, EventCode::EV_ABS(ABS_Y)) => state.abs_to_rel(1, ev),
+        // lifting the finger or pen, the next contact starts over
+        (Mode::AbsToRel, EventCode::EV_KEY(BTN_TOUCH | BTN_TOOL_PEN | BTN_TOOL_FINGER)) if ev.value == 0 => {
+            state.last = [None; 2];
+            state.remainders = [0.0; 2];
+            state.next.send_all(raw_ev);
+        }
+        (Mode::RelToAbs, EventCode::EV_REL(REL_X)) => state.rel_to_abs(0, ev),
+        (Mode::RelToAbs, EventCode::EV_REL(REL_Y)) => state.rel_to_abs(1, ev),
+        _ => state.next.send_all(raw_ev),
+    }
+}
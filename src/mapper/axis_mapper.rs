@@ -0,0 +1,776 @@
+use super::*;
+use crate::capabilities::AbsInfo;
+use crate::python::*;
+use crate::xkb::XKBTransformer;
+use crate::xkb_transformer_registry::{TransformerParams, XKB_TRANSFORMER_REGISTRY};
+use crate::*;
+use evdev_rs::enums::EV_ABS;
+use evdev_rs::TimeVal;
+use pythonize::depythonize;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex;
+
+use ApplicationError::TooManyEvents;
+
+#[derive(Default)]
+struct State {
+    transformer: Arc<XKBTransformer>,
+    prev: HashMap<Uuid, Arc<dyn LinkSrc>>,
+    next: HashMap<Uuid, Arc<dyn LinkDst>>,
+    axes: HashMap<EV_ABS, AxisTransform>,
+    sticks: Vec<Stick>,
+    axis_keys: HashMap<EV_ABS, AxisKeys>,
+    // the key each axis mapped to keys currently holds down
+    pressed: HashMap<EV_ABS, Key>,
+    keys_axes: HashMap<EV_ABS, KeysAxis>,
+    // keys that currently push an axis
+    held: HashSet<Key>,
+    // the last raw value of every axis, sticks need both to process either
+    values: HashMap<EV_ABS, i32>,
+    // the last value sent for every axis, values that didn't change aren't sent again
+    sent: HashMap<EV_ABS, i32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct AxisRange {
+    min: i32,
+    max: i32,
+}
+
+impl AxisRange {
+    fn from_py(value: &PyAny, name: &str) -> PyResult<Self> {
+        let info: AbsInfo = depythonize(value)
+            .map_err(|_| PyRuntimeError::new_err(format!("'{}' must be of type '{{ min: int, max: int }}'", name)))?;
+        if info.minimum >= info.maximum {
+            return Err(PyRuntimeError::new_err(format!("'{}' must have 'min' lower than 'max'", name)));
+        }
+        Ok(AxisRange { min: info.minimum, max: info.maximum })
+    }
+
+    // maps a raw value to [-1, 1] for centered axes and to [0, 1] for the others
+    fn normalize(&self, value: i32, centered: bool) -> f64 {
+        let (min, max) = (self.min as f64, self.max as f64);
+        let value = ((value as f64 - min) / (max - min)).clamp(0.0, 1.0);
+        if centered {
+            value * 2.0 - 1.0
+        } else {
+            value
+        }
+    }
+
+    fn denormalize(&self, value: f64, centered: bool) -> i32 {
+        let value = if centered { (value + 1.0) / 2.0 } else { value };
+        let (min, max) = (self.min as f64, self.max as f64);
+        (min + value * (max - min)).round() as i32
+    }
+
+    fn to_py_dict<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let dict = PyDict::new(py);
+        dict.set_item("min", self.min)?;
+        dict.set_item("max", self.max)?;
+        Ok(dict)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(super) enum Curve {
+    // the magnitude raised to a power, 1 is linear
+    Exponent(f64),
+    // (input, output) points, linearly interpolated in between
+    Points(Vec<(f64, f64)>),
+}
+
+impl Curve {
+    pub(super) fn from_py(value: &PyAny, name: &str) -> PyResult<Self> {
+        if let Ok(exponent) = value.extract::<f64>() {
+            if exponent <= 0.0 {
+                return Err(PyRuntimeError::new_err(format!("'{}' must be positive", name)));
+            }
+            return Ok(Curve::Exponent(exponent));
+        }
+
+        let points = value.extract::<Vec<(f64, f64)>>().map_err(|_| {
+            PyRuntimeError::new_err(format!("'{}' must be of type 'number' or '[number, number][]'", name))
+        })?;
+        if points.is_empty() {
+            return Err(PyRuntimeError::new_err(format!("'{}' must contain at least one point", name)));
+        }
+        if points.iter().any(|(x, y)| !(0.0..=1.0).contains(x) || !(0.0..=1.0).contains(y)) {
+            return Err(PyRuntimeError::new_err(format!("'{}' points must be within [0, 1]", name)));
+        }
+        if points.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Err(PyRuntimeError::new_err(format!("'{}' points must be ordered by their input", name)));
+        }
+        Ok(Curve::Points(points))
+    }
+
+    pub(super) fn to_object(&self, py: Python) -> PyObject {
+        match self {
+            Curve::Exponent(exponent) => exponent.to_object(py),
+            Curve::Points(points) => points.to_object(py),
+        }
+    }
+
+    pub(super) fn apply(&self, magnitude: f64) -> f64 {
+        match self {
+            Curve::Exponent(exponent) => magnitude.powf(*exponent),
+            Curve::Points(points) => {
+                let pos = points.iter().position(|(x, _)| *x >= magnitude);
+                match pos {
+                    // outside of the points the nearest one is used
+                    Some(0) => points[0].1,
+                    None => points[points.len() - 1].1,
+                    Some(pos) => {
+                        let ((x0, y0), (x1, y1)) = (points[pos - 1], points[pos]);
+                        y0 + (y1 - y0) * (magnitude - x0) / (x1 - x0)
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct AxisTransform {
+    input: AxisRange,
+    output: AxisRange,
+    // centered axes rest in the middle of their range (sticks), others at the minimum (pedals, triggers)
+    centered: bool,
+    deadzone: f64,
+    curve: Curve,
+    invert: bool,
+}
+
+impl Default for AxisTransform {
+    fn default() -> Self {
+        let range = AxisRange { min: 0, max: 255 };
+        Self { input: range, output: range, centered: true, deadzone: 0.0, curve: Curve::Exponent(1.0), invert: false }
+    }
+}
+
+impl AxisTransform {
+    fn from_options(options: &HashMap<&str, &PyAny>) -> PyResult<Self> {
+        let mut transform = AxisTransform::default();
+        if let Some(input) = options.get("input_range") {
+            transform.input = AxisRange::from_py(input, "input_range")?;
+        }
+        transform.output = match options.get("output_range") {
+            Some(output) => AxisRange::from_py(output, "output_range")?,
+            None => transform.input,
+        };
+        if let Some(centered) = options.get("centered") {
+            transform.centered =
+                centered.extract().map_err(|_| PyRuntimeError::new_err("'centered' must be of type 'bool'"))?;
+        }
+        if let Some(deadzone) = options.get("deadzone") {
+            transform.deadzone = parse_deadzone(deadzone)?;
+        }
+        if let Some(curve) = options.get("curve") {
+            transform.curve = Curve::from_py(curve, "curve")?;
+        }
+        if let Some(invert) = options.get("invert") {
+            transform.invert =
+                invert.extract().map_err(|_| PyRuntimeError::new_err("'invert' must be of type 'bool'"))?;
+        }
+        Ok(transform)
+    }
+
+    // the raw value an axis rests at before it reports one
+    fn rest_value(&self) -> i32 {
+        if self.centered {
+            (self.input.min + self.input.max) / 2
+        } else {
+            self.input.min
+        }
+    }
+
+    fn normalize(&self, value: i32) -> f64 {
+        let value = self.input.normalize(value, self.centered);
+        match (self.invert, self.centered) {
+            (false, _) => value,
+            (true, true) => -value,
+            (true, false) => 1.0 - value,
+        }
+    }
+
+    // applies the deadzone and the curve, the remaining range is stretched so there's no jump at the deadzone's edge
+    fn shape(&self, value: f64) -> f64 {
+        let magnitude = value.abs();
+        if magnitude < self.deadzone {
+            return 0.0;
+        }
+        let magnitude = ((magnitude - self.deadzone) / (1.0 - self.deadzone)).min(1.0);
+        self.curve.apply(magnitude).copysign(value)
+    }
+
+    fn denormalize(&self, value: f64) -> i32 {
+        self.output.denormalize(value, self.centered)
+    }
+
+    fn describe<'py>(&self, py: Python<'py>, info: &'py PyDict) -> PyResult<()> {
+        info.set_item("input_range", self.input.to_py_dict(py)?)?;
+        info.set_item("output_range", self.output.to_py_dict(py)?)?;
+        info.set_item("centered", self.centered)?;
+        info.set_item("deadzone", self.deadzone)?;
+        info.set_item("curve", self.curve.to_object(py))?;
+        info.set_item("invert", self.invert)?;
+        Ok(())
+    }
+}
+
+// two centered axes sharing a radial deadzone
+#[derive(Clone, Debug, PartialEq)]
+struct Stick {
+    x: EV_ABS,
+    y: EV_ABS,
+    deadzone: f64,
+}
+
+// an axis holding down a key for each direction once it's pushed far enough
+#[derive(Clone, Debug, PartialEq)]
+struct AxisKeys {
+    input: AxisRange,
+    centered: bool,
+    negative: Option<Key>,
+    positive: Option<Key>,
+    threshold: f64,
+    // how far below the threshold the axis has to return to release a key again
+    hysteresis: f64,
+}
+
+impl AxisKeys {
+    fn key(&self, value: i32, pressed: Option<&Key>) -> Option<Key> {
+        let value = self.input.normalize(value, self.centered);
+        let (key, magnitude) = if value < 0.0 { (self.negative, -value) } else { (self.positive, value) };
+        let threshold = match key {
+            Some(key) if pressed == Some(&key) => self.threshold - self.hysteresis,
+            _ => self.threshold,
+        };
+        key.filter(|_| magnitude >= threshold)
+    }
+}
+
+// keys pushing an axis all the way in their direction, or by `strength`
+#[derive(Clone, Debug, PartialEq)]
+struct KeysAxis {
+    output: AxisRange,
+    centered: bool,
+    negative: Option<Key>,
+    positive: Option<Key>,
+    strength: f64,
+}
+
+impl KeysAxis {
+    // holding both keys or none keeps the axis at rest
+    fn value(&self, held: &HashSet<Key>) -> i32 {
+        let is_held = |key: &Option<Key>| key.map_or(false, |key| held.contains(&key));
+        let value = match (is_held(&self.negative), is_held(&self.positive)) {
+            (true, false) => -self.strength,
+            (false, true) => self.strength,
+            _ => 0.0,
+        };
+        self.output.denormalize(value, self.centered)
+    }
+
+    fn contains(&self, key: &Key) -> bool {
+        self.negative.as_ref() == Some(key) || self.positive.as_ref() == Some(key)
+    }
+}
+
+#[pyclass]
+pub struct AxisMapper {
+    pub id: Uuid,
+    pub link: Arc<AxisMapperLink>,
+    ev_tx: tokio::sync::mpsc::Sender<InputEvent>,
+    state: Arc<Mutex<State>>,
+}
+
+#[pymethods]
+impl AxisMapper {
+    #[new]
+    #[pyo3(signature = (**kwargs))]
+    pub fn new(kwargs: Option<&PyDict>) -> PyResult<Self> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let kbd_model = options.get("model").and_then(|x| x.extract().ok());
+        let kbd_layout = options.get("layout").and_then(|x| x.extract().ok());
+        let kbd_variant = options.get("variant").and_then(|x| x.extract().ok());
+        let kbd_options = options.get("options").and_then(|x| x.extract().ok());
+        let transformer = XKB_TRANSFORMER_REGISTRY
+            .get(&TransformerParams::new(kbd_model, kbd_layout, kbd_variant, kbd_options))
+            .map_err(err_to_py)?;
+
+        let id = Uuid::new_v4();
+        let (ev_tx, mut ev_rx) = tokio::sync::mpsc::channel(64);
+        let state = Arc::new(Mutex::new(State { transformer, ..Default::default() }));
+        let link = Arc::new(AxisMapperLink { id, ev_tx: ev_tx.clone(), state: state.clone() });
+
+        {
+            let state = state.clone();
+            get_runtime().spawn(async move {
+                loop {
+                    let ev = ev_rx.recv().await;
+                    match ev {
+                        Some(ev) => handle(state.clone(), ev).await,
+                        None => return,
+                    }
+                }
+            });
+        }
+
+        Ok(Self { id, link, ev_tx, state })
+    }
+
+    #[pyo3(signature = (axis, **kwargs))]
+    pub fn map(&mut self, axis: String, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let axis = parse_axis(&axis)?;
+        let transform = AxisTransform::from_options(&options)?;
+        let mut state = self.state.blocking_lock();
+        state.remove_axis_keys(&axis);
+        state.axes.insert(axis, transform);
+        state.sent.remove(&axis);
+        Ok(())
+    }
+
+    #[pyo3(signature = (x, y, **kwargs))]
+    pub fn map_stick(&mut self, x: String, y: String, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let (x, y) = (parse_axis(&x)?, parse_axis(&y)?);
+        if x == y {
+            return Err(PyRuntimeError::new_err("a stick needs 2 different axes"));
+        }
+        let deadzone = match options.get("deadzone") {
+            Some(deadzone) => parse_deadzone(deadzone)?,
+            None => 0.0,
+        };
+
+        let mut state = self.state.blocking_lock();
+        state.remove_axis_keys(&x);
+        state.remove_axis_keys(&y);
+        state.sticks.retain(|stick| ![stick.x, stick.y].iter().any(|axis| *axis == x || *axis == y));
+        state.sticks.push(Stick { x, y, deadzone });
+        Ok(())
+    }
+
+    #[pyo3(signature = (axis, **kwargs))]
+    pub fn map_axis_to_keys(&mut self, axis: String, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let axis = parse_axis(&axis)?;
+        let mut state = self.state.blocking_lock();
+        let (negative, positive) = parse_direction_keys(&options, &state.transformer)?;
+        let input = match options.get("input_range") {
+            Some(input) => AxisRange::from_py(input, "input_range")?,
+            None => AxisTransform::default().input,
+        };
+        let centered = parse_centered(&options, negative.is_some())?;
+        let threshold = match options.get("threshold") {
+            Some(threshold) => match threshold.extract::<f64>() {
+                Ok(threshold) if threshold > 0.0 && threshold <= 1.0 => threshold,
+                _ => return Err(PyRuntimeError::new_err("'threshold' must be a number within (0, 1]")),
+            },
+            None => 0.5,
+        };
+        let hysteresis = match options.get("hysteresis") {
+            Some(hysteresis) => match hysteresis.extract::<f64>() {
+                Ok(hysteresis) if hysteresis >= 0.0 && hysteresis < threshold => hysteresis,
+                _ => return Err(PyRuntimeError::new_err("'hysteresis' must be a number within [0, threshold)")),
+            },
+            None => (threshold / 5.0).min(0.1),
+        };
+
+        state.remove_axis(&axis);
+        state.axis_keys.insert(axis, AxisKeys { input, centered, negative, positive, threshold, hysteresis });
+        Ok(())
+    }
+
+    #[pyo3(signature = (axis, **kwargs))]
+    pub fn map_keys_to_axis(&mut self, axis: String, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let axis = parse_axis(&axis)?;
+        let mut state = self.state.blocking_lock();
+        let (negative, positive) = parse_direction_keys(&options, &state.transformer)?;
+        let output = match options.get("output_range") {
+            Some(output) => AxisRange::from_py(output, "output_range")?,
+            None => AxisTransform::default().output,
+        };
+        let centered = parse_centered(&options, negative.is_some())?;
+        let strength = match options.get("strength") {
+            Some(strength) => match strength.extract::<f64>() {
+                Ok(strength) if strength > 0.0 && strength <= 1.0 => strength,
+                _ => return Err(PyRuntimeError::new_err("'strength' must be a number within (0, 1]")),
+            },
+            None => 1.0,
+        };
+
+        state.keys_axes.insert(axis, KeysAxis { output, centered, negative, positive, strength });
+        Ok(())
+    }
+
+    pub fn unmap(&mut self, axis: String) -> PyResult<bool> {
+        let axis = parse_axis(&axis)?;
+        let mut state = self.state.blocking_lock();
+        let mut removed = state.remove_axis(&axis);
+        removed |= state.keys_axes.remove(&axis).is_some();
+        state.sent.remove(&axis);
+        Ok(removed)
+    }
+
+    pub fn mappings(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let state = self.state.blocking_lock();
+        let mut acc: Vec<(String, &PyDict)> = vec![];
+        for (axis, transform) in state.axes.iter() {
+            let info = PyDict::new(py);
+            info.set_item("kind", "axis")?;
+            info.set_item("axis", axis_name(axis))?;
+            transform.describe(py, info)?;
+            acc.push((axis_name(axis), info));
+        }
+        for stick in state.sticks.iter() {
+            let info = PyDict::new(py);
+            info.set_item("kind", "stick")?;
+            info.set_item("axes", vec![axis_name(&stick.x), axis_name(&stick.y)])?;
+            info.set_item("deadzone", stick.deadzone)?;
+            acc.push((axis_name(&stick.x), info));
+        }
+        for (axis, mapping) in state.axis_keys.iter() {
+            let info = PyDict::new(py);
+            info.set_item("kind", "axis_to_keys")?;
+            info.set_item("axis", axis_name(axis))?;
+            info.set_item("negative", mapping.negative.as_ref().map(key_name))?;
+            info.set_item("positive", mapping.positive.as_ref().map(key_name))?;
+            info.set_item("input_range", mapping.input.to_py_dict(py)?)?;
+            info.set_item("centered", mapping.centered)?;
+            info.set_item("threshold", mapping.threshold)?;
+            info.set_item("hysteresis", mapping.hysteresis)?;
+            acc.push((axis_name(axis), info));
+        }
+        for (axis, mapping) in state.keys_axes.iter() {
+            let info = PyDict::new(py);
+            info.set_item("kind", "keys_to_axis")?;
+            info.set_item("axis", axis_name(axis))?;
+            info.set_item("negative", mapping.negative.as_ref().map(key_name))?;
+            info.set_item("positive", mapping.positive.as_ref().map(key_name))?;
+            info.set_item("output_range", mapping.output.to_py_dict(py)?)?;
+            info.set_item("centered", mapping.centered)?;
+            info.set_item("strength", mapping.strength)?;
+            acc.push((axis_name(axis), info));
+        }
+
+        acc.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(acc.into_iter().map(|(_, info)| info.to_object(py)).collect())
+    }
+
+    pub fn link_to(&mut self, target: &PyAny) -> PyResult<()> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.link_from(self.link.clone());
+        self.link.link_to(target);
+        Ok(())
+    }
+
+    pub fn unlink_to(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.unlink_from(&self.id);
+        self.link.unlink_to(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_to_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.next.values_mut() {
+            l.unlink_from(&self.id);
+        }
+        state.next.clear();
+    }
+
+    pub fn unlink_from(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_src(target).ok_or_else(|| PyRuntimeError::new_err("expected a source node"))?;
+        target.unlink_to(&self.id);
+        self.link.unlink_from(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_from_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.prev.values_mut() {
+            l.unlink_to(&self.id);
+        }
+        state.prev.clear();
+    }
+
+    pub fn unlink_all(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+
+    pub fn send(&mut self, val: String) -> PyResult<()> {
+        let actions = parse_key_sequence_without_toggles(val.as_str(), Some(&self.state.blocking_lock().transformer))
+            .map_err(|err| ApplicationError::KeySequenceParse(err.to_string()).into_py())?
+            .to_key_actions();
+        for action in actions {
+            self.ev_tx.try_send(InputEvent::Raw(action.to_input_ev())).expect(&TooManyEvents.to_string());
+        }
+        Ok(())
+    }
+}
+
+// the "ABS_" prefix is optional, i.e. "ABS_X" and "X" are the same axis
+fn parse_axis(axis: &str) -> PyResult<EV_ABS> {
+    let name = match axis.get(..4) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("abs_") => &axis[4..],
+        _ => axis,
+    };
+    parse_abs_tag(name).map_err(|_| PyRuntimeError::new_err(format!("invalid axis '{}'", axis)))
+}
+
+fn parse_direction_keys(
+    options: &HashMap<&str, &PyAny>,
+    transformer: &XKBTransformer,
+) -> PyResult<(Option<Key>, Option<Key>)> {
+    let parse = |name: &str| -> PyResult<Option<Key>> {
+        let raw = match options.get(name) {
+            Some(raw) => raw
+                .extract::<String>()
+                .map_err(|_| PyRuntimeError::new_err(format!("'{}' must be of type 'string'", name)))?,
+            None => return Ok(None),
+        };
+        parse_key(&raw, Some(transformer)).map(Some).map_err(|err| {
+            PyRuntimeError::new_err(format!(
+                "mapping error on the '{}' side:\n{}",
+                name,
+                ApplicationError::KeyParse(err.to_string()),
+            ))
+        })
+    };
+
+    let keys = (parse("negative")?, parse("positive")?);
+    if keys == (None, None) {
+        return Err(PyRuntimeError::new_err("expected at least one of 'negative' and 'positive'"));
+    }
+    Ok(keys)
+}
+
+// axes without a negative direction don't need to be centered
+fn parse_centered(options: &HashMap<&str, &PyAny>, has_negative: bool) -> PyResult<bool> {
+    let centered = match options.get("centered") {
+        Some(centered) => {
+            centered.extract().map_err(|_| PyRuntimeError::new_err("'centered' must be of type 'bool'"))?
+        }
+        None => true,
+    };
+    if !centered && has_negative {
+        return Err(PyRuntimeError::new_err("'negative' requires a centered axis"));
+    }
+    Ok(centered)
+}
+
+fn parse_deadzone(deadzone: &PyAny) -> PyResult<f64> {
+    match deadzone.extract::<f64>() {
+        Ok(deadzone) if (0.0..1.0).contains(&deadzone) => Ok(deadzone),
+        _ => Err(PyRuntimeError::new_err("'deadzone' must be a number within [0, 1)")),
+    }
+}
+
+// ABS_HAT0X -> "hat0x"
+fn axis_name(axis: &EV_ABS) -> String {
+    format!("{:?}", axis)[4..].to_lowercase()
+}
+
+impl Drop for AxisMapper {
+    fn drop(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+}
+
+#[derive(Clone)]
+pub struct AxisMapperLink {
+    id: Uuid,
+    ev_tx: Sender<InputEvent>,
+    state: Arc<Mutex<State>>,
+}
+
+impl LinkSrc for AxisMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_to(&self, node: Arc<dyn LinkDst>) -> Result<()> {
+        self.state.blocking_lock().next.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_to(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().next.remove(id).is_some())
+    }
+}
+
+impl LinkDst for AxisMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_from(&self, node: Arc<dyn LinkSrc>) -> Result<()> {
+        self.state.blocking_lock().prev.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_from(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().prev.remove(id).is_some())
+    }
+    fn send(&self, ev: InputEvent) -> Result<()> {
+        self.ev_tx.try_send(ev).map_err(|err| ApplicationError::TooManyEvents.into_py())?;
+        Ok(())
+    }
+}
+
+impl State {
+    fn is_mapped(&self, axis: &EV_ABS) -> bool {
+        self.axes.contains_key(axis) || self.sticks.iter().any(|stick| stick.x == *axis || stick.y == *axis)
+    }
+
+    // removes all mappings reading from the axis
+    fn remove_axis(&mut self, axis: &EV_ABS) -> bool {
+        let mut removed = self.axes.remove(axis).is_some();
+        let sticks = self.sticks.len();
+        self.sticks.retain(|stick| stick.x != *axis && stick.y != *axis);
+        removed |= self.sticks.len() != sticks;
+        removed |= self.remove_axis_keys(axis);
+        removed
+    }
+
+    // a key held by the axis is released
+    fn remove_axis_keys(&mut self, axis: &EV_ABS) -> bool {
+        if let Some(key) = self.pressed.remove(axis) {
+            self.next.send_all(InputEvent::Raw(key.to_input_ev(TYPE_UP)));
+        }
+        self.axis_keys.remove(axis).is_some()
+    }
+
+    fn press_axis_keys(&mut self, axis: EV_ABS, value: i32) {
+        let key = match self.axis_keys.get(&axis) {
+            Some(mapping) => mapping.key(value, self.pressed.get(&axis)),
+            None => return,
+        };
+        if self.pressed.get(&axis) == key.as_ref() {
+            return;
+        }
+        if let Some(key) = self.pressed.remove(&axis) {
+            self.next.send_all(InputEvent::Raw(key.to_input_ev(TYPE_UP)));
+        }
+        if let Some(key) = key {
+            self.pressed.insert(axis, key);
+            self.next.send_all(InputEvent::Raw(key.to_input_ev(TYPE_DOWN)));
+        }
+    }
+
+    fn pushes_axis(&self, key: &Key) -> bool {
+        self.keys_axes.values().any(|mapping| mapping.contains(key))
+    }
+
+    // the axes pushed by a key, along with their new values
+    fn keys_axes_values(&self, key: &Key) -> Vec<(EV_ABS, i32)> {
+        self.keys_axes
+            .iter()
+            .filter(|(_, mapping)| mapping.contains(key))
+            .map(|(axis, mapping)| (*axis, mapping.value(&self.held)))
+            .collect()
+    }
+
+    fn send_axis(&mut self, axis: EV_ABS, value: i32, time: &TimeVal) {
+        if self.sent.get(&axis) == Some(&value) {
+            return;
+        }
+        self.sent.insert(axis, value);
+        self.next.send_all(InputEvent::Raw(EvdevInputEvent {
+            event_code: EventCode::EV_ABS(axis),
+            value,
+            time: *time,
+        }));
+    }
+
+    // the transformed values of the axes an event on `axis` changes
+    fn transform(&self, axis: EV_ABS) -> Vec<(EV_ABS, i32)> {
+        let transform = |axis: &EV_ABS| self.axes.get(axis).cloned().unwrap_or_default();
+        let normalized = |axis: &EV_ABS| {
+            let transform = transform(axis);
+            transform.normalize(self.values.get(axis).copied().unwrap_or_else(|| transform.rest_value()))
+        };
+
+        let stick = match self.sticks.iter().find(|stick| stick.x == axis || stick.y == axis) {
+            Some(stick) => stick,
+            None => {
+                let transform = transform(&axis);
+                let value = transform.normalize(self.values[&axis]);
+                return vec![(axis, transform.denormalize(transform.shape(value)))];
+            }
+        };
+
+        let (x, y) = (normalized(&stick.x), normalized(&stick.y));
+        let radius = x.hypot(y);
+        let scale = if radius < stick.deadzone || radius == 0.0 {
+            0.0
+        } else {
+            ((radius - stick.deadzone) / (1.0 - stick.deadzone)).min(1.0) / radius
+        };
+
+        let mut acc = vec![];
+        for (other, value) in [(stick.x, x), (stick.y, y)] {
+            // the other axis only moves along once it reported a value
+            if other == axis || self.values.contains_key(&other) {
+                let transform = transform(&other);
+                acc.push((other, transform.denormalize(transform.shape(value * scale))));
+            }
+        }
+        acc
+    }
+}
+
+async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
+    let mut state = _state.lock().await;
+
+    let ev = match &raw_ev {
+        InputEvent::Raw(ev) => ev,
+    };
+
+    match ev.event_code {
+        EventCode::EV_ABS(axis) if state.axis_keys.contains_key(&axis) => {
+            state.press_axis_keys(axis, ev.value);
+        }
+        EventCode::EV_ABS(axis) if state.is_mapped(&axis) => {
+            state.values.insert(axis, ev.value);
+            for (axis, value) in state.transform(axis) {
+                state.send_axis(axis, value, &ev.time);
+            }
+        }
+        EventCode::EV_KEY(_) if state.pushes_axis(&Key { event_code: ev.event_code }) => {
+            let key = Key { event_code: ev.event_code };
+            match ev.value {
+                TYPE_DOWN => {
+                    state.held.insert(key);
+                }
+                TYPE_UP => {
+                    state.held.remove(&key);
+                }
+                _ => return,
+            }
+            for (axis, value) in state.keys_axes_values(&key) {
+                state.send_axis(axis, value, &ev.time);
+            }
+        }
+        _ => state.next.send_all(raw_ev),
+    }
+}
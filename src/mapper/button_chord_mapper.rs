@@ -0,0 +1,348 @@
+use super::*;
+use crate::python::*;
+use crate::*;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex;
+
+use ApplicationError::TooManyEvents;
+
+// the two buttons of a chord, sorted
+type Chord = [Key; 2];
+
+struct State {
+    prev: HashMap<Uuid, Arc<dyn LinkSrc>>,
+    next: HashMap<Uuid, Arc<dyn LinkDst>>,
+    timeout: Duration,
+    drag_distance: f64,
+    chords: HashMap<Chord, Key>,
+    pending: Option<Pending>,
+    // the chord currently held, its target and its buttons that weren't released yet
+    active: Option<(Chord, Key, Vec<Key>)>,
+    timers: Timers<Key>,
+}
+
+/// A chord button that was pressed, waiting for the other button of a chord.
+struct Pending {
+    button: Key,
+    down: EvdevInputEvent,
+    // events that arrived in the meantime, sent after the button
+    held_back: Vec<InputEvent>,
+    // how far the pointer moved in the meantime
+    distance: f64,
+}
+
+#[pyclass]
+pub struct ButtonChordMapper {
+    pub id: Uuid,
+    pub link: Arc<ButtonChordMapperLink>,
+    ev_tx: tokio::sync::mpsc::Sender<InputEvent>,
+    state: Arc<Mutex<State>>,
+}
+
+#[pymethods]
+impl ButtonChordMapper {
+    #[new]
+    #[pyo3(signature = (**kwargs))]
+    pub fn new(kwargs: Option<&PyDict>) -> PyResult<Self> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let timeout = match options.get("timeout_ms") {
+            Some(timeout_ms) => Duration::from_millis(
+                timeout_ms
+                    .extract::<u64>()
+                    .map_err(|_| PyRuntimeError::new_err("'timeout_ms' must be of type 'int'"))?,
+            ),
+            None => Duration::from_millis(50),
+        };
+        let drag_distance = match options.get("drag_distance") {
+            Some(drag_distance) => match drag_distance.extract::<f64>() {
+                Ok(drag_distance) if drag_distance > 0.0 => drag_distance,
+                _ => return Err(PyRuntimeError::new_err("'drag_distance' must be a positive number")),
+            },
+            None => 3.0,
+        };
+
+        let id = Uuid::new_v4();
+        let (ev_tx, mut ev_rx) = tokio::sync::mpsc::channel(64);
+        let state = Arc::new(Mutex::new(State {
+            prev: Default::default(),
+            next: Default::default(),
+            timeout,
+            drag_distance,
+            chords: Default::default(),
+            pending: None,
+            active: None,
+            timers: Default::default(),
+        }));
+        let link = Arc::new(ButtonChordMapperLink { id, ev_tx: ev_tx.clone(), state: state.clone() });
+
+        {
+            let state = state.clone();
+            get_runtime().spawn(async move {
+                loop {
+                    let ev = ev_rx.recv().await;
+                    match ev {
+                        Some(ev) => handle(state.clone(), ev).await,
+                        None => return,
+                    }
+                }
+            });
+        }
+
+        Ok(Self { id, link, ev_tx, state })
+    }
+
+    pub fn map(&mut self, from: Vec<String>, to: String) -> PyResult<()> {
+        let chord = parse_button_chord(from)?;
+        let to = parse_button(&to, "to")?;
+        self.state.blocking_lock().chords.insert(chord, to);
+        Ok(())
+    }
+
+    pub fn unmap(&mut self, from: Vec<String>) -> PyResult<bool> {
+        let chord = parse_button_chord(from)?;
+        Ok(self.state.blocking_lock().chords.remove(&chord).is_some())
+    }
+
+    pub fn mappings(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let state = self.state.blocking_lock();
+        let mut acc: Vec<(Vec<String>, &PyDict)> = vec![];
+        for (chord, to) in state.chords.iter() {
+            let from: Vec<String> = chord.iter().map(key_name).collect();
+            let info = PyDict::new(py);
+            info.set_item("from", from.clone())?;
+            info.set_item("to", key_name(to))?;
+            acc.push((from, info));
+        }
+
+        acc.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(acc.into_iter().map(|(_, info)| info.to_object(py)).collect())
+    }
+
+    pub fn link_to(&mut self, target: &PyAny) -> PyResult<()> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.link_from(self.link.clone());
+        self.link.link_to(target);
+        Ok(())
+    }
+
+    pub fn unlink_to(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.unlink_from(&self.id);
+        self.link.unlink_to(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_to_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.next.values_mut() {
+            l.unlink_from(&self.id);
+        }
+        state.next.clear();
+    }
+
+    pub fn unlink_from(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_src(target).ok_or_else(|| PyRuntimeError::new_err("expected a source node"))?;
+        target.unlink_to(&self.id);
+        self.link.unlink_from(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_from_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.prev.values_mut() {
+            l.unlink_to(&self.id);
+        }
+        state.prev.clear();
+    }
+
+    pub fn unlink_all(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+
+    pub fn send(&mut self, val: String) -> PyResult<()> {
+        let actions = parse_key_sequence_without_toggles(val.as_str(), None)
+            .map_err(|err| ApplicationError::KeySequenceParse(err.to_string()).into_py())?
+            .to_key_actions();
+        for action in actions {
+            self.ev_tx.try_send(InputEvent::Raw(action.to_input_ev())).expect(&TooManyEvents.to_string());
+        }
+        Ok(())
+    }
+}
+
+fn parse_button(raw: &str, side: &str) -> PyResult<Key> {
+    let key = parse_key(raw, None).map_err(|err| {
+        PyRuntimeError::new_err(format!(
+            "mapping error on the '{}' side:\n{}",
+            side,
+            ApplicationError::KeyParse(err.to_string()),
+        ))
+    })?;
+    match key.event_code {
+        EventCode::EV_KEY(_) => Ok(key),
+        _ => Err(PyRuntimeError::new_err(format!("mapping error on the '{}' side:\nexpected a button or key", side))),
+    }
+}
+
+fn parse_button_chord(from: Vec<String>) -> PyResult<Chord> {
+    let mut chord = match from.as_slice() {
+        [a, b] => [parse_button(a, "from")?, parse_button(b, "from")?],
+        _ => return Err(PyRuntimeError::new_err("mapping error on the 'from' side:\nexpected exactly 2 buttons")),
+    };
+    if chord[0] == chord[1] {
+        return Err(PyRuntimeError::new_err("mapping error on the 'from' side:\nexpected 2 different buttons"));
+    }
+    chord.sort();
+    Ok(chord)
+}
+
+impl Drop for ButtonChordMapper {
+    fn drop(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+}
+
+#[derive(Clone)]
+pub struct ButtonChordMapperLink {
+    id: Uuid,
+    ev_tx: Sender<InputEvent>,
+    state: Arc<Mutex<State>>,
+}
+
+impl LinkSrc for ButtonChordMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_to(&self, node: Arc<dyn LinkDst>) -> Result<()> {
+        self.state.blocking_lock().next.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_to(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().next.remove(id).is_some())
+    }
+}
+
+impl LinkDst for ButtonChordMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_from(&self, node: Arc<dyn LinkSrc>) -> Result<()> {
+        self.state.blocking_lock().prev.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_from(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().prev.remove(id).is_some())
+    }
+    fn send(&self, ev: InputEvent) -> Result<()> {
+        self.ev_tx.try_send(ev).map_err(|err| ApplicationError::TooManyEvents.into_py())?;
+        Ok(())
+    }
+}
+
+impl State {
+    fn is_chord_button(&self, key: &Key) -> bool {
+        self.chords.keys().any(|chord| chord.contains(key))
+    }
+
+    fn chord(&self, a: Key, b: Key) -> Option<Chord> {
+        let mut chord = [a, b];
+        chord.sort();
+        self.chords.contains_key(&chord).then_some(chord)
+    }
+
+    // sends the pending button as a regular press, followed by the events held back
+    fn release_pending(&mut self) {
+        let pending = match self.pending.take() {
+            Some(pending) => pending,
+            None => return,
+        };
+        self.timers.cancel(&pending.button);
+        self.next.send_all(InputEvent::Raw(pending.down));
+        for ev in pending.held_back {
+            self.next.send_all(ev);
+        }
+    }
+}
+
+async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
+    let mut state = _state.lock().await;
+
+    let ev = match &raw_ev {
+        InputEvent::Raw(ev) => ev.clone(),
+    };
+    let key = Key { event_code: ev.event_code };
+
+    if !matches!(ev.event_code, EventCode::EV_KEY(_)) {
+        if state.pending.is_none() {
+            return state.next.send_all(raw_ev);
+        }
+        let drag_distance = state.drag_distance;
+        let pending = state.pending.as_mut().unwrap();
+        pending.held_back.push(raw_ev);
+        // moving the pointer while holding a button is a drag, it shouldn't wait for the timeout
+        if let EventCode::EV_REL(REL_X | REL_Y) = ev.event_code {
+            pending.distance += (ev.value as f64).abs();
+            if pending.distance >= drag_distance {
+                state.release_pending();
+            }
+        }
+        return;
+    }
+
+    // the buttons of the held chord are swallowed, releasing either of them releases the target
+    if let Some((chord, to, held)) = state.active.clone() {
+        if chord.contains(&key) {
+            if ev.value == TYPE_UP {
+                if held.len() == chord.len() {
+                    state.next.send_all(InputEvent::Raw(to.to_input_ev(TYPE_UP)));
+                }
+                let held: Vec<Key> = held.into_iter().filter(|button| *button != key).collect();
+                state.active = if held.is_empty() { None } else { Some((chord, to, held)) };
+            }
+            return;
+        }
+    }
+
+    if let Some(button) = state.pending.as_ref().map(|pending| pending.button) {
+        let chord = state.chord(button, key);
+        match (ev.value, chord) {
+            (TYPE_DOWN, Some(chord)) => {
+                let pending = state.pending.take().unwrap();
+                state.timers.cancel(&pending.button);
+                for ev in pending.held_back {
+                    state.next.send_all(ev);
+                }
+                let to = state.chords[&chord];
+                state.next.send_all(InputEvent::Raw(to.to_input_ev(TYPE_DOWN)));
+                state.active = Some((chord, to, chord.to_vec()));
+                return;
+            }
+            // released before the timeout, a regular click
+            (TYPE_UP, _) if button == key => {
+                state.release_pending();
+                return state.next.send_all(raw_ev);
+            }
+            _ => state.release_pending(),
+        }
+    }
+
+    if ev.value == TYPE_DOWN && state.active.is_none() && state.is_chord_button(&key) {
+        state.pending = Some(Pending { button: key, down: ev, held_back: vec![], distance: 0.0 });
+        let timeout = state.timeout;
+        let _state = _state.clone();
+        state.timers.spawn_after(key, timeout, async move {
+            let mut state = _state.lock().await;
+            state.timers.forget(&key);
+            if state.pending.as_ref().map_or(false, |pending| pending.button == key) {
+                state.release_pending();
+            }
+        });
+        return;
+    }
+
+    state.next.send_all(raw_ev);
+}
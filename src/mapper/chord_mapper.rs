@@ -81,15 +81,7 @@ impl ChordMapper {
         //     ));
         // }
 
-        let mut from_parsed =
-            from.into_iter().map(|x| parse_key(&x, Some(&state.transformer))).collect::<Result<Vec<_>>>().map_err(
-                |err| {
-                    PyRuntimeError::new_err(format!(
-                        "mapping error on the 'from' side:\n{}",
-                        ApplicationError::KeyParse(err.to_string()),
-                    ))
-                },
-            )?;
+        let mut from_parsed = parse_chord(from, &state.transformer)?;
 
         let to = if to.as_ref(py).is_callable() {
             RuntimeAction::PythonCallback(Default::default(), Arc::new(to))
@@ -100,7 +92,7 @@ impl ChordMapper {
                     ApplicationError::InvalidInputType { type_: "String".to_string() }
                 ))
             })?;
-            let to = parse_key_sequence(&to, Some(&state.transformer)).map_err(|err| {
+            let to = parse_key_sequence_without_toggles(&to, Some(&state.transformer)).map_err(|err| {
                 PyRuntimeError::new_err(format!(
                     "mapping error on the 'to' side:\n{}",
                     ApplicationError::KeySequenceParse(err.to_string()),
@@ -125,14 +117,50 @@ impl ChordMapper {
         Ok(())
     }
 
+    pub fn unmap(&mut self, from: Vec<String>) -> PyResult<bool> {
+        let mut state = self.state.blocking_lock();
+        let mut from = parse_chord(from, &state.transformer)?;
+
+        let mut removed = state.mappings.remove(&from).is_some();
+        from.reverse();
+        removed |= state.mappings.remove(&from).is_some();
+
+        state.chorded_keys = chorded_keys(&state.mappings);
+        Ok(removed)
+    }
+
+    pub fn mappings(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let state = self.state.blocking_lock();
+        Ok(describe_mappings(py, &state.mappings)?.into_iter().map(|info| info.to_object(py)).collect())
+    }
+
+    pub fn resolve(&self, py: Python, from: Vec<String>) -> PyResult<Option<PyObject>> {
+        let state = self.state.blocking_lock();
+        let keys = parse_chord(from, &state.transformer)?;
+        match state.mappings.get(&keys) {
+            Some(action) => {
+                let from: Vec<String> = keys.iter().map(key_name).collect();
+                Ok(Some(mapping_info(py, from, action)?.to_object(py)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn load(&mut self, py: Python, path: String) -> PyResult<()> {
+        let entries = read_keymap(&path).map_err(err_to_py)?;
+        for (idx, entry) in entries.iter().enumerate() {
+            self.load_entry(py, entry).map_err(|err| {
+                PyRuntimeError::new_err(format!("keymap '{}', entry {}:\n{}", path, idx + 1, err.value(py)))
+            })?;
+        }
+        Ok(())
+    }
+
     pub fn snapshot(&self, existing: Option<&ChordMapperSnapshot>) -> PyResult<Option<ChordMapperSnapshot>> {
         let mut state = self.state.blocking_lock();
         if let Some(existing) = existing {
             state.mappings = existing.mappings.clone();
-            state.chorded_keys = state.mappings.keys().fold(HashSet::new(), |mut acc, e| {
-                acc.extend(e.iter().cloned());
-                acc
-            });
+            state.chorded_keys = chorded_keys(&state.mappings);
             return Ok(None);
         }
         Ok(Some(ChordMapperSnapshot { mappings: state.mappings.clone() }))
@@ -181,7 +209,7 @@ impl ChordMapper {
     }
 
     pub fn send(&mut self, val: String) -> PyResult<()> {
-        let actions = parse_key_sequence(val.as_str(), Some(&self.state.blocking_lock().transformer))
+        let actions = parse_key_sequence_without_toggles(val.as_str(), Some(&self.state.blocking_lock().transformer))
             .map_err(|err| ApplicationError::KeySequenceParse(err.to_string()).into_py())?
             .to_key_actions();
         for action in actions {
@@ -191,6 +219,59 @@ impl ChordMapper {
     }
 }
 
+impl ChordMapper {
+    fn load_entry(&mut self, py: Python, entry: &serde_json::Value) -> PyResult<()> {
+        let entry = KeymapEntry::from_json(py, entry)?;
+        if entry.kind == "record" || entry.kind == "replay" {
+            return Err(PyRuntimeError::new_err(format!("'{}' mappings are only supported by a Mapper", entry.kind)));
+        }
+        if entry.kind != "sequence" {
+            return Err(PyRuntimeError::new_err(format!("unsupported kind '{}', expected 'sequence'", entry.kind)));
+        }
+        let from = entry
+            .from
+            .extract::<Vec<String>>()
+            .map_err(|_| PyRuntimeError::new_err("'from' must be of type 'string[]'"))?;
+        self.map(py, from, entry.to()?.to_object(py))
+    }
+}
+
+// every chord is stored in both orders, only one of them is listed
+fn describe_mappings<'py>(py: Python<'py>, mappings: &Mappings) -> PyResult<Vec<&'py PyDict>> {
+    let mut acc: Vec<(String, &PyDict)> = vec![];
+    let mut listed: Vec<&Vec<Key>> = vec![];
+
+    for (keys, action) in mappings.iter() {
+        if listed.iter().any(|listed| listed.iter().rev().eq(keys.iter())) {
+            continue;
+        }
+        listed.push(keys);
+
+        let from: Vec<String> = keys.iter().map(key_name).collect();
+        let info = mapping_info(py, &from, action)?;
+        acc.push((from.join(" "), info));
+    }
+
+    acc.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(acc.into_iter().map(|(_, info)| info).collect())
+}
+
+fn parse_chord(from: Vec<String>, transformer: &XKBTransformer) -> PyResult<Vec<Key>> {
+    from.into_iter().map(|x| parse_key(&x, Some(transformer))).collect::<Result<Vec<_>>>().map_err(|err| {
+        PyRuntimeError::new_err(format!(
+            "mapping error on the 'from' side:\n{}",
+            ApplicationError::KeyParse(err.to_string()),
+        ))
+    })
+}
+
+fn chorded_keys(mappings: &Mappings) -> HashSet<Key> {
+    mappings.keys().fold(HashSet::new(), |mut acc, e| {
+        acc.extend(e.iter().cloned());
+        acc
+    })
+}
+
 impl Drop for ChordMapper {
     fn drop(&mut self) {
         self.unlink_from_all();
@@ -241,6 +322,17 @@ pub struct ChordMapperSnapshot {
     mappings: Mappings,
 }
 
+#[pymethods]
+impl ChordMapperSnapshot {
+    pub fn to_file(&self, py: Python, path: String) -> PyResult<()> {
+        let mut entries = vec![];
+        for info in describe_mappings(py, &self.mappings)? {
+            entries.extend(to_keymap_entry(info)?);
+        }
+        write_keymap(&path, entries).map_err(err_to_py)
+    }
+}
+
 async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
     let mut state = _state.lock().await;
 
@@ -376,6 +468,8 @@ async fn handle_cb(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
                                 }
                             }
                         }
+                        // rejected when mapping, only the key mapper keeps latched keys and macros
+                        RuntimeKeyAction::Toggle(_) | RuntimeKeyAction::Macro(_) => {}
                     }
                 }
             }
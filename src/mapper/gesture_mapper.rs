@@ -0,0 +1,413 @@
+use super::*;
+use crate::mapper::mapping_functions::*;
+use crate::python::*;
+use crate::xkb::XKBTransformer;
+use crate::xkb_transformer_registry::{TransformerParams, XKB_TRANSFORMER_REGISTRY};
+use crate::*;
+use evdev_rs::enums::{EV_REL, EV_SYN};
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex;
+
+use ApplicationError::TooManyEvents;
+
+struct State {
+    transformer: Arc<XKBTransformer>,
+    prev: HashMap<Uuid, Arc<dyn LinkSrc>>,
+    next: HashMap<Uuid, Arc<dyn LinkDst>>,
+    button: Key,
+    threshold: f64,
+    fallback: Fallback,
+    mappings: HashMap<Vec<Direction>, RuntimeAction>,
+    // set while the trigger button is held
+    gesture: Option<Gesture>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Fallback {
+    // sends the button and the motion as if there was no gesture
+    Replay,
+    Drop,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Direction {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+// clockwise, starting at the top
+const DIRECTIONS: [(&str, Direction); 8] = [
+    ("up", Direction::Up),
+    ("up-right", Direction::UpRight),
+    ("right", Direction::Right),
+    ("down-right", Direction::DownRight),
+    ("down", Direction::Down),
+    ("down-left", Direction::DownLeft),
+    ("left", Direction::Left),
+    ("up-left", Direction::UpLeft),
+];
+
+impl Direction {
+    // the nearest of the 8 directions, the y axis points down
+    fn from_motion(x: f64, y: f64) -> Self {
+        let angle = x.atan2(-y).to_degrees().rem_euclid(360.0);
+        DIRECTIONS[((angle + 22.5) / 45.0) as usize % 8].1
+    }
+
+    fn name(&self) -> &'static str {
+        DIRECTIONS.iter().find(|(_, direction)| direction == self).unwrap().0
+    }
+}
+
+/// The strokes recognized while the trigger button is held.
+struct Gesture {
+    down: EvdevInputEvent,
+    strokes: Vec<Direction>,
+    // the motion since the last stroke
+    x: f64,
+    y: f64,
+    // the motion, sent if no gesture is recognized
+    held_back: Vec<InputEvent>,
+}
+
+impl Gesture {
+    fn push(&mut self, axis: EV_REL, value: i32, threshold: f64) {
+        match axis {
+            REL_X => self.x += value as f64,
+            _ => self.y += value as f64,
+        }
+        if self.x.hypot(self.y) < threshold {
+            return;
+        }
+        let direction = Direction::from_motion(self.x, self.y);
+        if self.strokes.last() != Some(&direction) {
+            self.strokes.push(direction);
+        }
+        self.x = 0.0;
+        self.y = 0.0;
+    }
+}
+
+#[pyclass]
+pub struct GestureMapper {
+    pub id: Uuid,
+    pub link: Arc<GestureMapperLink>,
+    ev_tx: tokio::sync::mpsc::Sender<InputEvent>,
+    state: Arc<Mutex<State>>,
+}
+
+#[pymethods]
+impl GestureMapper {
+    #[new]
+    #[pyo3(signature = (**kwargs))]
+    pub fn new(kwargs: Option<&PyDict>) -> PyResult<Self> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let kbd_model = options.get("model").and_then(|x| x.extract().ok());
+        let kbd_layout = options.get("layout").and_then(|x| x.extract().ok());
+        let kbd_variant = options.get("variant").and_then(|x| x.extract().ok());
+        let kbd_options = options.get("options").and_then(|x| x.extract().ok());
+        let transformer = XKB_TRANSFORMER_REGISTRY
+            .get(&TransformerParams::new(kbd_model, kbd_layout, kbd_variant, kbd_options))
+            .map_err(err_to_py)?;
+
+        let button = match options.get("button") {
+            Some(button) => {
+                let button = button
+                    .extract::<String>()
+                    .map_err(|_| PyRuntimeError::new_err("'button' must be of type 'string'"))?;
+                parse_key(&button, Some(&transformer)).map_err(|err| {
+                    PyRuntimeError::new_err(format!(
+                        "mapping error on the 'button' side:\n{}",
+                        ApplicationError::KeyParse(err.to_string()),
+                    ))
+                })?
+            }
+            None => Key { event_code: EventCode::EV_KEY(BTN_RIGHT) },
+        };
+        let threshold = match options.get("threshold") {
+            Some(threshold) => match threshold.extract::<f64>() {
+                Ok(threshold) if threshold > 0.0 => threshold,
+                _ => return Err(PyRuntimeError::new_err("'threshold' must be a positive number")),
+            },
+            None => 50.0,
+        };
+        let fallback = match options.get("fallback").map(|fallback| fallback.extract::<String>()) {
+            None => Fallback::Replay,
+            Some(Ok(fallback)) if fallback == "replay" => Fallback::Replay,
+            Some(Ok(fallback)) if fallback == "drop" => Fallback::Drop,
+            Some(_) => return Err(PyRuntimeError::new_err("'fallback' must be one of: 'replay', 'drop'")),
+        };
+
+        let id = Uuid::new_v4();
+        let (ev_tx, mut ev_rx) = tokio::sync::mpsc::channel(64);
+        let state = Arc::new(Mutex::new(State {
+            transformer,
+            prev: Default::default(),
+            next: Default::default(),
+            button,
+            threshold,
+            fallback,
+            mappings: Default::default(),
+            gesture: None,
+        }));
+        let link = Arc::new(GestureMapperLink { id, ev_tx: ev_tx.clone(), state: state.clone() });
+
+        {
+            let state = state.clone();
+            get_runtime().spawn(async move {
+                loop {
+                    let ev = ev_rx.recv().await;
+                    match ev {
+                        Some(ev) => handle(state.clone(), ev).await,
+                        None => return,
+                    }
+                }
+            });
+        }
+
+        Ok(Self { id, link, ev_tx, state })
+    }
+
+    pub fn map(&mut self, py: Python, gesture: String, to: PyObject) -> PyResult<()> {
+        let mut state = self.state.blocking_lock();
+        let gesture = parse_gesture(&gesture)?;
+
+        let to = if to.as_ref(py).is_callable() {
+            RuntimeAction::PythonCallback(Default::default(), Arc::new(to))
+        } else {
+            let to = to.extract::<String>(py).map_err(|_| {
+                PyRuntimeError::new_err(format!(
+                    "mapping error on the 'to' side:\n{}",
+                    ApplicationError::InvalidInputType { type_: "String".to_string() }
+                ))
+            })?;
+            let to = parse_key_sequence_without_toggles(&to, Some(&state.transformer)).map_err(|err| {
+                PyRuntimeError::new_err(format!(
+                    "mapping error on the 'to' side:\n{}",
+                    ApplicationError::KeySequenceParse(err.to_string()),
+                ))
+            })?;
+            RuntimeAction::ActionSequence(to.to_key_actions().into_iter().map(RuntimeKeyAction::KeyAction).collect())
+        };
+
+        state.mappings.insert(gesture, to);
+        Ok(())
+    }
+
+    pub fn unmap(&mut self, gesture: String) -> PyResult<bool> {
+        let gesture = parse_gesture(&gesture)?;
+        Ok(self.state.blocking_lock().mappings.remove(&gesture).is_some())
+    }
+
+    pub fn mappings(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let state = self.state.blocking_lock();
+        let mut acc: Vec<(String, &PyDict)> = vec![];
+        for (gesture, action) in state.mappings.iter() {
+            let from = format_gesture(gesture);
+            acc.push((from.clone(), mapping_info(py, from, action)?));
+        }
+
+        acc.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(acc.into_iter().map(|(_, info)| info.to_object(py)).collect())
+    }
+
+    pub fn link_to(&mut self, target: &PyAny) -> PyResult<()> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.link_from(self.link.clone());
+        self.link.link_to(target);
+        Ok(())
+    }
+
+    pub fn unlink_to(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.unlink_from(&self.id);
+        self.link.unlink_to(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_to_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.next.values_mut() {
+            l.unlink_from(&self.id);
+        }
+        state.next.clear();
+    }
+
+    pub fn unlink_from(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_src(target).ok_or_else(|| PyRuntimeError::new_err("expected a source node"))?;
+        target.unlink_to(&self.id);
+        self.link.unlink_from(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_from_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.prev.values_mut() {
+            l.unlink_to(&self.id);
+        }
+        state.prev.clear();
+    }
+
+    pub fn unlink_all(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+
+    pub fn send(&mut self, val: String) -> PyResult<()> {
+        let actions = parse_key_sequence_without_toggles(val.as_str(), Some(&self.state.blocking_lock().transformer))
+            .map_err(|err| ApplicationError::KeySequenceParse(err.to_string()).into_py())?
+            .to_key_actions();
+        for action in actions {
+            self.ev_tx.try_send(InputEvent::Raw(action.to_input_ev())).expect(&TooManyEvents.to_string());
+        }
+        Ok(())
+    }
+}
+
+// i.e. "left", "up-right" or "down then right"
+fn parse_gesture(raw: &str) -> PyResult<Vec<Direction>> {
+    let mut gesture = vec![];
+    for stroke in raw.split(" then ") {
+        let stroke = stroke.trim().to_lowercase();
+        let direction =
+            DIRECTIONS.iter().find(|(name, _)| *name == stroke).map(|(_, direction)| *direction).ok_or_else(|| {
+                PyRuntimeError::new_err(format!(
+                    "mapping error on the 'from' side:\ninvalid direction '{}', expected one of: {}",
+                    stroke,
+                    DIRECTIONS.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(", "),
+                ))
+            })?;
+        if gesture.last() == Some(&direction) {
+            return Err(PyRuntimeError::new_err(format!(
+                "mapping error on the 'from' side:\nthe direction '{}' is repeated, consecutive strokes need different directions",
+                stroke
+            )));
+        }
+        gesture.push(direction);
+    }
+    Ok(gesture)
+}
+
+fn format_gesture(gesture: &[Direction]) -> String {
+    gesture.iter().map(Direction::name).collect::<Vec<_>>().join(" then ")
+}
+
+impl Drop for GestureMapper {
+    fn drop(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+}
+
+#[derive(Clone)]
+pub struct GestureMapperLink {
+    id: Uuid,
+    ev_tx: Sender<InputEvent>,
+    state: Arc<Mutex<State>>,
+}
+
+impl LinkSrc for GestureMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_to(&self, node: Arc<dyn LinkDst>) -> Result<()> {
+        self.state.blocking_lock().next.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_to(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().next.remove(id).is_some())
+    }
+}
+
+impl LinkDst for GestureMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_from(&self, node: Arc<dyn LinkSrc>) -> Result<()> {
+        self.state.blocking_lock().prev.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_from(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().prev.remove(id).is_some())
+    }
+    fn send(&self, ev: InputEvent) -> Result<()> {
+        self.ev_tx.try_send(ev).map_err(|err| ApplicationError::TooManyEvents.into_py())?;
+        Ok(())
+    }
+}
+
+async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
+    let mut state = _state.lock().await;
+
+    let ev = match &raw_ev {
+        InputEvent::Raw(ev) => ev.clone(),
+    };
+
+    if state.gesture.is_none() {
+        if ev.event_code == state.button.event_code && ev.value == TYPE_DOWN {
+            state.gesture = Some(Gesture { down: ev, strokes: vec![], x: 0.0, y: 0.0, held_back: vec![] });
+        } else {
+            state.next.send_all(raw_ev);
+        }
+        return;
+    }
+
+    let threshold = state.threshold;
+    let gesture = state.gesture.as_mut().unwrap();
+    match ev.event_code {
+        EventCode::EV_REL(axis @ (REL_X | REL_Y)) => {
+            gesture.push(axis, ev.value, threshold);
+            gesture.held_back.push(raw_ev);
+        }
+        EventCode::EV_SYN(EV_SYN::SYN_REPORT) => gesture.held_back.push(raw_ev),
+        event_code if event_code == state.button.event_code => {
+            if ev.value != TYPE_UP {
+                return;
+            }
+            let gesture = state.gesture.take().unwrap();
+            let action = state.mappings.get(&gesture.strokes).cloned();
+
+            match action {
+                Some(RuntimeAction::ActionSequence(seq)) => {
+                    for action in seq {
+                        match action {
+                            RuntimeKeyAction::KeyAction(action) => {
+                                state.next.send_all(InputEvent::Raw(action.to_input_ev()));
+                            }
+                            // `map` only creates key actions, toggles are rejected there and macros can't be mapped
+                            RuntimeKeyAction::ReleaseRestoreModifiers(..)
+                            | RuntimeKeyAction::Toggle(_)
+                            | RuntimeKeyAction::Macro(_) => {}
+                        }
+                    }
+                }
+                Some(RuntimeAction::PythonCallback(_, handler)) => {
+                    let transformer = state.transformer.clone();
+                    let next = state.next.values().cloned().collect();
+                    drop(state);
+                    run_python_handler(handler, None, ev, transformer, next).await;
+                }
+                Some(RuntimeAction::NOP) => {}
+                // a click or an unknown gesture
+                None => {
+                    if gesture.strokes.is_empty() || state.fallback == Fallback::Replay {
+                        state.next.send_all(InputEvent::Raw(gesture.down));
+                        for ev in gesture.held_back {
+                            state.next.send_all(ev);
+                        }
+                        state.next.send_all(raw_ev);
+                    }
+                }
+            }
+        }
+        _ => state.next.send_all(raw_ev),
+    }
+}
@@ -0,0 +1,125 @@
+use std::hash::Hash;
+
+use crate::*;
+
+/// Prefix tree over key sequences, used to track partially typed sequence triggers.
+pub struct KeyTrie<K, Value> {
+    root: KeyTrieNode<K, Value>,
+}
+
+impl<K, Value> Default for KeyTrie<K, Value> {
+    fn default() -> Self {
+        Self { root: KeyTrieNode::default() }
+    }
+}
+
+impl<K: Eq + Hash + Clone, Value> KeyTrie<K, Value> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: &[K], value: Value) {
+        self.root.insert(key, value);
+    }
+
+    pub fn get(&self, key: &[K]) -> Option<&KeyTrieNode<K, Value>> {
+        self.root.get(key)
+    }
+
+    pub fn remove(&mut self, key: &[K]) -> Option<Value> {
+        self.root.remove(key)
+    }
+
+    /// All stored key sequences with their values.
+    pub fn entries(&self) -> Vec<(Vec<K>, &Value)> {
+        let mut acc = vec![];
+        self.root.collect_entries(&mut vec![], &mut acc);
+        acc
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.children.is_empty()
+    }
+}
+
+impl<K: Clone, Value: Clone> Clone for KeyTrie<K, Value> {
+    fn clone(&self) -> Self {
+        Self { root: self.root.clone() }
+    }
+}
+
+pub struct KeyTrieNode<K, Value> {
+    value: Option<Value>,
+    children: HashMap<K, KeyTrieNode<K, Value>>,
+}
+
+impl<K, Value> Default for KeyTrieNode<K, Value> {
+    fn default() -> Self {
+        Self { value: None, children: HashMap::new() }
+    }
+}
+
+impl<K: Eq + Hash + Clone, Value> KeyTrieNode<K, Value> {
+    pub fn insert(&mut self, key: &[K], value: Value) {
+        match key.split_first() {
+            Some((first, rest)) => self.children.entry(first.clone()).or_default().insert(rest, value),
+            None => self.value = Some(value),
+        }
+    }
+
+    pub fn get(&self, key: &[K]) -> Option<&KeyTrieNode<K, Value>> {
+        match key.split_first() {
+            Some((first, rest)) => self.children.get(first).and_then(|x| x.get(rest)),
+            None => Some(self),
+        }
+    }
+
+    // removes the value and prunes the nodes left without values
+    pub fn remove(&mut self, key: &[K]) -> Option<Value> {
+        match key.split_first() {
+            Some((first, rest)) => {
+                let child = self.children.get_mut(first)?;
+                let value = child.remove(rest);
+                if child.value.is_none() && child.children.is_empty() {
+                    self.children.remove(first);
+                }
+                value
+            }
+            None => self.value.take(),
+        }
+    }
+
+    fn collect_entries<'a>(&'a self, path: &mut Vec<K>, acc: &mut Vec<(Vec<K>, &'a Value)>) {
+        if let Some(value) = self.value.as_ref() {
+            acc.push((path.clone(), value));
+        }
+        for (key, child) in self.children.iter() {
+            path.push(key.clone());
+            child.collect_entries(path, acc);
+            path.pop();
+        }
+    }
+
+    pub fn value(&self) -> Option<&Value> {
+        self.value.as_ref()
+    }
+
+    pub fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    /// All values stored in this node and its descendants.
+    pub fn values(&self) -> Vec<&Value> {
+        let mut acc: Vec<&Value> = self.value.iter().collect();
+        for child in self.children.values() {
+            acc.extend(child.values());
+        }
+        acc
+    }
+}
+
+impl<K: Clone, Value: Clone> Clone for KeyTrieNode<K, Value> {
+    fn clone(&self) -> Self {
+        Self { value: self.value.clone(), children: self.children.clone() }
+    }
+}
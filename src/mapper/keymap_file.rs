@@ -0,0 +1,93 @@
+use crate::python::*;
+use crate::*;
+use serde_json::{json, Value};
+use std::path::Path;
+
+const KEYMAP_VERSION: u64 = 1;
+
+/// Writes keymap entries to a file, `.toml` files are written as TOML, everything else as JSON.
+pub fn write_keymap(path: &str, mut mappings: Vec<Value>) -> Result<()> {
+    // hash map order is random, sorting keeps the files diffable
+    mappings.sort_by_key(|entry| entry["from"].to_string());
+    let keymap = json!({ "version": KEYMAP_VERSION, "mappings": mappings });
+
+    let raw = if is_toml(path) { toml::to_string_pretty(&keymap)? } else { serde_json::to_string_pretty(&keymap)? };
+    fs::write(path, raw).map_err(|err| anyhow!("failed to write keymap '{}': {}", path, err))
+}
+
+/// Reads the entries of a keymap file written by [`write_keymap`].
+pub fn read_keymap(path: &str) -> Result<Vec<Value>> {
+    let raw = fs::read_to_string(path).map_err(|err| anyhow!("failed to read keymap '{}': {}", path, err))?;
+    let keymap: Value = if is_toml(path) { toml::from_str(&raw)? } else { serde_json::from_str(&raw)? };
+
+    match keymap.get("version").and_then(Value::as_u64) {
+        Some(KEYMAP_VERSION) => {}
+        Some(version) => return Err(anyhow!("unsupported keymap version '{}', expected {}", version, KEYMAP_VERSION)),
+        None => return Err(anyhow!("keymap '{}' is missing the 'version' field", path)),
+    }
+
+    match keymap.get("mappings") {
+        Some(Value::Array(mappings)) => Ok(mappings.clone()),
+        Some(_) => Err(anyhow!("'mappings' must be a list")),
+        None => Ok(vec![]),
+    }
+}
+
+fn is_toml(path: &str) -> bool {
+    Path::new(path).extension().map_or(false, |ext| ext == "toml")
+}
+
+/// A keymap entry converted to python, the remaining fields are passed on as options.
+pub struct KeymapEntry<'py> {
+    pub from: &'py PyAny,
+    pub to: Option<&'py PyAny>,
+    pub kind: String,
+    pub options: &'py PyDict,
+}
+
+impl<'py> KeymapEntry<'py> {
+    pub fn from_json(py: Python<'py>, entry: &Value) -> PyResult<Self> {
+        let options: &PyDict = pythonize::pythonize(py, entry)?
+            .into_ref(py)
+            .downcast()
+            .map_err(|_| PyRuntimeError::new_err("keymap entries must be tables"))?;
+
+        let from = take_item(options, "from")?.ok_or_else(|| PyRuntimeError::new_err("missing 'from'"))?;
+        let to = take_item(options, "to")?;
+        let kind = match take_item(options, "kind")? {
+            Some(kind) => {
+                kind.extract::<String>().map_err(|_| PyRuntimeError::new_err("'kind' must be of type 'string'"))?
+            }
+            None => "sequence".to_string(),
+        };
+        Ok(KeymapEntry { from, to, kind, options })
+    }
+
+    pub fn to(&self) -> PyResult<&'py PyAny> {
+        self.to.ok_or_else(|| PyRuntimeError::new_err("missing 'to'"))
+    }
+}
+
+fn take_item<'py>(dict: &'py PyDict, key: &str) -> PyResult<Option<&'py PyAny>> {
+    let item = dict.get_item(key)?;
+    if item.is_some() {
+        dict.del_item(key)?;
+    }
+    Ok(item)
+}
+
+/// Converts a mapping description to a keymap entry, callbacks can't be stored and yield `None`.
+pub fn to_keymap_entry(info: &PyDict) -> PyResult<Option<Value>> {
+    if let Some(kind) = info.get_item("kind")? {
+        if kind.extract::<String>()? == "callback" {
+            return Ok(None);
+        }
+    }
+    // TOML has no null value, unset fields are left out instead
+    if let Some(to) = info.get_item("to")? {
+        if to.is_none() {
+            info.del_item("to")?;
+        }
+    }
+    Ok(Some(pythonize::depythonize(info)?))
+}
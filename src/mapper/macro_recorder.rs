@@ -0,0 +1,109 @@
+use crate::python::*;
+use crate::*;
+use std::time::Instant;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MacroTiming {
+    // waits between events as long as they were apart while recording
+    Preserve,
+    // sends all events at once
+    Compress,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MacroAction {
+    // starts recording into the register, or stops recording if already recording
+    Record(u32),
+    Replay(u32, MacroTiming),
+}
+
+impl MacroAction {
+    pub fn describe(&self, info: &PyDict) -> PyResult<()> {
+        match self {
+            MacroAction::Record(register) => {
+                info.set_item("kind", "record")?;
+                info.set_item("register", register)?;
+            }
+            MacroAction::Replay(register, timing) => {
+                info.set_item("kind", "replay")?;
+                info.set_item("register", register)?;
+                info.set_item("timing", format_macro_timing(*timing))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Events a mapper emitted, captured into numbered registers to be replayed later on.
+#[derive(Default)]
+pub struct MacroRecorder {
+    // events along with the time they were emitted at, relative to the start of the recording
+    registers: HashMap<u32, Vec<(Duration, EvdevInputEvent)>>,
+    recording: Option<(u32, Instant)>,
+}
+
+impl MacroRecorder {
+    /// Starts recording into a register, replacing its previous contents.
+    pub fn start(&mut self, register: u32) {
+        self.registers.insert(register, vec![]);
+        self.recording = Some((register, Instant::now()));
+    }
+
+    /// Stops recording, returns the register that was recorded into.
+    pub fn stop(&mut self) -> Option<u32> {
+        self.recording.take().map(|(register, _)| register)
+    }
+
+    pub fn recording(&self) -> Option<u32> {
+        self.recording.map(|(register, _)| register)
+    }
+
+    pub fn record(&mut self, ev: &EvdevInputEvent) {
+        if let Some((register, start)) = self.recording {
+            self.registers.entry(register).or_default().push((start.elapsed(), ev.clone()));
+        }
+    }
+
+    /// The events of a register along with the delay before each of them.
+    pub fn playback(&self, register: u32, timing: MacroTiming) -> Option<Vec<(Duration, EvdevInputEvent)>> {
+        let events = self.registers.get(&register)?;
+        let mut prev = events.first().map(|(at, _)| *at).unwrap_or_default();
+        let playback = events
+            .iter()
+            .map(|(at, ev)| {
+                let delay = match timing {
+                    MacroTiming::Preserve => at.saturating_sub(prev),
+                    MacroTiming::Compress => Duration::ZERO,
+                };
+                prev = *at;
+                (delay, ev.clone())
+            })
+            .collect();
+        Some(playback)
+    }
+}
+
+pub fn parse_macro_register(options: &HashMap<&str, &PyAny>) -> PyResult<u32> {
+    match options.get("register") {
+        Some(register) => {
+            register.extract::<u32>().map_err(|_| PyRuntimeError::new_err("'register' must be of type 'int'"))
+        }
+        None => Ok(0),
+    }
+}
+
+pub fn parse_macro_timing(options: &HashMap<&str, &PyAny>) -> PyResult<MacroTiming> {
+    match options.get("timing").map(|timing| timing.extract::<String>()) {
+        None => Ok(MacroTiming::Preserve),
+        Some(Ok(timing)) if timing == "preserve" => Ok(MacroTiming::Preserve),
+        Some(Ok(timing)) if timing == "compress" => Ok(MacroTiming::Compress),
+        Some(_) => Err(PyRuntimeError::new_err("'timing' must be one of: 'preserve', 'compress'")),
+    }
+}
+
+fn format_macro_timing(timing: MacroTiming) -> &'static str {
+    match timing {
+        MacroTiming::Preserve => "preserve",
+        MacroTiming::Compress => "compress",
+    }
+}
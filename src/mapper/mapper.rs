@@ -3,12 +3,14 @@ use super::*;
 use crate::mapper::mapping_functions::*;
 use crate::mapper::{RuntimeAction, RuntimeKeyAction};
 use crate::python::*;
+use crate::window::{watch_active_window, ActiveWindowInfo, WindowCondition};
 use crate::xkb::XKBTransformer;
 use crate::xkb_transformer_registry::{TransformerParams, XKB_TRANSFORMER_REGISTRY};
 use crate::*;
 use futures::executor::block_on;
 use tokio::sync::mpsc::Sender;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, MutexGuard};
+use tokio::task::JoinHandle;
 
 use ApplicationError::TooManyEvents;
 
@@ -17,11 +19,908 @@ struct State {
     transformer: Arc<XKBTransformer>,
     prev: HashMap<Uuid, Arc<dyn LinkSrc>>,
     next: HashMap<Uuid, Arc<dyn LinkDst>>,
+    // the mappings set on the mapper itself
+    layer: Layer,
+    // snapshots bound to window conditions, the first one matching the active window is used instead of `layer`
+    bound_layers: Vec<(WindowCondition, Layer)>,
+    active_layer: Option<usize>,
+    window: Option<ActiveWindowInfo>,
+    window_watch: Option<JoinHandle<()>>,
+    // keys with an ongoing tap sequence
+    tap_states: HashMap<Key, TapState>,
+    // the key sequence being typed and the keys buffered meanwhile
+    sequence_path: Vec<KeyClickActionWithMods>,
+    sequence_buffer: Vec<EvdevInputEvent>,
+    // modifier state from before the buffered events, restored when they are replayed
+    sequence_modifiers: Arc<KeyModifierState>,
+    // keys whose press completed a sequence, their release is swallowed
+    sequence_swallowed: HashSet<Key>,
+    leader: Option<KeyClickActionWithMods>,
+    repeat: Repeat,
+    // the key that currently generates repeat events
+    repeat_key: Option<Key>,
+    // keys held down by toggle actions
+    latched: HashSet<Key>,
+    macros: MacroRecorder,
+    // keys a timed replay pressed and hasn't released yet
+    replayed_keys: HashSet<Key>,
+    // keys held down for motion mappings
+    motion: MotionState,
+    timers: Timers<TimerId>,
+    modifiers: Arc<KeyModifierState>,
+}
+
+// everything a snapshot captures
+#[derive(Clone, Default)]
+struct Layer {
     mappings: Mappings,
+    // modifier-agnostic mappings keyed by the ignored modifiers, most specific (fewest ignored) first
+    ignored_mappings: Vec<(KeyModifierFlags, Mappings)>,
+    // mappings that only apply while the active window matches, ordered like `ignored_mappings`
+    window_mappings: Vec<(WindowCondition, KeyModifierFlags, Mappings)>,
+    // multi-tap and long-press triggers
+    key_patterns: HashMap<KeyClickActionWithMods, KeyPatterns>,
+    // key sequence triggers
+    sequences: KeyTrie<KeyClickActionWithMods, SequenceMapping>,
+    repeat_overrides: HashMap<KeyClickActionWithMods, Repeat>,
+    turbo: HashMap<KeyClickActionWithMods, TurboMapping>,
+    motion: HashMap<KeyClickActionWithMods, MotionMapping>,
     fallback_handler: Option<Arc<PyObject>>,
     relative_handler: Option<Arc<PyObject>>,
     absolute_handler: Option<Arc<PyObject>>,
-    modifiers: Arc<KeyModifierState>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum TimerId {
+    Tap(Key),
+    Sequence,
+    Repeat,
+    Turbo(Key),
+    Motion,
+    Replay,
+}
+
+#[derive(Clone, PartialEq)]
+struct TurboMapping {
+    // a full click of the target
+    action: RuntimeAction,
+    to: KeyClickActionWithMods,
+    interval: Duration,
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Repeat {
+    // repeat events of the source device are handled like any other event
+    #[default]
+    Passthrough,
+    Disabled,
+    // source repeat events are dropped, the mapper generates its own
+    Generated { delay: Duration, interval: Duration },
+}
+
+#[derive(Clone, PartialEq)]
+struct SequenceMapping {
+    action: RuntimeAction,
+    timeout: Duration,
+}
+
+// the actions a click trigger runs on press and on release
+#[derive(Clone, PartialEq)]
+struct ClickActions {
+    down: RuntimeAction,
+    up: RuntimeAction,
+}
+
+impl ClickActions {
+    fn from_mappings(mappings: Vec<Mapping>) -> Self {
+        let mut actions = ClickActions { down: RuntimeAction::NOP, up: RuntimeAction::NOP };
+        for (from, to) in mappings {
+            match from.value {
+                TYPE_DOWN => actions.down = to,
+                TYPE_UP => actions.up = to,
+                _ => {}
+            }
+        }
+        actions
+    }
+
+    fn action(&self) -> RuntimeAction {
+        click_action(Some(&self.down), Some(&self.up))
+    }
+}
+
+#[derive(Clone)]
+struct KeyPatterns {
+    // tap count -> action
+    taps: HashMap<u32, ClickActions>,
+    tap_window: Duration,
+    hold: Option<(Duration, ClickActions)>,
+}
+
+impl Default for KeyPatterns {
+    fn default() -> Self {
+        Self { taps: HashMap::new(), tap_window: Duration::from_millis(200), hold: None }
+    }
+}
+
+#[derive(Clone)]
+struct TapState {
+    trigger: KeyClickActionWithMods,
+    count: u32,
+    held: bool,
+    // set once the sequence resolved while the key is still held, runs when it is released
+    release: Option<RuntimeAction>,
+}
+
+impl Layer {
+    fn mappings_mut(&mut self, ignored: &KeyModifierFlags, when: Option<&WindowCondition>) -> &mut Mappings {
+        if let Some(when) = when {
+            let pos = match self.window_mappings.iter().position(|(condition, mask, _)| condition == when && mask == ignored) {
+                Some(pos) => pos,
+                None => {
+                    let pos = self
+                        .window_mappings
+                        .iter()
+                        .position(|(_, mask, _)| mask.count() > ignored.count())
+                        .unwrap_or(self.window_mappings.len());
+                    self.window_mappings.insert(pos, (when.clone(), *ignored, Mappings::new()));
+                    pos
+                }
+            };
+            return &mut self.window_mappings[pos].2;
+        }
+
+        if ignored.is_empty() {
+            return &mut self.mappings;
+        }
+
+        let pos = match self.ignored_mappings.iter().position(|(mask, _)| mask == ignored) {
+            Some(pos) => pos,
+            None => {
+                let pos = self
+                    .ignored_mappings
+                    .iter()
+                    .position(|(mask, _)| mask.count() > ignored.count())
+                    .unwrap_or(self.ignored_mappings.len());
+                self.ignored_mappings.insert(pos, (*ignored, Mappings::new()));
+                pos
+            }
+        };
+        &mut self.ignored_mappings[pos].1
+    }
+
+    fn repeat_for(&self, action: &KeyActionWithMods, default: Repeat) -> Repeat {
+        let click = KeyClickActionWithMods::new_with_mods(action.key, action.modifiers);
+        if let Some(repeat) = self.repeat_overrides.get(&click) {
+            return *repeat;
+        }
+        self.ignored_mappings
+            .iter()
+            .find_map(|(mask, _)| {
+                self.repeat_overrides.get(&KeyClickActionWithMods { modifiers: click.modifiers.without(mask), ..click })
+            })
+            .copied()
+            .unwrap_or(default)
+    }
+
+    // window specific mappings come first, then exact mappings, then the ones ignoring the fewest modifiers,
+    // wildcards come last
+    fn tables(&self) -> impl Iterator<Item = MappingTable<'_>> {
+        let window = self.window_mappings.iter().map(|(when, ignored, mappings)| MappingTable {
+            when: Some(when),
+            ignored: *ignored,
+            mappings,
+        });
+        let exact =
+            std::iter::once(MappingTable { when: None, ignored: KeyModifierFlags::new(), mappings: &self.mappings });
+        let ignored = self.ignored_mappings.iter().map(|(ignored, mappings)| MappingTable {
+            when: None,
+            ignored: *ignored,
+            mappings,
+        });
+        window.chain(exact).chain(ignored)
+    }
+
+    fn lookup_table(&self, action: &KeyActionWithMods, window: Option<&ActiveWindowInfo>) -> Option<MappingTable<'_>> {
+        self.tables()
+            .filter(|table| table.when.map_or(true, |when| when.matches(window)))
+            .find(|table| table.get(action).is_some())
+    }
+
+    fn lookup(&self, action: &KeyActionWithMods, window: Option<&ActiveWindowInfo>) -> Option<&RuntimeAction> {
+        self.lookup_table(action, window).and_then(|table| table.get(action))
+    }
+
+    fn table(&self, when: Option<&WindowCondition>, ignored: &KeyModifierFlags) -> Option<&Mappings> {
+        match when {
+            Some(when) => self
+                .window_mappings
+                .iter()
+                .find(|(condition, mask, _)| condition == when && mask == ignored)
+                .map(|(_, _, mappings)| mappings),
+            None if ignored.is_empty() => Some(&self.mappings),
+            None => self.ignored_mappings.iter().find(|(mask, _)| mask == ignored).map(|(_, mappings)| mappings),
+        }
+    }
+
+    // drops the tables and patterns left empty after removing mappings
+    fn prune(&mut self) {
+        self.ignored_mappings.retain(|(_, mappings)| !mappings.is_empty());
+        self.window_mappings.retain(|(_, _, mappings)| !mappings.is_empty());
+        self.key_patterns.retain(|_, patterns| !patterns.taps.is_empty() || patterns.hold.is_some());
+    }
+
+    fn slots(&self) -> Vec<Slot> {
+        let mut acc = vec![];
+        for table in self.tables() {
+            let triggers: HashSet<KeyClickActionWithMods> = table
+                .mappings
+                .keys()
+                .map(|action| KeyClickActionWithMods::new_with_mods(action.key, action.modifiers))
+                .collect();
+            acc.extend(triggers.into_iter().map(|trigger| Slot::Table {
+                when: table.when.cloned(),
+                ignored: table.ignored,
+                trigger,
+            }));
+        }
+        for (trigger, patterns) in self.key_patterns.iter() {
+            acc.extend(patterns.taps.keys().map(|taps| Slot::Taps(*trigger, *taps)));
+            if patterns.hold.is_some() {
+                acc.push(Slot::Hold(*trigger));
+            }
+        }
+        acc.extend(self.sequences.entries().into_iter().map(|(seq, _)| Slot::Sequence(seq)));
+        acc.extend(self.turbo.keys().map(|trigger| Slot::Turbo(*trigger)));
+        acc.extend(self.motion.keys().map(|trigger| Slot::Motion(*trigger)));
+        for (slot, handler) in [
+            (Slot::Fallback, &self.fallback_handler),
+            (Slot::Relative, &self.relative_handler),
+            (Slot::Absolute, &self.absolute_handler),
+        ] {
+            if handler.is_some() {
+                acc.push(slot);
+            }
+        }
+        acc
+    }
+
+    // what the slot maps to, `None` if it isn't mapped
+    fn slot_value(&self, slot: &Slot) -> Option<SlotValue<'_>> {
+        match slot {
+            Slot::Table { when, ignored, trigger } => {
+                let mappings = self.table(when.as_ref(), ignored)?;
+                let states = [TYPE_UP, TYPE_DOWN, TYPE_REPEAT].map(|value| mappings.get(&trigger.to_key_action(value)));
+                if states.iter().all(Option::is_none) {
+                    return None;
+                }
+                let repeat = if when.is_none() { self.repeat_overrides.get(trigger) } else { None };
+                Some(SlotValue::Table(states, repeat))
+            }
+            Slot::Taps(trigger, taps) => {
+                let patterns = self.key_patterns.get(trigger)?;
+                Some(SlotValue::Taps(patterns.taps.get(taps)?, patterns.tap_window))
+            }
+            Slot::Hold(trigger) => Some(SlotValue::Hold(self.key_patterns.get(trigger)?.hold.as_ref()?)),
+            Slot::Sequence(seq) => Some(SlotValue::Sequence(self.sequences.get(seq)?.value()?)),
+            Slot::Turbo(trigger) => Some(SlotValue::Turbo(self.turbo.get(trigger)?)),
+            Slot::Motion(trigger) => Some(SlotValue::Motion(self.motion.get(trigger)?)),
+            Slot::Fallback => self.fallback_handler.as_ref().map(|handler| SlotValue::Handler(Arc::as_ptr(handler))),
+            Slot::Relative => self.relative_handler.as_ref().map(|handler| SlotValue::Handler(Arc::as_ptr(handler))),
+            Slot::Absolute => self.absolute_handler.as_ref().map(|handler| SlotValue::Handler(Arc::as_ptr(handler))),
+        }
+    }
+
+    // makes the slot map to what it maps to in `from`, unmapping it if it isn't mapped there
+    fn copy_slot(&mut self, from: &Layer, slot: &Slot) {
+        match slot {
+            Slot::Table { when, ignored, trigger } => {
+                let source = from.table(when.as_ref(), ignored);
+                let target = self.mappings_mut(ignored, when.as_ref());
+                for value in [TYPE_UP, TYPE_DOWN, TYPE_REPEAT] {
+                    let action = trigger.to_key_action(value);
+                    match source.and_then(|source| source.get(&action)) {
+                        Some(to) => target.insert(action, to.clone()),
+                        None => target.remove(&action),
+                    };
+                }
+                if when.is_none() {
+                    match from.repeat_overrides.get(trigger) {
+                        Some(repeat) => self.repeat_overrides.insert(*trigger, *repeat),
+                        None => self.repeat_overrides.remove(trigger),
+                    };
+                }
+            }
+            Slot::Taps(trigger, taps) => {
+                let source = from
+                    .key_patterns
+                    .get(trigger)
+                    .and_then(|patterns| patterns.taps.get(taps).map(|actions| (actions, patterns.tap_window)));
+                match source {
+                    Some((actions, tap_window)) => {
+                        let patterns = self.key_patterns.entry(*trigger).or_default();
+                        patterns.taps.insert(*taps, actions.clone());
+                        patterns.tap_window = tap_window;
+                    }
+                    None => {
+                        if let Some(patterns) = self.key_patterns.get_mut(trigger) {
+                            patterns.taps.remove(taps);
+                        }
+                    }
+                }
+            }
+            Slot::Hold(trigger) => match from.key_patterns.get(trigger).and_then(|patterns| patterns.hold.clone()) {
+                Some(hold) => self.key_patterns.entry(*trigger).or_default().hold = Some(hold),
+                None => {
+                    if let Some(patterns) = self.key_patterns.get_mut(trigger) {
+                        patterns.hold = None;
+                    }
+                }
+            },
+            Slot::Sequence(seq) => match from.sequences.get(seq).and_then(|node| node.value()) {
+                Some(mapping) => self.sequences.insert(seq, mapping.clone()),
+                None => {
+                    self.sequences.remove(seq);
+                }
+            },
+            Slot::Turbo(trigger) => match from.turbo.get(trigger) {
+                Some(turbo) => {
+                    self.turbo.insert(*trigger, turbo.clone());
+                }
+                None => {
+                    self.turbo.remove(trigger);
+                }
+            },
+            Slot::Motion(trigger) => match from.motion.get(trigger) {
+                Some(motion) => {
+                    self.motion.insert(*trigger, *motion);
+                }
+                None => {
+                    self.motion.remove(trigger);
+                }
+            },
+            Slot::Fallback => self.fallback_handler = from.fallback_handler.clone(),
+            Slot::Relative => self.relative_handler = from.relative_handler.clone(),
+            Slot::Absolute => self.absolute_handler = from.absolute_handler.clone(),
+        }
+    }
+
+    // the slot's mappings along with the formatted trigger, formatted the same as `Mapper.mappings()`
+    fn describe_slot<'py>(
+        &self,
+        py: Python<'py>,
+        slot: &Slot,
+        transformer: &XKBTransformer,
+    ) -> PyResult<Vec<(String, &'py PyDict)>> {
+        let from = slot.name(transformer)?;
+        let mut acc = vec![];
+        match slot {
+            Slot::Table { when, ignored, trigger } => {
+                if let Some(mappings) = self.table(when.as_ref(), ignored) {
+                    let table = MappingTable { when: when.as_ref(), ignored: *ignored, mappings };
+                    for (_, value) in table.triggers().into_iter().filter(|(other, _)| other == trigger) {
+                        acc.push(table.describe(py, trigger, value, transformer)?);
+                    }
+                }
+            }
+            Slot::Taps(trigger, taps) => {
+                if let Some(actions) = self.key_patterns.get(trigger).and_then(|patterns| patterns.taps.get(taps)) {
+                    let info = mapping_info(py, &from, &actions.action())?;
+                    info.set_item("taps", taps)?;
+                    acc.push((from, info));
+                }
+            }
+            Slot::Hold(trigger) => {
+                if let Some((delay, actions)) =
+                    self.key_patterns.get(trigger).and_then(|patterns| patterns.hold.as_ref())
+                {
+                    let info = mapping_info(py, &from, &actions.action())?;
+                    info.set_item("hold_ms", delay.as_millis() as u64)?;
+                    acc.push((from, info));
+                }
+            }
+            Slot::Sequence(seq) => {
+                if let Some(mapping) = self.sequences.get(seq).and_then(|node| node.value()) {
+                    let keys = seq.iter().map(|key| format_trigger(key, transformer)).collect::<PyResult<Vec<_>>>()?;
+                    acc.push((from, mapping_info(py, keys, &mapping.action)?));
+                }
+            }
+            Slot::Turbo(trigger) => {
+                if let Some(turbo) = self.turbo.get(trigger) {
+                    let info = mapping_info(py, &from, &turbo.action)?;
+                    info.set_item("rate_hz", 1.0 / turbo.interval.as_secs_f64())?;
+                    acc.push((from, info));
+                }
+            }
+            Slot::Motion(trigger) => {
+                if let Some(motion) = self.motion.get(trigger) {
+                    let info = motion_info(py, &from, motion)?;
+                    acc.push((from, info));
+                }
+            }
+            Slot::Fallback | Slot::Relative | Slot::Absolute => {
+                let handler = match slot {
+                    Slot::Fallback => &self.fallback_handler,
+                    Slot::Relative => &self.relative_handler,
+                    _ => &self.absolute_handler,
+                };
+                if let Some(handler) = handler {
+                    let action = RuntimeAction::PythonCallback(Default::default(), handler.clone());
+                    let info = mapping_info(py, py.None(), &action)?;
+                    info.set_item("handler", &from)?;
+                    acc.push((from, info));
+                }
+            }
+        }
+        Ok(acc)
+    }
+
+    // takes every slot of `other` that isn't mapped the same way already
+    fn merge(&self, other: &Layer, on_conflict: Conflict, transformer: &XKBTransformer) -> PyResult<Layer> {
+        let mut merged = self.clone();
+        for slot in other.slots() {
+            let ours = self.slot_value(&slot);
+            if ours.is_some() && ours != other.slot_value(&slot) {
+                match on_conflict {
+                    Conflict::Error => {
+                        return Err(PyRuntimeError::new_err(format!(
+                            "conflicting mappings for '{}', pass on_conflict=\"keep\" or on_conflict=\"replace\" to resolve",
+                            slot.name(transformer)?
+                        )))
+                    }
+                    Conflict::Keep => continue,
+                    Conflict::Replace => {}
+                }
+            }
+            merged.copy_slot(other, &slot);
+        }
+        merged.prune();
+        Ok(merged)
+    }
+
+    // callbacks and handlers can't be stored and are left out
+    fn keymap_entries(&self, py: Python, transformer: &XKBTransformer) -> PyResult<Vec<serde_json::Value>> {
+        let mut acc = vec![];
+
+        for table in self.tables() {
+            for (trigger, value) in table.triggers() {
+                let (_, info) = table.describe(py, &trigger, value, transformer)?;
+                if value.is_none() {
+                    set_key_target(info, table.get(&trigger.to_key_action(TYPE_UP)), transformer)?;
+                    if let (None, Some(repeat)) = (table.when, self.repeat_overrides.get(&trigger)) {
+                        info.set_item("repeat", repeat_info(py, repeat)?)?;
+                    }
+                }
+                acc.extend(to_keymap_entry(info)?);
+            }
+        }
+
+        for (trigger, patterns) in self.key_patterns.iter() {
+            let from = format_trigger(trigger, transformer)?;
+            for (taps, actions) in patterns.taps.iter() {
+                let info = mapping_info(py, &from, &actions.action())?;
+                set_key_target(info, Some(&actions.up), transformer)?;
+                info.set_item("taps", taps)?;
+                if patterns.tap_window != KeyPatterns::default().tap_window {
+                    info.set_item("window_ms", patterns.tap_window.as_millis() as u64)?;
+                }
+                acc.extend(to_keymap_entry(info)?);
+            }
+            if let Some((delay, actions)) = patterns.hold.as_ref() {
+                let info = mapping_info(py, &from, &actions.action())?;
+                set_key_target(info, Some(&actions.up), transformer)?;
+                info.set_item("hold_ms", delay.as_millis() as u64)?;
+                acc.extend(to_keymap_entry(info)?);
+            }
+        }
+
+        for (seq, mapping) in self.sequences.entries() {
+            let from = seq.iter().map(|key| format_trigger(key, transformer)).collect::<PyResult<Vec<_>>>()?;
+            let info = mapping_info(py, from, &mapping.action)?;
+            if mapping.timeout != Duration::from_millis(1000) {
+                info.set_item("timeout_ms", mapping.timeout.as_millis() as u64)?;
+            }
+            acc.extend(to_keymap_entry(info)?);
+        }
+
+        for (trigger, turbo) in self.turbo.iter() {
+            let info = mapping_info(py, format_trigger(trigger, transformer)?, &turbo.action)?;
+            info.set_item("kind", "key")?;
+            info.set_item("to", format_trigger(&turbo.to, transformer)?)?;
+            info.set_item("rate_hz", 1.0 / turbo.interval.as_secs_f64())?;
+            acc.extend(to_keymap_entry(info)?);
+        }
+
+        for (trigger, motion) in self.motion.iter() {
+            let info = motion_info(py, format_trigger(trigger, transformer)?, motion)?;
+            acc.extend(to_keymap_entry(info)?);
+        }
+
+        Ok(acc)
+    }
+}
+
+// mappings to a single key hold the target down for as long as the trigger is held, they are stored as such rather
+// than as a sequence, the release restoring the trigger's modifiers gives them away
+fn set_key_target(info: &PyDict, up: Option<&RuntimeAction>, transformer: &XKBTransformer) -> PyResult<()> {
+    if let Some(RuntimeAction::ActionSequence(seq)) = up {
+        if let (
+            Some(RuntimeKeyAction::KeyAction(action)),
+            Some(RuntimeKeyAction::ReleaseRestoreModifiers(_, to_modifiers, TYPE_DOWN)),
+        ) = (seq.first(), seq.last())
+        {
+            if action.value == TYPE_UP {
+                info.set_item("kind", "key")?;
+                info.set_item(
+                    "to",
+                    format_key_trigger(&action.key, None, to_modifiers, transformer).map_err(err_to_py)?,
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn format_trigger(trigger: &KeyClickActionWithMods, transformer: &XKBTransformer) -> PyResult<String> {
+    format_key_trigger(&trigger.key, None, &trigger.modifiers, transformer).map_err(err_to_py)
+}
+
+fn motion_info<'py>(py: Python<'py>, from: impl ToPyObject, motion: &MotionMapping) -> PyResult<&'py PyDict> {
+    let info = PyDict::new(py);
+    info.set_item("from", from)?;
+    info.set_item("to", py.None())?;
+    motion.describe(info)?;
+    Ok(info)
+}
+
+fn repeat_info(py: Python, repeat: &Repeat) -> PyResult<PyObject> {
+    match repeat {
+        Repeat::Passthrough => Ok(py.None()),
+        Repeat::Disabled => Ok(false.to_object(py)),
+        Repeat::Generated { delay, interval } => {
+            let info = PyDict::new(py);
+            info.set_item("delay", delay.as_millis() as u64)?;
+            info.set_item("rate", 1.0 / interval.as_secs_f64())?;
+            Ok(info.to_object(py))
+        }
+    }
+}
+
+// a single trigger of a layer, the unit layers are merged and compared in
+#[derive(Clone, PartialEq)]
+enum Slot {
+    // all states of a trigger in one mapping table
+    Table { when: Option<WindowCondition>, ignored: KeyModifierFlags, trigger: KeyClickActionWithMods },
+    Taps(KeyClickActionWithMods, u32),
+    Hold(KeyClickActionWithMods),
+    Sequence(Vec<KeyClickActionWithMods>),
+    Turbo(KeyClickActionWithMods),
+    Motion(KeyClickActionWithMods),
+    Fallback,
+    Relative,
+    Absolute,
+}
+
+impl Slot {
+    fn name(&self, transformer: &XKBTransformer) -> PyResult<String> {
+        Ok(match self {
+            Slot::Table { ignored, trigger, .. } if *ignored == KeyModifierFlags::all() => {
+                format!("*{}", format_trigger(trigger, transformer)?)
+            }
+            Slot::Table { trigger, .. }
+            | Slot::Taps(trigger, _)
+            | Slot::Hold(trigger)
+            | Slot::Turbo(trigger)
+            | Slot::Motion(trigger) => format_trigger(trigger, transformer)?,
+            Slot::Sequence(seq) => {
+                seq.iter().map(|key| format_trigger(key, transformer)).collect::<PyResult<Vec<_>>>()?.join(" ")
+            }
+            Slot::Fallback => "fallback".to_string(),
+            Slot::Relative => "relative".to_string(),
+            Slot::Absolute => "absolute".to_string(),
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum SlotValue<'a> {
+    // up, down and repeat
+    Table([Option<&'a RuntimeAction>; 3], Option<&'a Repeat>),
+    Taps(&'a ClickActions, Duration),
+    Hold(&'a (Duration, ClickActions)),
+    Sequence(&'a SequenceMapping),
+    Turbo(&'a TurboMapping),
+    Motion(&'a MotionMapping),
+    // handlers are only equal to themselves
+    Handler(*const PyObject),
+}
+
+#[derive(Clone, Copy)]
+enum Conflict {
+    Error,
+    Keep,
+    Replace,
+}
+
+#[derive(Clone, Copy)]
+struct MappingTable<'a> {
+    when: Option<&'a WindowCondition>,
+    ignored: KeyModifierFlags,
+    mappings: &'a Mappings,
+}
+
+impl<'a> MappingTable<'a> {
+    fn get(&self, action: &KeyActionWithMods) -> Option<&'a RuntimeAction> {
+        self.mappings.get(&KeyActionWithMods { modifiers: action.modifiers.without(&self.ignored), ..*action })
+    }
+
+    // triggers mapped in all three states are listed as a single click trigger
+    fn triggers(&self) -> Vec<(KeyClickActionWithMods, Option<i32>)> {
+        let mut acc = vec![];
+        for action in self.mappings.keys() {
+            let trigger = KeyClickActionWithMods::new_with_mods(action.key, action.modifiers);
+            let is_click = (0..=2).all(|value| self.mappings.contains_key(&trigger.to_key_action(value)));
+            match (is_click, action.value) {
+                (true, TYPE_DOWN) => acc.push((trigger, None)),
+                (true, _) => {}
+                (false, value) => acc.push((trigger, Some(value))),
+            }
+        }
+        acc
+    }
+
+    // returns the formatted trigger along with the description
+    fn describe<'py>(
+        &self,
+        py: Python<'py>,
+        trigger: &KeyClickActionWithMods,
+        value: Option<i32>,
+        transformer: &XKBTransformer,
+    ) -> PyResult<(String, &'py PyDict)> {
+        let trigger = KeyClickActionWithMods { modifiers: trigger.modifiers.without(&self.ignored), ..*trigger };
+        let action = match value {
+            Some(value) => self.mappings.get(&trigger.to_key_action(value)).cloned().unwrap_or(RuntimeAction::NOP),
+            None => click_action(
+                self.mappings.get(&trigger.to_key_action(TYPE_DOWN)),
+                self.mappings.get(&trigger.to_key_action(TYPE_UP)),
+            ),
+        };
+
+        let wildcard = self.ignored == KeyModifierFlags::all();
+        let from = format!(
+            "{}{}",
+            if wildcard { "*" } else { "" },
+            format_key_trigger(&trigger.key, value, &trigger.modifiers, transformer).map_err(err_to_py)?
+        );
+        let info = mapping_info(py, &from, &action)?;
+        if !wildcard && !self.ignored.is_empty() {
+            info.set_item("ignore", modifier_names(&self.ignored))?;
+        }
+        if let Some(when) = self.when {
+            info.set_item("when", when.to_py_dict(py)?)?;
+        }
+        Ok((from, info))
+    }
+}
+
+// the target of a click trigger, the press and release actions are shown as one sequence
+fn click_action(down: Option<&RuntimeAction>, up: Option<&RuntimeAction>) -> RuntimeAction {
+    match (down, up) {
+        (Some(RuntimeAction::ActionSequence(down)), Some(RuntimeAction::ActionSequence(up))) => {
+            RuntimeAction::ActionSequence(down.iter().chain(up.iter()).cloned().collect())
+        }
+        (Some(down), _) => down.clone(),
+        (None, _) => RuntimeAction::NOP,
+    }
+}
+
+impl State {
+    fn modifier_flags(&self) -> KeyModifierFlags {
+        let mut flags = KeyModifierFlags::new();
+        flags.ctrl = self.modifiers.is_ctrl();
+        flags.alt = self.modifiers.is_alt();
+        flags.right_alt = self.modifiers.is_right_alt();
+        flags.shift = self.modifiers.is_shift();
+        flags.meta = self.modifiers.is_meta();
+        flags
+    }
+
+    // the layer events are currently mapped with
+    fn layer(&self) -> &Layer {
+        match self.active_layer {
+            Some(idx) => &self.bound_layers[idx].1,
+            None => &self.layer,
+        }
+    }
+
+    fn lookup(&self, action: &KeyActionWithMods) -> Option<&RuntimeAction> {
+        self.layer().lookup(action, self.window.as_ref())
+    }
+
+    fn repeat_for(&self, action: &KeyActionWithMods) -> Repeat {
+        self.layer().repeat_for(action, self.repeat)
+    }
+
+    fn release_latched(&mut self) {
+        let latched: Vec<Key> = self.latched.drain().collect();
+        for key in latched {
+            self.emit(key.to_input_ev(TYPE_UP));
+        }
+    }
+
+    // every event the mapper outputs goes through here so macros can record it
+    fn emit(&mut self, ev: EvdevInputEvent) {
+        self.macros.record(&ev);
+        self.next.send_all(InputEvent::Raw(ev));
+    }
+
+    // replayed events aren't recorded, otherwise replaying while recording would copy the macro into the register
+    fn emit_replayed(&mut self, ev: EvdevInputEvent) {
+        if let EventCode::EV_KEY(_) = ev.event_code {
+            let key = Key { event_code: ev.event_code };
+            if ev.value == TYPE_UP {
+                self.replayed_keys.remove(&key);
+            } else {
+                self.replayed_keys.insert(key);
+            }
+        }
+        self.next.send_all(InputEvent::Raw(ev));
+    }
+
+    // releases the keys a cancelled replay left held down
+    fn stop_replay(&mut self) {
+        self.timers.cancel(&TimerId::Replay);
+        let replayed_keys: Vec<Key> = self.replayed_keys.drain().collect();
+        for key in replayed_keys {
+            self.next.send_all(InputEvent::Raw(key.to_input_ev(TYPE_UP)));
+        }
+    }
+
+    // turbo clicking and motion keep going for as long as their trigger is held
+    fn stop_held_actions(&mut self) {
+        self.timers.cancel_matching(|id| matches!(id, TimerId::Turbo(_) | TimerId::Motion));
+        self.motion.clear();
+    }
+
+    // drops everything in progress when the mappings are swapped out
+    fn reset_pending(&mut self) {
+        self.sequence_path.clear();
+        self.sequence_buffer.clear();
+        self.stop_replay();
+        // pending tap sequences are dropped, keys that are still held get released as usual
+        self.timers.cancel_all();
+        self.repeat_key = None;
+        self.motion.clear();
+        self.release_latched();
+        self.tap_states.retain(|_, tap_state| tap_state.release.is_some());
+    }
+
+    fn set_window(&mut self, window: Option<ActiveWindowInfo>) {
+        self.window = window;
+        let active_layer = self.bound_layers.iter().position(|(condition, _)| condition.matches(self.window.as_ref()));
+        if active_layer != self.active_layer {
+            self.active_layer = active_layer;
+            self.reset_pending();
+        }
+    }
+}
+
+fn parse_trigger(
+    from: &str,
+    transformer: &XKBTransformer,
+    options: &HashMap<&str, &PyAny>,
+) -> PyResult<(ParsedKeyAction, KeyModifierFlags)> {
+    let (mut from, wildcard) = parse_key_trigger(from, Some(transformer)).map_err(|err| {
+        PyRuntimeError::new_err(format!(
+            "mapping error on the 'from' side:\n{}",
+            ApplicationError::KeyParse(err.to_string()),
+        ))
+    })?;
+
+    let ignored = match options.get("ignore") {
+        _ if wildcard => KeyModifierFlags::all(),
+        Some(ignore) => {
+            let ignore = ignore
+                .extract::<Vec<String>>()
+                .map_err(|_| PyRuntimeError::new_err("'ignore' must be of type 'string[]'"))?;
+            parse_modifier_flags(&ignore).map_err(err_to_py)?
+        }
+        None => KeyModifierFlags::new(),
+    };
+
+    match &mut from {
+        ParsedKeyAction::KeyAction(from) => from.modifiers = from.modifiers.without(&ignored),
+        ParsedKeyAction::KeyClickAction(from) => from.modifiers = from.modifiers.without(&ignored),
+        ParsedKeyAction::Action(_) | ParsedKeyAction::Toggle(_) => {}
+    }
+
+    Ok((from, ignored))
+}
+
+// generated events are at least a millisecond apart, timer intervals must not round down to zero
+const MAX_RATE_HZ: f64 = 1000.0;
+const MAX_REPEAT_DELAY_MS: f64 = 10_000.0;
+
+fn interval_from_rate(rate: f64) -> Option<Duration> {
+    if !rate.is_finite() || rate <= 0.0 || rate > MAX_RATE_HZ {
+        return None;
+    }
+    Duration::try_from_secs_f64(1.0 / rate).ok()
+}
+
+fn parse_repeat(value: &PyAny) -> PyResult<Repeat> {
+    if let Ok(false) = value.extract::<bool>() {
+        return Ok(Repeat::Disabled);
+    }
+    let options = value
+        .extract::<HashMap<String, f64>>()
+        .map_err(|_| PyRuntimeError::new_err("'repeat' must be False or of type '{ delay?: int, rate?: int }'"))?;
+
+    let delay = options.get("delay").copied().unwrap_or(250.0);
+    let rate = options.get("rate").copied().unwrap_or(30.0);
+    let delay = match delay {
+        delay if (0.0..=MAX_REPEAT_DELAY_MS).contains(&delay) => Duration::try_from_secs_f64(delay / 1000.0).ok(),
+        _ => None,
+    };
+    match (delay, interval_from_rate(rate)) {
+        (Some(delay), Some(interval)) => Ok(Repeat::Generated { delay, interval }),
+        _ => Err(PyRuntimeError::new_err(format!(
+            "'repeat' delay must be between 0 and {} and rate must be a positive number of at most {}",
+            MAX_REPEAT_DELAY_MS, MAX_RATE_HZ
+        ))),
+    }
+}
+
+fn parse_sequence_trigger(
+    from: &[String],
+    leader: Option<KeyClickActionWithMods>,
+    transformer: &XKBTransformer,
+) -> PyResult<Vec<KeyClickActionWithMods>> {
+    let mut seq = vec![];
+    for key in from.iter() {
+        let key = match key.as_str() {
+            "leader" => leader.ok_or_else(|| PyRuntimeError::new_err("no leader key set, pass 'leader' to the mapper"))?,
+            key => match parse_key_action_with_mods(key, Some(transformer)) {
+                Ok(ParsedKeyAction::KeyClickAction(key)) => key,
+                Ok(_) => {
+                    return Err(PyRuntimeError::new_err(format!(
+                        "mapping error on the 'from' side:\nexpected a key without state, got '{}'",
+                        key
+                    )))
+                }
+                Err(err) => {
+                    return Err(PyRuntimeError::new_err(format!(
+                        "mapping error on the 'from' side:\n{}",
+                        ApplicationError::KeyParse(err.to_string()),
+                    )))
+                }
+            },
+        };
+        if key.key.is_modifier() {
+            return Err(PyRuntimeError::new_err("modifier keys cannot be part of a sequence"));
+        }
+        seq.push(key);
+    }
+    Ok(seq)
+}
+
+fn parse_click_trigger(raw: &str, transformer: &XKBTransformer) -> PyResult<KeyClickActionWithMods> {
+    match parse_key_action_with_mods(raw, Some(transformer)) {
+        Ok(ParsedKeyAction::KeyClickAction(from)) => Ok(from),
+        Ok(_) => Err(ApplicationError::NonButton.into_py()),
+        Err(err) => Err(PyRuntimeError::new_err(format!(
+            "mapping error on the 'from' side:\n{}",
+            ApplicationError::KeyParse(err.to_string()),
+        ))),
+    }
+}
+
+fn parse_toggle_key(raw: &str, transformer: &XKBTransformer) -> PyResult<Key> {
+    match parse_key_action_with_mods(raw, Some(transformer)) {
+        Ok(ParsedKeyAction::KeyClickAction(action)) if action.modifiers.is_empty() => Ok(action.key),
+        Ok(_) => Err(PyRuntimeError::new_err(format!("expected a single key without modifiers, got '{}'", raw))),
+        Err(err) => Err(ApplicationError::KeyParse(err.to_string()).into_py()),
+    }
 }
 
 #[pyclass]
@@ -50,9 +949,27 @@ impl Mapper {
             .get(&TransformerParams::new(kbd_model, kbd_layout, kbd_variant, kbd_options))
             .map_err(err_to_py)?;
 
+        let leader = match options.get("leader") {
+            Some(leader) => {
+                let leader =
+                    leader.extract::<String>().map_err(|_| PyRuntimeError::new_err("'leader' must be of type 'string'"))?;
+                match parse_key_action_with_mods(&leader, Some(&transformer)) {
+                    Ok(ParsedKeyAction::KeyClickAction(leader)) => Some(leader),
+                    Ok(_) => return Err(PyRuntimeError::new_err("'leader' must be a single key")),
+                    Err(err) => return Err(ApplicationError::KeyParse(err.to_string()).into_py()),
+                }
+            }
+            None => None,
+        };
+
+        let repeat = match options.get("repeat") {
+            Some(repeat) => parse_repeat(repeat)?,
+            None => Repeat::Passthrough,
+        };
+
         let id = Uuid::new_v4();
         let (ev_tx, mut ev_rx) = tokio::sync::mpsc::channel(64);
-        let state = Arc::new(Mutex::new(State { transformer, ..Default::default() }));
+        let state = Arc::new(Mutex::new(State { transformer, leader, repeat, ..Default::default() }));
         let link = Arc::new(MapperLink { id, ev_tx: ev_tx.clone(), state: state.clone() });
 
         {
@@ -71,47 +988,43 @@ impl Mapper {
         Ok(Self { id, link, ev_tx, state })
     }
 
-    pub fn map(&mut self, py: Python, from: String, to: PyObject) -> PyResult<()> {
+    #[pyo3(signature = (from, to, **kwargs))]
+    pub fn map(&mut self, py: Python, from: String, to: PyObject, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
         let mut state = self.state.blocking_lock();
-        let from = parse_key_action_with_mods(&from, Some(&state.transformer)).map_err(|err| {
-            PyRuntimeError::new_err(format!(
-                "mapping error on the 'from' side:\n{}",
-                ApplicationError::KeyParse(err.to_string()),
-            ))
-        })?;
+        let (from, ignored) = parse_trigger(&from, &state.transformer, &options)?;
 
-        if let Ok(to) = to.extract::<String>(py) {
+        let mappings = if let Ok(to) = to.extract::<String>(py) {
             let to = parse_key_sequence(&to, Some(&state.transformer)).map_err(|err| {
                 PyRuntimeError::new_err(format!(
                     "mapping error on the 'to' side:\n{}",
                     ApplicationError::KeySequenceParse(err.to_string()),
                 ))
             })?;
+            key_mappings(from.clone(), to)?
+        } else if to.as_ref(py).is_callable() {
+            callback_mappings(from.clone(), to)?
+        } else {
+            return Err(ApplicationError::NotCallable.into());
+        };
 
-            drop(state);
-            self._map_key(from, to)?;
-            return Ok(());
-        }
-
-        let is_callable = to.as_ref(py).is_callable();
-
-        if is_callable {
-            drop(state);
-            self._map_callback(from, to)?;
-            return Ok(());
-        }
-
-        Err(ApplicationError::NotCallable.into())
+        drop(state);
+        self._map(from, mappings, ignored, &options)
     }
 
-    pub fn map_key(&mut self, from: String, to: String) -> PyResult<()> {
+    #[pyo3(signature = (from, to, **kwargs))]
+    pub fn map_key(&mut self, from: String, to: String, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
         let mut state = self.state.blocking_lock();
-        let from = parse_key_action_with_mods(&from, Some(&state.transformer)).map_err(|err| {
-            PyRuntimeError::new_err(format!(
-                "mapping error on the 'from' side:\n{}",
-                ApplicationError::KeyParse(err.to_string()),
-            ))
-        })?;
+        let (from, ignored) = parse_trigger(&from, &state.transformer, &options)?;
 
         let to = parse_key_action_with_mods(&to, Some(&state.transformer)).map_err(|err| {
             PyRuntimeError::new_err(format!(
@@ -120,17 +1033,216 @@ impl Mapper {
             ))
         })?;
 
+        let mappings = key_mappings(from.clone(), vec![to])?;
         drop(state);
-        self._map_key(from, vec![to])?;
+        self._map(from, mappings, ignored, &options)
+    }
+
+    #[pyo3(signature = (from, to, **kwargs))]
+    pub fn map_sequence(
+        &mut self,
+        py: Python,
+        from: Vec<String>,
+        to: PyObject,
+        kwargs: Option<&PyDict>,
+    ) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let timeout = match options.get("timeout_ms") {
+            Some(timeout_ms) => Duration::from_millis(
+                timeout_ms.extract::<u64>().map_err(|_| PyRuntimeError::new_err("'timeout_ms' must be of type 'int'"))?,
+            ),
+            None => Duration::from_millis(1000),
+        };
+
+        let mut state = self.state.blocking_lock();
+        if from.is_empty() {
+            return Err(PyRuntimeError::new_err("'from' must contain at least 1 key"));
+        }
+
+        let seq = parse_sequence_trigger(&from, state.leader, &state.transformer)?;
+
+        let last = *seq.last().unwrap();
+        let action = if let Ok(to) = to.extract::<String>(py) {
+            let to = parse_key_sequence(&to, Some(&state.transformer)).map_err(|err| {
+                PyRuntimeError::new_err(format!(
+                    "mapping error on the 'to' side:\n{}",
+                    ApplicationError::KeySequenceParse(err.to_string()),
+                ))
+            })?;
+            let [(_, down), _, _] = map_click_to_seq(last, to);
+            down
+        } else if to.as_ref(py).is_callable() {
+            RuntimeAction::PythonCallback(last.modifiers, Arc::new(to))
+        } else {
+            return Err(ApplicationError::NotCallable.into());
+        };
+
+        state.layer.sequences.insert(&seq, SequenceMapping { action, timeout });
         Ok(())
     }
 
+    #[pyo3(signature = (from, to = None, **kwargs))]
+    pub fn map_turbo(&mut self, from: String, to: Option<String>, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let rate_hz = match options.get("rate_hz") {
+            Some(rate_hz) => {
+                rate_hz.extract::<f64>().map_err(|_| PyRuntimeError::new_err("'rate_hz' must be of type 'number'"))?
+            }
+            None => 15.0,
+        };
+        let interval = interval_from_rate(rate_hz).ok_or_else(|| {
+            PyRuntimeError::new_err(format!("'rate_hz' must be a positive number of at most {}", MAX_RATE_HZ))
+        })?;
+
+        let mut state = self.state.blocking_lock();
+        let parse_click = |raw: &str, side: &str| match parse_key_action_with_mods(raw, Some(&state.transformer)) {
+            Ok(ParsedKeyAction::KeyClickAction(action)) => Ok(action),
+            Ok(_) => Err(PyRuntimeError::new_err(format!(
+                "mapping error on the '{}' side:\nexpected a key without state, got '{}'",
+                side, raw
+            ))),
+            Err(err) => Err(PyRuntimeError::new_err(format!(
+                "mapping error on the '{}' side:\n{}",
+                side,
+                ApplicationError::KeyParse(err.to_string()),
+            ))),
+        };
+
+        let from = parse_click(&from, "from")?;
+        let to = match to {
+            Some(to) => parse_click(&to, "to")?,
+            None => from,
+        };
+
+        let [(_, down), (_, up), _] = map_click_to_click(&from, &to);
+        let action = match (down, up) {
+            (RuntimeAction::ActionSequence(mut down), RuntimeAction::ActionSequence(up)) => {
+                down.extend(up);
+                RuntimeAction::ActionSequence(down)
+            }
+            _ => unreachable!(),
+        };
+
+        state.layer.turbo.insert(from, TurboMapping { action, to, interval });
+        Ok(())
+    }
+
+    #[pyo3(signature = (from, **kwargs))]
+    pub fn map_motion(&mut self, from: String, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let motion = MotionMapping::from_options(&options, parse_motion_axis(&options)?)?;
+        let mut state = self.state.blocking_lock();
+        let from = parse_click_trigger(&from, &state.transformer)?;
+        state.layer.motion.insert(from, motion);
+        Ok(())
+    }
+
+    #[pyo3(signature = (from, **kwargs))]
+    pub fn map_scroll(&mut self, from: String, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let motion = MotionMapping::from_options(&options, parse_scroll_axis(&options)?)?;
+        let mut state = self.state.blocking_lock();
+        let from = parse_click_trigger(&from, &state.transformer)?;
+        state.layer.motion.insert(from, motion);
+        Ok(())
+    }
+
+    pub fn map_toggle(&mut self, from: String, to: String) -> PyResult<()> {
+        let mut state = self.state.blocking_lock();
+        let from = parse_click_trigger(&from, &state.transformer)?;
+        let to = parse_toggle_key(&to, &state.transformer)?;
+
+        let mappings = map_click_to_seq(from, vec![ParsedKeyAction::Toggle(to)]);
+        state.layer.repeat_overrides.remove(&from);
+        for (from, to) in mappings {
+            state.layer.mappings.insert(from, to);
+        }
+        Ok(())
+    }
+
+    #[pyo3(signature = (from, **kwargs))]
+    pub fn map_record(&mut self, from: String, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let register = parse_macro_register(&options)?;
+        self.map_macro(from, MacroAction::Record(register))
+    }
+
+    #[pyo3(signature = (from, **kwargs))]
+    pub fn map_replay(&mut self, from: String, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let register = parse_macro_register(&options)?;
+        let timing = parse_macro_timing(&options)?;
+        self.map_macro(from, MacroAction::Replay(register, timing))
+    }
+
+    #[pyo3(signature = (register = 0))]
+    pub fn record(&mut self, register: u32) {
+        self.state.blocking_lock().macros.start(register);
+    }
+
+    pub fn stop_recording(&mut self) -> Option<u32> {
+        self.state.blocking_lock().macros.stop()
+    }
+
+    pub fn recording(&self) -> Option<u32> {
+        self.state.blocking_lock().macros.recording()
+    }
+
+    #[pyo3(signature = (register = 0, **kwargs))]
+    pub fn replay(&mut self, register: u32, kwargs: Option<&PyDict>) -> PyResult<bool> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let timing = parse_macro_timing(&options)?;
+        let mut state = self.state.blocking_lock();
+        Ok(replay_macro(&mut state, &self.state, register, timing))
+    }
+
+    pub fn latched(&self) -> Vec<String> {
+        let state = self.state.blocking_lock();
+        let mut keys: Vec<String> = state.latched.iter().map(key_name).collect();
+        keys.sort();
+        keys
+    }
+
+    pub fn is_latched(&self, key: String) -> PyResult<bool> {
+        let state = self.state.blocking_lock();
+        let key = parse_toggle_key(&key, &state.transformer)?;
+        Ok(state.latched.contains(&key))
+    }
+
     pub fn map_fallback(&mut self, py: Python, handler: PyObject) -> PyResult<()> {
         let mut state = self.state.blocking_lock();
         if !handler.as_ref(py).is_callable() {
             return Err(ApplicationError::NotCallable.into());
         }
-        state.fallback_handler = Some(Arc::new(handler));
+        state.layer.fallback_handler = Some(Arc::new(handler));
         Ok(())
     }
 
@@ -139,7 +1251,7 @@ impl Mapper {
         if !handler.as_ref(py).is_callable() {
             return Err(ApplicationError::NotCallable.into());
         }
-        state.relative_handler = Some(Arc::new(handler));
+        state.layer.relative_handler = Some(Arc::new(handler));
         Ok(())
     }
 
@@ -148,32 +1260,161 @@ impl Mapper {
         if !handler.as_ref(py).is_callable() {
             return Err(ApplicationError::NotCallable.into());
         }
-        state.absolute_handler = Some(Arc::new(handler));
+        state.layer.absolute_handler = Some(Arc::new(handler));
         Ok(())
     }
 
-    pub fn nop(&mut self, from: String) -> PyResult<()> {
+    #[pyo3(signature = (from, **kwargs))]
+    pub fn nop(&mut self, from: String, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let when = match options.get("when") {
+            Some(when) => Some(WindowCondition::from_py(when)?),
+            None => None,
+        };
+
         let mut state = self.state.blocking_lock();
-        let from = parse_key_action_with_mods(&from, Some(&state.transformer)).map_err(|err| {
-            PyRuntimeError::new_err(format!(
-                "mapping error on the 'from' side:\n{}",
-                ApplicationError::KeyParse(err.to_string()),
-            ))
-        })?;
+        let (from, ignored) = parse_trigger(&from, &state.transformer, &options)?;
+        let mappings = state.layer.mappings_mut(&ignored, when.as_ref());
 
         match from {
             ParsedKeyAction::KeyAction(from) => {
-                state.mappings.insert(from, RuntimeAction::NOP);
+                mappings.insert(from, RuntimeAction::NOP);
             }
             ParsedKeyAction::KeyClickAction(from) => {
                 for value in 0..=2 {
                     let from = KeyActionWithMods::new(from.key, value, from.modifiers);
-                    state.mappings.insert(from, RuntimeAction::NOP);
+                    mappings.insert(from, RuntimeAction::NOP);
+                }
+            }
+            ParsedKeyAction::Action(_) | ParsedKeyAction::Toggle(_) => {
+                return Err(ApplicationError::NonButton.into_py());
+            }
+        }
+
+        if when.is_some() {
+            self.watch_window(&mut state)?;
+        }
+        Ok(())
+    }
+
+    #[pyo3(signature = (from, **kwargs))]
+    pub fn unmap(&mut self, from: &PyAny, kwargs: Option<&PyDict>) -> PyResult<bool> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let mut state = self.state.blocking_lock();
+        let from = match from.extract::<String>() {
+            Ok(from) => from,
+            Err(_) => {
+                let from = from
+                    .extract::<Vec<String>>()
+                    .map_err(|_| PyRuntimeError::new_err("'from' must be of type 'string' or 'string[]'"))?;
+                let seq = parse_sequence_trigger(&from, state.leader, &state.transformer)?;
+                return Ok(state.layer.sequences.remove(&seq).is_some());
+            }
+        };
+
+        let when = match options.get("when") {
+            Some(when) => Some(WindowCondition::from_py(when)?),
+            None => None,
+        };
+        let (from, ignored) = parse_trigger(&from, &state.transformer, &options)?;
+
+        let layer = &mut state.layer;
+        let mappings = layer.mappings_mut(&ignored, when.as_ref());
+        let removed = match from {
+            ParsedKeyAction::KeyAction(from) => mappings.remove(&from).is_some(),
+            ParsedKeyAction::KeyClickAction(from) => {
+                let mut removed = false;
+                for value in 0..=2 {
+                    removed |= mappings.remove(&from.to_key_action(value)).is_some();
+                }
+                if ignored.is_empty() && when.is_none() {
+                    removed |= layer.key_patterns.remove(&from).is_some();
+                    removed |= layer.turbo.remove(&from).is_some();
+                    removed |= layer.motion.remove(&from).is_some();
+                    layer.repeat_overrides.remove(&from);
                 }
+                removed
             }
-            ParsedKeyAction::Action(_) => {
+            ParsedKeyAction::Action(_) | ParsedKeyAction::Toggle(_) => {
                 return Err(ApplicationError::NonButton.into_py());
             }
+        };
+
+        layer.prune();
+        Ok(removed)
+    }
+
+    pub fn mappings(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let state = self.state.blocking_lock();
+        let mut acc: Vec<(String, &PyDict)> = vec![];
+        for slot in state.layer.slots() {
+            if !matches!(slot, Slot::Fallback | Slot::Relative | Slot::Absolute) {
+                acc.extend(state.layer.describe_slot(py, &slot, &state.transformer)?);
+            }
+        }
+
+        acc.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(acc.into_iter().map(|(_, info)| info.to_object(py)).collect())
+    }
+
+    pub fn resolve(&self, py: Python, key: String) -> PyResult<Option<PyObject>> {
+        let state = self.state.blocking_lock();
+        let (trigger, value) = match parse_key_action_with_mods(&key, Some(&state.transformer)) {
+            Ok(ParsedKeyAction::KeyClickAction(action)) => (action, None),
+            Ok(ParsedKeyAction::KeyAction(action)) => {
+                (KeyClickActionWithMods::new_with_mods(action.key, action.modifiers), Some(action.value))
+            }
+            Ok(_) => return Err(ApplicationError::NonButton.into_py()),
+            Err(err) => return Err(ApplicationError::KeyParse(err.to_string()).into_py()),
+        };
+        let layer = state.layer();
+        let from = format_trigger(&trigger, &state.transformer)?;
+
+        // checked in the same order key presses are handled in
+        if value.is_none() {
+            if let Some(patterns) = layer.key_patterns.get(&trigger) {
+                if let Some((taps, actions)) = patterns.taps.iter().min_by_key(|(taps, _)| **taps) {
+                    let info = mapping_info(py, &from, &actions.action())?;
+                    info.set_item("taps", taps)?;
+                    return Ok(Some(info.to_object(py)));
+                }
+                if let Some((delay, actions)) = patterns.hold.as_ref() {
+                    let info = mapping_info(py, &from, &actions.action())?;
+                    info.set_item("hold_ms", delay.as_millis() as u64)?;
+                    return Ok(Some(info.to_object(py)));
+                }
+            }
+            if let Some(turbo) = layer.turbo.get(&trigger) {
+                let info = mapping_info(py, &from, &turbo.action)?;
+                info.set_item("rate_hz", 1.0 / turbo.interval.as_secs_f64())?;
+                return Ok(Some(info.to_object(py)));
+            }
+            if let Some(motion) = layer.motion.get(&trigger) {
+                return Ok(Some(motion_info(py, &from, motion)?.to_object(py)));
+            }
+        }
+
+        let action = trigger.to_key_action(value.unwrap_or(TYPE_DOWN));
+        match layer.lookup_table(&action, state.window.as_ref()) {
+            Some(table) => Ok(Some(table.describe(py, &trigger, value, &state.transformer)?.1.to_object(py))),
+            None => Ok(None),
+        }
+    }
+
+    pub fn load(&mut self, py: Python, path: String) -> PyResult<()> {
+        let entries = read_keymap(&path).map_err(err_to_py)?;
+        for (idx, entry) in entries.iter().enumerate() {
+            self.load_entry(py, entry).map_err(|err| {
+                PyRuntimeError::new_err(format!("keymap '{}', entry {}:\n{}", path, idx + 1, err.value(py)))
+            })?;
         }
         Ok(())
     }
@@ -181,18 +1422,26 @@ impl Mapper {
     pub fn snapshot(&self, py: Python, existing: Option<&KeyMapperSnapshot>) -> PyResult<Option<KeyMapperSnapshot>> {
         let mut state = self.state.blocking_lock();
         if let Some(existing) = existing {
-            state.mappings = existing.mappings.clone();
-            state.fallback_handler = existing.fallback_handler.clone();
-            state.relative_handler = existing.relative_handler.clone();
-            state.absolute_handler = existing.absolute_handler.clone();
+            state.layer = existing.layer.clone();
+            state.reset_pending();
             return Ok(None);
         }
-        Ok(Some(KeyMapperSnapshot {
-            mappings: state.mappings.clone(),
-            fallback_handler: state.fallback_handler.clone(),
-            relative_handler: state.relative_handler.clone(),
-            absolute_handler: state.absolute_handler.clone(),
-        }))
+        Ok(Some(KeyMapperSnapshot { layer: state.layer.clone(), transformer: state.transformer.clone() }))
+    }
+
+    pub fn bind_snapshot(&mut self, snapshot: &KeyMapperSnapshot, when: &PyAny) -> PyResult<()> {
+        let when = WindowCondition::from_py(when)?;
+        let mut state = self.state.blocking_lock();
+        match state.bound_layers.iter_mut().find(|(condition, _)| *condition == when) {
+            Some((_, layer)) => *layer = snapshot.layer.clone(),
+            None => state.bound_layers.push((when, snapshot.layer.clone())),
+        }
+
+        // the active layer might have been replaced, force re-evaluating it
+        state.active_layer = None;
+        let window = state.window.clone();
+        state.set_window(window);
+        self.watch_window(&mut state)
     }
 
     pub fn link_to(&mut self, target: &PyAny) -> PyResult<()> {
@@ -204,23 +1453,30 @@ impl Mapper {
 
     pub fn unlink_to(&mut self, py: Python, target: &PyAny) -> PyResult<bool> {
         let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        self.state.blocking_lock().release_latched();
         target.unlink_from(&self.id);
         let ret = self.link.unlink_to(target.id()).map_err(err_to_py)?;
+        self.state.blocking_lock().stop_held_actions();
         Ok(ret)
     }
 
     pub fn unlink_to_all(&mut self) {
         let mut state = self.state.blocking_lock();
+        state.release_latched();
         for l in state.next.values_mut() {
             l.unlink_from(&self.id);
         }
         state.next.clear();
+        state.stop_held_actions();
     }
 
     pub fn unlink_from(&mut self, target: &PyAny) -> PyResult<bool> {
         let target = node_to_link_src(target).ok_or_else(|| PyRuntimeError::new_err("expected a source node"))?;
         target.unlink_to(&self.id);
         let ret = self.link.unlink_from(target.id()).map_err(err_to_py)?;
+        let mut state = self.state.blocking_lock();
+        state.stop_held_actions();
+        state.release_latched();
         Ok(ret)
     }
 
@@ -230,6 +1486,8 @@ impl Mapper {
             l.unlink_to(&self.id);
         }
         state.prev.clear();
+        state.stop_held_actions();
+        state.release_latched();
     }
 
     pub fn unlink_all(&mut self) {
@@ -237,115 +1495,262 @@ impl Mapper {
         self.unlink_to_all();
     }
 
-    pub fn send(&mut self, val: String) -> PyResult<()> {
+    pub fn send(&mut self, val: String) -> PyResult<()> {
+        let mut state = self.state.blocking_lock();
+        let actions = parse_key_sequence_without_toggles(val.as_str(), Some(&state.transformer))
+            .map_err(|err| ApplicationError::KeySequenceParse(err.to_string()).into_py())?
+            .to_key_actions();
+        for action in actions {
+            self.ev_tx.try_send(InputEvent::Raw(action.to_input_ev())).expect(&TooManyEvents.to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Mapper {
+    fn _map(
+        &mut self,
+        from: ParsedKeyAction,
+        mappings: Vec<Mapping>,
+        ignored: KeyModifierFlags,
+        options: &HashMap<&str, &PyAny>,
+    ) -> PyResult<()> {
+        let taps = match options.get("taps") {
+            Some(taps) => Some(taps.extract::<u32>().map_err(|_| PyRuntimeError::new_err("'taps' must be of type 'int'"))?),
+            None => None,
+        };
+        let hold_ms = match options.get("hold_ms") {
+            Some(hold_ms) => {
+                Some(hold_ms.extract::<u64>().map_err(|_| PyRuntimeError::new_err("'hold_ms' must be of type 'int'"))?)
+            }
+            None => None,
+        };
+        let window_ms = match options.get("window_ms") {
+            Some(window_ms) => Some(
+                window_ms.extract::<u64>().map_err(|_| PyRuntimeError::new_err("'window_ms' must be of type 'int'"))?,
+            ),
+            None => None,
+        };
+        let repeat = match options.get("repeat") {
+            Some(repeat) => Some(parse_repeat(repeat)?),
+            None => None,
+        };
+        let when = match options.get("when") {
+            Some(when) => Some(WindowCondition::from_py(when)?),
+            None => None,
+        };
+
+        let mut state = self.state.blocking_lock();
+
+        if taps.is_none() && hold_ms.is_none() {
+            if window_ms.is_some() {
+                return Err(PyRuntimeError::new_err("'window_ms' can only be used together with 'taps'"));
+            }
+            if let Some(when) = when {
+                if repeat.is_some() {
+                    return Err(PyRuntimeError::new_err("'repeat' cannot be used together with 'when'"));
+                }
+                let target = state.layer.mappings_mut(&ignored, Some(&when));
+                for (from, to) in mappings {
+                    target.insert(from, to);
+                }
+                return self.watch_window(&mut state);
+            }
+            match (&from, repeat) {
+                (ParsedKeyAction::KeyClickAction(from), Some(repeat)) => {
+                    state.layer.repeat_overrides.insert(*from, repeat);
+                }
+                (ParsedKeyAction::KeyClickAction(from), None) => {
+                    state.layer.repeat_overrides.remove(from);
+                }
+                (_, Some(_)) => return Err(PyRuntimeError::new_err("'repeat' can only be used with key triggers")),
+                (_, None) => {}
+            }
+            let target = state.layer.mappings_mut(&ignored, None);
+            for (from, to) in mappings {
+                target.insert(from, to);
+            }
+            return Ok(());
+        }
+
+        if repeat.is_some() {
+            return Err(PyRuntimeError::new_err("'repeat' cannot be used together with 'taps' or 'hold_ms'"));
+        }
+        if when.is_some() {
+            return Err(PyRuntimeError::new_err("'when' cannot be used together with 'taps' or 'hold_ms'"));
+        }
+
+        let trigger = match from {
+            ParsedKeyAction::KeyClickAction(from) if ignored.is_empty() => from,
+            _ => {
+                return Err(PyRuntimeError::new_err(
+                    "'taps' and 'hold_ms' can only be used with key triggers without modifiers ignored, such as 'a' or '^a'",
+                ))
+            }
+        };
+        let actions = ClickActions::from_mappings(mappings);
+
+        match (taps, hold_ms) {
+            (Some(_), Some(_)) => Err(PyRuntimeError::new_err("expected only one of: 'taps', 'hold_ms'")),
+            (Some(0), _) => Err(PyRuntimeError::new_err("'taps' must be at least 1")),
+            (Some(taps), _) => {
+                let patterns = state.layer.key_patterns.entry(trigger).or_default();
+                patterns.taps.insert(taps, actions);
+                if let Some(window_ms) = window_ms {
+                    patterns.tap_window = Duration::from_millis(window_ms);
+                }
+                Ok(())
+            }
+            (_, Some(hold_ms)) => {
+                if window_ms.is_some() {
+                    return Err(PyRuntimeError::new_err("'window_ms' can only be used together with 'taps'"));
+                }
+                let patterns = state.layer.key_patterns.entry(trigger).or_default();
+                patterns.hold = Some((Duration::from_millis(hold_ms), actions));
+                Ok(())
+            }
+            (None, None) => unreachable!(),
+        }
+    }
+
+    fn map_macro(&mut self, from: String, action: MacroAction) -> PyResult<()> {
         let mut state = self.state.blocking_lock();
-        let actions = parse_key_sequence(val.as_str(), Some(&state.transformer))
-            .map_err(|err| ApplicationError::KeySequenceParse(err.to_string()).into_py())?
-            .to_key_actions();
-        for action in actions {
-            self.ev_tx.try_send(InputEvent::Raw(action.to_input_ev())).expect(&TooManyEvents.to_string());
-        }
+        let from = parse_click_trigger(&from, &state.transformer)?;
+
+        state.layer.repeat_overrides.remove(&from);
+        state.layer.mappings.insert(
+            from.to_key_action(TYPE_DOWN),
+            RuntimeAction::ActionSequence(vec![RuntimeKeyAction::Macro(action)]),
+        );
+        state.layer.mappings.insert(from.to_key_action(TYPE_UP), RuntimeAction::NOP);
+        state.layer.mappings.insert(from.to_key_action(TYPE_REPEAT), RuntimeAction::NOP);
         Ok(())
     }
-}
 
-impl Mapper {
-    fn _map_callback(&mut self, from: ParsedKeyAction, to: PyObject) -> PyResult<()> {
-        let mut state = self.state.blocking_lock();
-        let to = Arc::new(to);
-        match from {
-            ParsedKeyAction::KeyAction(from) => {
-                state.mappings.insert(from, RuntimeAction::PythonCallback(from.modifiers, to));
-            }
-            ParsedKeyAction::KeyClickAction(from) => {
-                state.mappings.insert(from.to_key_action(1), RuntimeAction::PythonCallback(from.modifiers, to));
-                state.mappings.insert(from.to_key_action(0), RuntimeAction::NOP);
-                state.mappings.insert(from.to_key_action(2), RuntimeAction::NOP);
-            }
-            ParsedKeyAction::Action(_) => {
-                return Err(ApplicationError::NonButton.into());
+    // adds a keymap file entry through the mapping function that would have created it
+    fn load_entry(&mut self, py: Python, entry: &serde_json::Value) -> PyResult<()> {
+        let entry = KeymapEntry::from_json(py, entry)?;
+        let options = Some(entry.options);
+
+        if let Ok(from) = entry.from.extract::<Vec<String>>() {
+            return self.map_sequence(py, from, entry.to()?.to_object(py), options);
+        }
+        let from = entry
+            .from
+            .extract::<String>()
+            .map_err(|_| PyRuntimeError::new_err("'from' must be of type 'string' or 'string[]'"))?;
+
+        match entry.kind.as_str() {
+            "sequence" => self.map(py, from, entry.to()?.to_object(py), options),
+            "nop" => self.nop(from, options),
+            "record" => self.map_record(from, options),
+            "replay" => self.map_replay(from, options),
+            "motion" => self.map_motion(from, options),
+            "key" => {
+                let to = entry
+                    .to()?
+                    .extract::<String>()
+                    .map_err(|_| PyRuntimeError::new_err("'to' must be of type 'string'"))?;
+                if entry.options.contains("rate_hz")? {
+                    self.map_turbo(from, Some(to), options)
+                } else {
+                    self.map_key(from, to, options)
+                }
             }
+            kind => Err(PyRuntimeError::new_err(format!(
+                "unknown kind '{}', expected one of: 'sequence', 'key', 'nop', 'record', 'replay', 'motion'",
+                kind
+            ))),
+        }
+    }
+
+    // keeps the mapper's active window up to date, started once a mapping depends on it
+    fn watch_window(&self, state: &mut State) -> PyResult<()> {
+        if state.window_watch.is_some() {
+            return Ok(());
         }
 
+        let mut window_rx = watch_active_window().map_err(err_to_py)?;
+        let window = window_rx.borrow_and_update().clone();
+        state.set_window(window);
+
+        let _state = self.state.clone();
+        state.window_watch = Some(get_runtime().spawn(async move {
+            while window_rx.changed().await.is_ok() {
+                let window = window_rx.borrow_and_update().clone();
+                _state.lock().await.set_window(window);
+            }
+        }));
         Ok(())
     }
+}
 
-    fn _map_key(&mut self, from: ParsedKeyAction, mut to: Vec<ParsedKeyAction>) -> PyResult<()> {
-        let mut state = self.state.blocking_lock();
-        match from {
-            ParsedKeyAction::KeyAction(from) => {
-                if to.len() == 1 {
-                    let to = to.remove(0);
-                    match to {
-                        // key action to click
-                        ParsedKeyAction::KeyClickAction(to) => {
-                            let mapping = map_action_to_click(&from, &to);
-                            state.mappings.insert(mapping.0, mapping.1);
-                        }
-                        // key action to key action
-                        ParsedKeyAction::KeyAction(to) => {
-                            let mapping = map_action_to_action(&from, &to);
-                            state.mappings.insert(mapping.0, mapping.1);
-                        }
-                        // key action to action
-                        ParsedKeyAction::Action(to) => {
-                            let mapping = map_action_to_action(&from, &to.to_key_action_with_mods(Default::default()));
-                            state.mappings.insert(mapping.0, mapping.1);
-                        }
-                    }
-                    return Ok(());
-                }
+fn callback_mappings(from: ParsedKeyAction, to: PyObject) -> PyResult<Vec<Mapping>> {
+    let to = Arc::new(to);
+    match from {
+        ParsedKeyAction::KeyAction(from) => Ok(vec![(from, RuntimeAction::PythonCallback(from.modifiers, to))]),
+        ParsedKeyAction::KeyClickAction(from) => Ok(vec![
+            (from.to_key_action(1), RuntimeAction::PythonCallback(from.modifiers, to)),
+            (from.to_key_action(0), RuntimeAction::NOP),
+            (from.to_key_action(2), RuntimeAction::NOP),
+        ]),
+        ParsedKeyAction::Action(_) | ParsedKeyAction::Toggle(_) => Err(ApplicationError::NonButton.into()),
+    }
+}
 
-                // action to seq
-                let mapping = map_action_to_seq(from, to);
-                state.mappings.insert(mapping.0, mapping.1);
+fn key_mappings(from: ParsedKeyAction, mut to: Vec<ParsedKeyAction>) -> PyResult<Vec<Mapping>> {
+    match from {
+        ParsedKeyAction::KeyAction(from) => {
+            if to.len() == 1 && !matches!(to[0], ParsedKeyAction::Toggle(_)) {
+                let mapping = match to.remove(0) {
+                    // key action to click
+                    ParsedKeyAction::KeyClickAction(to) => map_action_to_click(&from, &to),
+                    // key action to key action
+                    ParsedKeyAction::KeyAction(to) => map_action_to_action(&from, &to),
+                    // key action to action
+                    ParsedKeyAction::Action(to) => {
+                        map_action_to_action(&from, &to.to_key_action_with_mods(Default::default()))
+                    }
+                    ParsedKeyAction::Toggle(_) => unreachable!(),
+                };
+                return Ok(vec![mapping]);
             }
-            ParsedKeyAction::KeyClickAction(from) => {
-                if to.len() == 1 {
-                    match to.remove(0) {
-                        // click to click
-                        ParsedKeyAction::KeyClickAction(to) => {
-                            let mappings = map_click_to_click(&from, &to);
-
-                            IntoIterator::into_iter(mappings).for_each(|(from, to)| {
-                                state.mappings.insert(from, to);
-                            });
-                        }
-                        // click to key action
-                        ParsedKeyAction::KeyAction(to) => {
-                            let mappings = map_click_to_action(&from, &to);
-                            IntoIterator::into_iter(mappings).for_each(|(from, to)| {
-                                state.mappings.insert(from, to);
-                            });
-                        }
-                        // click to action
-                        ParsedKeyAction::Action(to) => {
-                            let to = to.to_key_action_with_mods(Default::default());
-                            let mappings = map_click_to_action(&from, &to);
-                            IntoIterator::into_iter(mappings).for_each(|(from, to)| {
-                                state.mappings.insert(from, to);
-                            });
-                        }
-                    };
-                    return Ok(());
-                }
 
-                // click to seq
-                let mappings = map_click_to_seq(from, to);
-                IntoIterator::into_iter(mappings).for_each(|(from, to)| {
-                    state.mappings.insert(from, to);
-                });
-            }
-            ParsedKeyAction::Action(_) => {
-                return Err(ApplicationError::NonButton.into());
+            // action to seq
+            Ok(vec![map_action_to_seq(from, to)])
+        }
+        ParsedKeyAction::KeyClickAction(from) => {
+            if to.len() == 1 && !matches!(to[0], ParsedKeyAction::Toggle(_)) {
+                let mappings = match to.remove(0) {
+                    // click to click
+                    ParsedKeyAction::KeyClickAction(to) => map_click_to_click(&from, &to),
+                    // click to key action
+                    ParsedKeyAction::KeyAction(to) => map_click_to_action(&from, &to),
+                    // click to action
+                    ParsedKeyAction::Action(to) => {
+                        map_click_to_action(&from, &to.to_key_action_with_mods(Default::default()))
+                    }
+                    ParsedKeyAction::Toggle(_) => unreachable!(),
+                };
+                return Ok(IntoIterator::into_iter(mappings).collect());
             }
+
+            // click to seq
+            Ok(IntoIterator::into_iter(map_click_to_seq(from, to)).collect())
         }
-        Ok(())
+        ParsedKeyAction::Action(_) | ParsedKeyAction::Toggle(_) => Err(ApplicationError::NonButton.into()),
     }
 }
 
 impl Drop for Mapper {
     fn drop(&mut self) {
+        {
+            let mut state = self.state.blocking_lock();
+            state.timers.cancel_all();
+            if let Some(window_watch) = state.window_watch.take() {
+                window_watch.abort();
+            }
+        }
         self.unlink_from_all();
         self.unlink_to_all();
     }
@@ -390,13 +1795,94 @@ impl LinkDst for MapperLink {
 
 #[pyclass]
 pub struct KeyMapperSnapshot {
-    mappings: Mappings,
-    fallback_handler: Option<Arc<PyObject>>,
-    relative_handler: Option<Arc<PyObject>>,
-    absolute_handler: Option<Arc<PyObject>>,
+    layer: Layer,
+    // formats the triggers typed with right alt
+    transformer: Arc<XKBTransformer>,
+}
+
+#[pymethods]
+impl KeyMapperSnapshot {
+    #[pyo3(signature = (other, **kwargs))]
+    pub fn merge(&self, other: &KeyMapperSnapshot, kwargs: Option<&PyDict>) -> PyResult<KeyMapperSnapshot> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let on_conflict = match options.get("on_conflict").map(|x| x.extract::<String>()) {
+            None => Conflict::Error,
+            Some(Ok(policy)) if policy == "error" => Conflict::Error,
+            Some(Ok(policy)) if policy == "keep" => Conflict::Keep,
+            Some(Ok(policy)) if policy == "replace" => Conflict::Replace,
+            Some(_) => {
+                return Err(PyRuntimeError::new_err("'on_conflict' must be one of: 'error', 'keep', 'replace'"));
+            }
+        };
+        let layer = self.layer.merge(&other.layer, on_conflict, &self.transformer)?;
+        Ok(KeyMapperSnapshot { layer, transformer: self.transformer.clone() })
+    }
+
+    pub fn overlay(&self, other: &KeyMapperSnapshot) -> PyResult<KeyMapperSnapshot> {
+        let layer = self.layer.merge(&other.layer, Conflict::Replace, &self.transformer)?;
+        Ok(KeyMapperSnapshot { layer, transformer: self.transformer.clone() })
+    }
+
+    pub fn diff(&self, py: Python, other: &KeyMapperSnapshot) -> PyResult<PyObject> {
+        let mut added = vec![];
+        let mut removed = vec![];
+        let mut changed = vec![];
+
+        for slot in other.layer.slots() {
+            match self.layer.slot_value(&slot) {
+                None => added.extend(other.layer.describe_slot(py, &slot, &other.transformer)?),
+                Some(ours) if Some(ours) != other.layer.slot_value(&slot) => {
+                    changed.extend(other.layer.describe_slot(py, &slot, &other.transformer)?)
+                }
+                Some(_) => {}
+            }
+        }
+        for slot in self.layer.slots() {
+            if other.layer.slot_value(&slot).is_none() {
+                removed.extend(self.layer.describe_slot(py, &slot, &self.transformer)?);
+            }
+        }
+
+        let diff = PyDict::new(py);
+        for (key, mut entries) in [("added", added), ("removed", removed), ("changed", changed)] {
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            diff.set_item(key, entries.into_iter().map(|(_, info)| info).collect::<Vec<_>>())?;
+        }
+        Ok(diff.to_object(py))
+    }
+
+    pub fn to_file(&self, py: Python, path: String) -> PyResult<()> {
+        write_keymap(&path, self.layer.keymap_entries(py, &self.transformer)?).map_err(err_to_py)
+    }
 }
 
 async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
+    let ev = match &raw_ev {
+        InputEvent::Raw(ev) => ev.clone(),
+    };
+
+    let mut state = _state.lock().await;
+    match sequence_step(&mut state, &_state, &ev) {
+        SequenceStep::Pass => {
+            drop(state);
+            handle_event(_state, raw_ev).await;
+        }
+        SequenceStep::Buffered => {}
+        SequenceStep::Matched(action) => run_action(&_state, state, action, ev).await,
+        SequenceStep::Flush(events) => {
+            drop(state);
+            for ev in events {
+                handle_event(_state.clone(), InputEvent::Raw(ev)).await;
+            }
+        }
+    }
+}
+
+async fn handle_event(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
     let mut state = _state.lock().await;
     let ev = match &raw_ev {
         InputEvent::Raw(ev) => ev,
@@ -405,69 +1891,46 @@ async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
     match ev {
         // key event
         EvdevInputEvent { event_code: EventCode::EV_KEY(key), value, .. } => {
-            let mut from_modifiers = KeyModifierFlags::new();
-            from_modifiers.ctrl = state.modifiers.is_ctrl();
-            from_modifiers.alt = state.modifiers.is_alt();
-            from_modifiers.right_alt = state.modifiers.is_right_alt();
-            from_modifiers.shift = state.modifiers.is_shift();
-            from_modifiers.meta = state.modifiers.is_meta();
-
             let from_key_action = KeyActionWithMods {
                 key: Key { event_code: ev.event_code },
                 value: ev.value,
-                modifiers: from_modifiers,
+                modifiers: state.modifier_flags(),
             };
 
-            if let Some(runtime_action) = state.mappings.get(&from_key_action) {
-                match runtime_action {
-                    RuntimeAction::ActionSequence(seq) => {
-                        for action in seq {
-                            match action {
-                                RuntimeKeyAction::KeyAction(key_action) => {
-                                    let _ = state.next.send_all(InputEvent::Raw(key_action.to_input_ev()));
-                                }
-                                RuntimeKeyAction::ReleaseRestoreModifiers(from_flags, to_flags, to_type) => {
-                                    let new_events =
-                                        release_restore_modifiers(&state.modifiers, &from_flags, &to_flags, &to_type);
-                                    for ev in new_events {
-                                        state.next.send_all(InputEvent::Raw(ev));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    RuntimeAction::PythonCallback(from_modifiers, handler) => {
-                        if !state.next.is_empty() {
-                            // always release all trigger mods before running the callback
-                            let new_events = release_restore_modifiers(
-                                &state.modifiers,
-                                &from_modifiers,
-                                &KeyModifierFlags::new(),
-                                &TYPE_UP,
-                            );
-                            new_events.iter().cloned().for_each(|ev| state.next.send_all(InputEvent::Raw(ev)));
-                        }
+            if !update_repeat(&mut state, &_state, &from_key_action) {
+                return;
+            }
 
-                        drop(ev);
-                        let ev = match raw_ev {
-                            InputEvent::Raw(ev) => ev,
-                        };
+            // a different key going down resolves the pending tap sequences first to keep the output in order
+            let interrupted = interrupt_key_patterns(&mut state, &from_key_action);
+            if !interrupted.is_empty() {
+                drop(state);
+                run_actions(&_state, interrupted, ev.clone()).await;
+                state = _state.lock().await;
+            }
 
-                        let handler = handler.clone();
-                        let transformer = state.transformer.clone();
-                        let next = state.next.values().cloned().collect();
-                        drop(state);
-                        run_python_handler(handler, None, ev, transformer, next).await;
-                    }
-                    RuntimeAction::NOP => {}
-                }
+            if let Some(actions) = handle_key_patterns(&mut state, &_state, &from_key_action) {
+                drop(state);
+                run_actions(&_state, actions, ev.clone()).await;
+                return;
+            }
+
+            if handle_turbo(&mut state, &_state, &from_key_action) {
+                return;
+            }
+
+            if handle_motion(&mut state, &_state, &from_key_action) {
+                return;
+            }
 
+            if let Some(runtime_action) = state.lookup(&from_key_action).cloned() {
+                run_action(&_state, state, runtime_action, ev.clone()).await;
                 return;
             }
 
             event_handlers::update_modifiers(&mut state.modifiers, &KeyAction::from_input_ev(&ev));
 
-            if let Some(handler) = state.fallback_handler.as_ref() {
+            if let Some(handler) = state.layer().fallback_handler.as_ref() {
                 let name = match key {
                     KEY_SPACE => "space".to_string(),
                     KEY_TAB => "tab".to_string(),
@@ -505,8 +1968,8 @@ async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
             if matches!(event_code, EventCode::EV_REL(..)) || matches!(event_code, EventCode::EV_ABS(..)) =>
         {
             let (key, handler) = match event_code {
-                EventCode::EV_REL(key) => (format!("{key:?}").to_string(), &state.relative_handler),
-                EventCode::EV_ABS(key) => (format!("{key:?}").to_string(), &state.absolute_handler),
+                EventCode::EV_REL(key) => (format!("{key:?}").to_string(), &state.layer().relative_handler),
+                EventCode::EV_ABS(key) => (format!("{key:?}").to_string(), &state.layer().absolute_handler),
                 _ => unreachable!(),
             };
             if let Some(handler) = handler.as_ref() {
@@ -531,5 +1994,476 @@ async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
         _ => {}
     }
 
-    state.next.send_all(raw_ev);
+    match raw_ev {
+        InputEvent::Raw(ev) => state.emit(ev),
+    }
+}
+
+async fn run_action(
+    _state: &Arc<Mutex<State>>,
+    mut state: MutexGuard<'_, State>,
+    runtime_action: RuntimeAction,
+    ev: EvdevInputEvent,
+) {
+    match runtime_action {
+        RuntimeAction::ActionSequence(seq) => {
+            for action in seq {
+                match action {
+                    RuntimeKeyAction::KeyAction(key_action) => {
+                        state.emit(key_action.to_input_ev());
+                    }
+                    RuntimeKeyAction::ReleaseRestoreModifiers(from_flags, to_flags, to_type) => {
+                        let new_events = release_restore_modifiers(&state.modifiers, &from_flags, &to_flags, &to_type);
+                        for ev in new_events {
+                            state.emit(ev);
+                        }
+                    }
+                    RuntimeKeyAction::Toggle(key) => {
+                        let value = if state.latched.remove(&key) {
+                            TYPE_UP
+                        } else {
+                            state.latched.insert(key);
+                            TYPE_DOWN
+                        };
+                        state.emit(key.to_input_ev(value));
+                    }
+                    RuntimeKeyAction::Macro(MacroAction::Record(register)) => {
+                        if state.macros.stop().is_none() {
+                            state.macros.start(register);
+                        }
+                    }
+                    RuntimeKeyAction::Macro(MacroAction::Replay(register, timing)) => {
+                        replay_macro(&mut state, _state, register, timing);
+                    }
+                }
+            }
+        }
+        RuntimeAction::PythonCallback(from_modifiers, handler) => {
+            if !state.next.is_empty() {
+                // always release all trigger mods before running the callback
+                let new_events =
+                    release_restore_modifiers(&state.modifiers, &from_modifiers, &KeyModifierFlags::new(), &TYPE_UP);
+                for ev in new_events {
+                    state.emit(ev);
+                }
+            }
+
+            let transformer = state.transformer.clone();
+            let next = state.next.values().cloned().collect();
+            drop(state);
+            run_python_handler(handler, None, ev, transformer, next).await;
+        }
+        RuntimeAction::NOP => {}
+    }
+}
+
+// the state lock is re-acquired for every action since callbacks release it
+async fn run_actions(_state: &Arc<Mutex<State>>, actions: Vec<RuntimeAction>, ev: EvdevInputEvent) {
+    for action in actions {
+        run_action(_state, _state.lock().await, action, ev.clone()).await;
+    }
+}
+
+// sends the recorded events to the next nodes again, returns false if the register is empty
+fn replay_macro(state: &mut State, _state: &Arc<Mutex<State>>, register: u32, timing: MacroTiming) -> bool {
+    let events = match state.macros.playback(register, timing) {
+        Some(events) => events,
+        None => return false,
+    };
+
+    // a replay that is still running gets replaced
+    state.stop_replay();
+
+    if timing == MacroTiming::Compress {
+        for (_, ev) in events {
+            state.emit_replayed(ev);
+        }
+        state.replayed_keys.clear();
+        return true;
+    }
+
+    let _state = _state.clone();
+    state.timers.spawn(TimerId::Replay, async move {
+        for (delay, ev) in events {
+            tokio::time::sleep(delay).await;
+            _state.lock().await.emit_replayed(ev);
+        }
+        let mut state = _state.lock().await;
+        state.timers.forget(&TimerId::Replay);
+        state.replayed_keys.clear();
+    });
+    true
+}
+
+// returns the actions to run if the key is part of a multi-tap or long-press trigger
+fn handle_key_patterns(
+    state: &mut State,
+    _state: &Arc<Mutex<State>>,
+    action: &KeyActionWithMods,
+) -> Option<Vec<RuntimeAction>> {
+    let key = action.key;
+    if !state.tap_states.contains_key(&key) {
+        let trigger = KeyClickActionWithMods::new_with_mods(key, action.modifiers);
+        if action.value != TYPE_DOWN || !state.layer().key_patterns.contains_key(&trigger) {
+            return None;
+        }
+        state.tap_states.insert(key, TapState { trigger, count: 0, held: false, release: None });
+    }
+
+    let trigger = state.tap_states[&key].trigger;
+    let patterns = match state.layer().key_patterns.get(&trigger) {
+        Some(patterns) => patterns.clone(),
+        None => {
+            state.tap_states.remove(&key);
+            return None;
+        }
+    };
+
+    let tap_state = state.tap_states.get_mut(&key).unwrap();
+    match action.value {
+        TYPE_DOWN => {
+            state.timers.cancel(&TimerId::Tap(key));
+            tap_state.count += 1;
+            tap_state.held = true;
+
+            if let (1, Some((delay, _))) = (tap_state.count, &patterns.hold) {
+                let _state = _state.clone();
+                state.timers.spawn_after(TimerId::Tap(key), *delay, async move {
+                    let mut guard = _state.lock().await;
+                    let state = &mut *guard;
+                    state.timers.forget(&TimerId::Tap(key));
+
+                    let hold = state.layer().key_patterns.get(&trigger).and_then(|patterns| patterns.hold.clone());
+                    let hold = match (state.tap_states.get_mut(&key), hold) {
+                        (Some(tap_state), Some((_, hold))) if tap_state.held && tap_state.release.is_none() => {
+                            tap_state.release = Some(hold.up);
+                            hold.down
+                        }
+                        _ => return,
+                    };
+                    drop(guard);
+                    run_actions(&_state, vec![hold], key.to_input_ev(TYPE_DOWN)).await;
+                });
+            }
+            Some(vec![])
+        }
+        TYPE_UP => {
+            tap_state.held = false;
+            if let Some(release) = tap_state.release.take() {
+                state.tap_states.remove(&key);
+                return Some(vec![release]);
+            }
+            state.timers.cancel(&TimerId::Tap(key));
+
+            // no higher tap count can match, no need to wait
+            let max_taps = patterns.taps.keys().copied().max().unwrap_or(0);
+            if tap_state.count >= max_taps {
+                let tap_state = state.tap_states.remove(&key).unwrap();
+                return Some(resolve_taps(state, &patterns, &tap_state).0);
+            }
+
+            let _state = _state.clone();
+            state.timers.spawn_after(TimerId::Tap(key), patterns.tap_window, async move {
+                let mut guard = _state.lock().await;
+                let state = &mut *guard;
+                state.timers.forget(&TimerId::Tap(key));
+
+                let tap_state = match state.tap_states.remove(&key) {
+                    Some(tap_state) => tap_state,
+                    None => return,
+                };
+                let patterns = match state.layer().key_patterns.get(&trigger) {
+                    Some(patterns) => patterns.clone(),
+                    None => return,
+                };
+                let (actions, _) = resolve_taps(state, &patterns, &tap_state);
+                drop(guard);
+                run_actions(&_state, actions, key.to_input_ev(TYPE_DOWN)).await;
+            });
+            Some(vec![])
+        }
+        // repeat events of pattern keys are swallowed
+        _ => Some(vec![]),
+    }
+}
+
+fn interrupt_key_patterns(state: &mut State, action: &KeyActionWithMods) -> Vec<RuntimeAction> {
+    if action.value != TYPE_DOWN || state.tap_states.is_empty() {
+        return vec![];
+    }
+
+    let keys: Vec<Key> = state
+        .tap_states
+        .iter()
+        .filter(|(key, tap_state)| **key != action.key && tap_state.release.is_none())
+        .map(|(key, _)| *key)
+        .collect();
+
+    let mut actions = vec![];
+    for key in keys {
+        state.timers.cancel(&TimerId::Tap(key));
+        let tap_state = state.tap_states.remove(&key).unwrap();
+        let patterns = match state.layer().key_patterns.get(&tap_state.trigger) {
+            Some(patterns) => patterns.clone(),
+            None => continue,
+        };
+
+        let (press, release) = resolve_taps(state, &patterns, &tap_state);
+        actions.extend(press);
+        if let Some(release) = release {
+            state.tap_states.insert(key, TapState { release: Some(release), ..tap_state });
+        }
+    }
+    actions
+}
+
+// the actions a finished tap sequence emits, the second value runs once the still held key is released
+fn resolve_taps(
+    state: &State,
+    patterns: &KeyPatterns,
+    tap_state: &TapState,
+) -> (Vec<RuntimeAction>, Option<RuntimeAction>) {
+    let (actions, count) = match patterns.taps.get(&tap_state.count) {
+        Some(actions) => (actions.clone(), 1),
+        // nothing matched, replay the swallowed presses as if they were never intercepted
+        None => (fallback_actions(state, &tap_state.trigger), tap_state.count),
+    };
+
+    let mut seq = vec![];
+    for _ in 1..count {
+        seq.push(actions.down.clone());
+        seq.push(actions.up.clone());
+    }
+    seq.push(actions.down);
+    if tap_state.held {
+        return (seq, Some(actions.up));
+    }
+    seq.push(actions.up);
+    (seq, None)
+}
+
+fn fallback_actions(state: &State, trigger: &KeyClickActionWithMods) -> ClickActions {
+    let action = |value| {
+        state.lookup(&trigger.to_key_action(value)).cloned().unwrap_or_else(|| {
+            RuntimeAction::ActionSequence(vec![RuntimeKeyAction::KeyAction(KeyAction::new(trigger.key, value))])
+        })
+    };
+    ClickActions { down: action(TYPE_DOWN), up: action(TYPE_UP) }
+}
+
+enum SequenceStep {
+    // not part of a sequence, handle as usual
+    Pass,
+    Buffered,
+    Matched(RuntimeAction),
+    // the sequence did not complete, handle the buffered events as usual
+    Flush(Vec<EvdevInputEvent>),
+}
+
+fn sequence_step(state: &mut State, _state: &Arc<Mutex<State>>, ev: &EvdevInputEvent) -> SequenceStep {
+    let key = match ev.event_code {
+        EventCode::EV_KEY(_) => Key { event_code: ev.event_code },
+        _ => return SequenceStep::Pass,
+    };
+
+    if ev.value != TYPE_DOWN && state.sequence_swallowed.contains(&key) {
+        if ev.value == TYPE_UP {
+            state.sequence_swallowed.remove(&key);
+        }
+        return SequenceStep::Buffered;
+    }
+
+    if state.sequence_buffer.is_empty() {
+        if ev.value != TYPE_DOWN || key.is_modifier() {
+            return SequenceStep::Pass;
+        }
+        let click = KeyClickActionWithMods::new_with_mods(key, state.modifier_flags());
+        if state.layer().sequences.get(&[click]).is_none() {
+            return SequenceStep::Pass;
+        }
+        state.sequence_modifiers = state.modifiers.clone();
+    }
+
+    state.sequence_buffer.push(ev.clone());
+
+    // modifiers and releases are buffered so they can be replayed in order
+    if ev.value != TYPE_DOWN || key.is_modifier() {
+        event_handlers::update_modifiers(&mut state.modifiers, &KeyAction::from_input_ev(ev));
+        return SequenceStep::Buffered;
+    }
+
+    let click = KeyClickActionWithMods::new_with_mods(key, state.modifier_flags());
+    state.sequence_path.push(click);
+
+    let (matched, timeout) = match state.layer().sequences.get(&state.sequence_path) {
+        Some(node) if node.is_leaf() => (node.value().map(|x| x.action.clone()), Duration::ZERO),
+        // wait for the next key, a complete sequence that is also a prefix fires on timeout
+        Some(node) => (None, node.values().iter().map(|x| x.timeout).max().unwrap_or_default()),
+        None => {
+            state.timers.cancel(&TimerId::Sequence);
+            return SequenceStep::Flush(flush_sequence(state));
+        }
+    };
+
+    if let Some(action) = matched {
+        state.timers.cancel(&TimerId::Sequence);
+        finish_sequence(state);
+        return SequenceStep::Matched(action);
+    }
+
+    let _state = _state.clone();
+    state.timers.spawn_after(TimerId::Sequence, timeout, async move {
+        let mut guard = _state.lock().await;
+        let state = &mut *guard;
+        state.timers.forget(&TimerId::Sequence);
+
+        let action = state.layer().sequences.get(&state.sequence_path).and_then(|x| x.value()).map(|x| x.action.clone());
+        match action {
+            Some(action) => {
+                let ev = state.sequence_buffer.last().cloned().unwrap_or_else(|| key.to_input_ev(TYPE_DOWN));
+                finish_sequence(state);
+                run_action(&_state, guard, action, ev).await;
+            }
+            None => {
+                let events = flush_sequence(state);
+                drop(guard);
+                for ev in events {
+                    handle_event(_state.clone(), InputEvent::Raw(ev)).await;
+                }
+            }
+        }
+    });
+    SequenceStep::Buffered
+}
+
+fn flush_sequence(state: &mut State) -> Vec<EvdevInputEvent> {
+    state.sequence_path.clear();
+    state.modifiers = state.sequence_modifiers.clone();
+    state.sequence_buffer.drain(..).collect()
+}
+
+// clears the buffer of a matched sequence, keys that are still held get their release swallowed
+fn finish_sequence(state: &mut State) {
+    for ev in state.sequence_buffer.drain(..) {
+        let key = Key { event_code: ev.event_code };
+        if key.is_modifier() {
+            continue;
+        }
+        match ev.value {
+            TYPE_DOWN => state.sequence_swallowed.insert(key),
+            TYPE_UP => state.sequence_swallowed.remove(&key),
+            _ => false,
+        };
+    }
+    state.sequence_path.clear();
+}
+
+// returns false if the event is a source repeat that gets replaced by generated ones
+fn update_repeat(state: &mut State, _state: &Arc<Mutex<State>>, action: &KeyActionWithMods) -> bool {
+    let key = action.key;
+    let repeat = state.repeat_for(action);
+    match action.value {
+        TYPE_REPEAT => matches!(repeat, Repeat::Passthrough),
+        TYPE_DOWN if !key.is_modifier() => {
+            // like on a real keyboard only the most recently pressed key repeats
+            state.timers.cancel(&TimerId::Repeat);
+            state.repeat_key = None;
+
+            let click = KeyClickActionWithMods::new_with_mods(key, action.modifiers);
+            let is_special = state.layer().key_patterns.contains_key(&click)
+                || state.layer().turbo.contains_key(&click)
+                || state.layer().motion.contains_key(&click);
+            if let (Repeat::Generated { delay, interval }, false) = (repeat, is_special) {
+                let _state = _state.clone();
+                let action = KeyActionWithMods { value: TYPE_REPEAT, ..*action };
+                state.repeat_key = Some(key);
+                state.timers.spawn_after(TimerId::Repeat, delay, async move {
+                    let mut interval = tokio::time::interval(interval);
+                    loop {
+                        interval.tick().await;
+                        let state = _state.lock().await;
+                        let runtime_action = state.lookup(&action).cloned().unwrap_or_else(|| {
+                            RuntimeAction::ActionSequence(vec![RuntimeKeyAction::KeyAction(KeyAction::new(key, TYPE_REPEAT))])
+                        });
+                        run_action(&_state, state, runtime_action, key.to_input_ev(TYPE_REPEAT)).await;
+                    }
+                });
+            }
+            true
+        }
+        TYPE_UP if state.repeat_key == Some(key) => {
+            state.timers.cancel(&TimerId::Repeat);
+            state.repeat_key = None;
+            true
+        }
+        _ => true,
+    }
+}
+
+// returns true if the event belongs to a turbo mapping
+fn handle_turbo(state: &mut State, _state: &Arc<Mutex<State>>, action: &KeyActionWithMods) -> bool {
+    let key = action.key;
+    match action.value {
+        TYPE_DOWN => {
+            let turbo = match state.layer().turbo.get(&KeyClickActionWithMods::new_with_mods(key, action.modifiers)) {
+                Some(turbo) => turbo.clone(),
+                None => return false,
+            };
+
+            let _state = _state.clone();
+            state.timers.spawn(TimerId::Turbo(key), async move {
+                let mut interval = tokio::time::interval(turbo.interval);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                loop {
+                    interval.tick().await;
+                    let state = _state.lock().await;
+                    run_action(&_state, state, turbo.action.clone(), key.to_input_ev(TYPE_DOWN)).await;
+                }
+            });
+            true
+        }
+        TYPE_UP => state.timers.cancel(&TimerId::Turbo(key)),
+        _ => state.timers.is_pending(&TimerId::Turbo(key)),
+    }
+}
+
+// returns true if the event belongs to a motion mapping, all held motion keys share one timer so moving
+// diagonally and speeding up happen in the same steps
+fn handle_motion(state: &mut State, _state: &Arc<Mutex<State>>, action: &KeyActionWithMods) -> bool {
+    let key = action.key;
+    match action.value {
+        TYPE_DOWN => {
+            let motion = match state.layer().motion.get(&KeyClickActionWithMods::new_with_mods(key, action.modifiers)) {
+                Some(motion) => *motion,
+                None => return false,
+            };
+
+            if state.motion.is_empty() {
+                let _state = _state.clone();
+                state.timers.spawn(TimerId::Motion, async move {
+                    let mut interval = tokio::time::interval(MOTION_INTERVAL);
+                    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                    loop {
+                        interval.tick().await;
+                        let mut state = _state.lock().await;
+                        for ev in state.motion.step() {
+                            state.emit(ev);
+                        }
+                    }
+                });
+            }
+            state.motion.press(key, motion);
+            true
+        }
+        TYPE_UP => {
+            if !state.motion.release(&key) {
+                return false;
+            }
+            if state.motion.is_empty() {
+                state.timers.cancel(&TimerId::Motion);
+            }
+            true
+        }
+        _ => state.motion.is_held(&key),
+    }
 }
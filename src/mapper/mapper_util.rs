@@ -13,6 +13,184 @@ pub fn hash_path(path: &Vec<uuid::Uuid>) -> u64 {
     path_hash
 }
 
+pub fn parse_modifier_flags(names: &[String]) -> Result<KeyModifierFlags> {
+    let mut flags = KeyModifierFlags::new();
+    for name in names {
+        match &*name.to_lowercase() {
+            "ctrl" => flags.ctrl(),
+            "shift" => flags.shift(),
+            "alt" => flags.alt(),
+            "right_alt" => flags.right_alt(),
+            "meta" => flags.meta(),
+            _ => {
+                return Err(anyhow!("unknown modifier '{}', expected one of: ctrl, shift, alt, right_alt, meta", name))
+            }
+        }
+    }
+    Ok(flags)
+}
+
+// lowercase key name without the "KEY_" prefix, the way the key parser accepts it
+pub fn key_name(key: &Key) -> String {
+    let name = match key.event_code {
+        EventCode::EV_KEY(key) => format!("{key:?}"),
+        event_code => format!("{event_code:?}"),
+    };
+    name.strip_prefix("KEY_").unwrap_or(&name).to_lowercase()
+}
+
+pub fn modifier_names(flags: &KeyModifierFlags) -> Vec<String> {
+    [
+        (flags.ctrl, "ctrl"),
+        (flags.shift, "shift"),
+        (flags.alt, "alt"),
+        (flags.right_alt, "right_alt"),
+        (flags.meta, "meta"),
+    ]
+    .iter()
+    .filter(|(set, _)| *set)
+    .map(|(_, name)| name.to_string())
+    .collect()
+}
+
+// formats a trigger the way the key parser accepts it, i.e. "^a" or "^{a down}"
+pub fn format_key_trigger(
+    key: &Key,
+    value: Option<i32>,
+    modifiers: &KeyModifierFlags,
+    transformer: &XKBTransformer,
+) -> Result<String> {
+    if modifiers.right_alt {
+        return format_right_alt_trigger(key, value, modifiers, transformer);
+    }
+    Ok(format_trigger_with(&key_name(key), value, modifiers))
+}
+
+fn format_trigger_with(name: &str, value: Option<i32>, modifiers: &KeyModifierFlags) -> String {
+    let mut acc = String::new();
+    for (set, prefix) in [(modifiers.ctrl, '^'), (modifiers.alt, '!'), (modifiers.shift, '+'), (modifiers.meta, '#')] {
+        if set {
+            acc.push(prefix);
+        }
+    }
+    match value {
+        Some(value) => acc.push_str(&format!("{{{} {}}}", name, format_key_state(value))),
+        None => acc.push_str(name),
+    }
+    acc
+}
+
+// right alt has no prefix, the trigger is written as the character it types instead, i.e. "€". Shift is either
+// part of the character or written as a prefix, whichever parses back to the same trigger.
+fn format_right_alt_trigger(
+    key: &Key,
+    value: Option<i32>,
+    modifiers: &KeyModifierFlags,
+    transformer: &XKBTransformer,
+) -> Result<String> {
+    let err = || anyhow!("the right alt trigger on '{}' can't be written in the keyboard layout", key_name(key));
+    let ev_key = match key.event_code {
+        EventCode::EV_KEY(ev_key) => ev_key,
+        _ => return Err(err()),
+    };
+
+    let shifted = if modifiers.shift { vec![true, false] } else { vec![false] };
+    for shifted in shifted {
+        let mut state = KeyModifierState::new();
+        state.right_alt = true;
+        state.left_shift = shifted;
+        let ch = match transformer.raw_to_utf(&ev_key, &state) {
+            Some(ch) if matches!(ch.as_str(), "\\" | "{" | "}") => format!("\\{}", ch),
+            Some(ch) => ch,
+            None => continue,
+        };
+
+        let mut prefixes = *modifiers;
+        prefixes.right_alt = false;
+        prefixes.shift = modifiers.shift && !shifted;
+        let formatted = format_trigger_with(&ch, value, &prefixes);
+
+        let parsed = match parse_key_action_with_mods(&formatted, Some(transformer)) {
+            Ok(ParsedKeyAction::KeyClickAction(action)) if value.is_none() => (action.key, action.modifiers),
+            Ok(ParsedKeyAction::KeyAction(action)) if value == Some(action.value) => (action.key, action.modifiers),
+            _ => continue,
+        };
+        if parsed == (*key, *modifiers) {
+            return Ok(formatted);
+        }
+    }
+    Err(err())
+}
+
+pub fn format_key_state(value: i32) -> &'static str {
+    match value {
+        TYPE_UP => "up",
+        TYPE_DOWN => "down",
+        _ => "repeat",
+    }
+}
+
+// formats runtime actions as a key sequence that emits the same events
+pub fn format_key_sequence(seq: &[RuntimeKeyAction]) -> String {
+    let mut acc = String::new();
+    let mut iter = seq.iter().peekable();
+    while let Some(action) = iter.next() {
+        match action {
+            RuntimeKeyAction::KeyAction(action) => {
+                let name = key_name(&action.key);
+                let is_click = action.value == TYPE_DOWN
+                    && matches!(iter.peek(), Some(RuntimeKeyAction::KeyAction(next)) if next.key == action.key && next.value == TYPE_UP);
+                if !is_click {
+                    acc.push_str(&format!("{{{} {}}}", name, format_key_state(action.value)));
+                    continue;
+                }
+                iter.next();
+                if name.chars().count() == 1 {
+                    acc.push_str(&name);
+                } else {
+                    acc.push_str(&format!("{{{}}}", name));
+                }
+            }
+            // depends on the modifiers held at runtime
+            RuntimeKeyAction::ReleaseRestoreModifiers(..) => {}
+            RuntimeKeyAction::Toggle(key) => acc.push_str(&format!("{{toggle {}}}", key_name(key))),
+            // described by `mapping_info` instead, macro actions are never part of longer sequences
+            RuntimeKeyAction::Macro(_) => {}
+        }
+    }
+    acc
+}
+
+/// Describes a mapping target as a python dict with the keys `from`, `kind` and `to`, macro actions also
+/// contain their `register` and `timing`.
+pub fn mapping_info<'py>(py: Python<'py>, from: impl ToPyObject, action: &RuntimeAction) -> PyResult<&'py PyDict> {
+    if let RuntimeAction::ActionSequence(seq) = action {
+        if let [RuntimeKeyAction::Macro(action)] = seq.as_slice() {
+            let info = PyDict::new(py);
+            info.set_item("from", from)?;
+            info.set_item("to", py.None())?;
+            action.describe(info)?;
+            return Ok(info);
+        }
+    }
+
+    let (kind, to) = match action {
+        RuntimeAction::ActionSequence(seq) => ("sequence", Some(format_key_sequence(seq))),
+        RuntimeAction::PythonCallback(_, handler) => {
+            let handler = handler.deref().as_ref(py);
+            let name = handler.getattr("__name__").and_then(|name| name.extract::<String>());
+            ("callback", Some(name.unwrap_or_else(|_| handler.to_string())))
+        }
+        RuntimeAction::NOP => ("nop", None),
+    };
+
+    let info = PyDict::new(py);
+    info.set_item("from", from)?;
+    info.set_item("kind", kind)?;
+    info.set_item("to", to)?;
+    Ok(info)
+}
+
 #[derive(Debug, Clone)]
 pub enum PythonReturn {
     String(String),
@@ -68,7 +246,7 @@ pub async fn run_python_handler(
 
                 match ret {
                     Some(PythonReturn::String(ret)) => {
-                        let seq = parse_key_sequence(&ret, Some(&transformer))?;
+                        let seq = parse_key_sequence_without_toggles(&ret, Some(&transformer))?;
 
                         for action in seq.to_key_actions() {
                             next.send_all(InputEvent::Raw(action.to_input_ev()));
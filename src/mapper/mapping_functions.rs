@@ -1,11 +1,15 @@
 use pyo3::prelude::*;
 
 use crate::*;
+use super::macro_recorder::MacroAction;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum RuntimeKeyAction {
     KeyAction(KeyAction),
     ReleaseRestoreModifiers(KeyModifierFlags, KeyModifierFlags, i32),
+    // latches the key down or releases it, depending on the mapper state
+    Toggle(Key),
+    Macro(MacroAction),
 }
 
 #[derive(Clone, Debug)]
@@ -16,12 +20,37 @@ pub enum RuntimeAction {
     NOP,
 }
 
+impl PartialEq for RuntimeAction {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (RuntimeAction::ActionSequence(a), RuntimeAction::ActionSequence(b)) => a == b,
+            // callbacks are only equal to themselves
+            (RuntimeAction::PythonCallback(a_flags, a), RuntimeAction::PythonCallback(b_flags, b)) => {
+                a_flags == b_flags && Arc::ptr_eq(a, b)
+            }
+            (RuntimeAction::NOP, RuntimeAction::NOP) => true,
+            _ => false,
+        }
+    }
+}
+
 pub type Mapping = (KeyActionWithMods, RuntimeAction);
 pub type Mappings = HashMap<KeyActionWithMods, RuntimeAction>;
 
+pub fn to_runtime_key_actions(to: Vec<ParsedKeyAction>) -> Vec<RuntimeKeyAction> {
+    to.into_iter().fold(vec![], |mut acc, action| {
+        match action {
+            ParsedKeyAction::Toggle(key) => acc.push(RuntimeKeyAction::Toggle(key)),
+            action => {
+                acc.extend(vec![action].to_key_actions().into_iter().map(|action| RuntimeKeyAction::KeyAction(action)))
+            }
+        }
+        acc
+    })
+}
+
 pub fn map_action_to_seq(from: KeyActionWithMods, to: Vec<ParsedKeyAction>) -> Mapping {
-    let mut seq: Vec<RuntimeKeyAction> =
-        to.to_key_actions().into_iter().map(|action| RuntimeKeyAction::KeyAction(action)).collect();
+    let mut seq: Vec<RuntimeKeyAction> = to_runtime_key_actions(to);
 
     seq.insert(0, RuntimeKeyAction::ReleaseRestoreModifiers(from.modifiers.clone(), KeyModifierFlags::new(), TYPE_UP));
 
@@ -118,8 +147,7 @@ pub fn map_action_to_action(from: &KeyActionWithMods, to: &KeyActionWithMods) ->
 }
 
 pub fn map_click_to_seq(from: KeyClickActionWithMods, to: Vec<ParsedKeyAction>) -> [Mapping; 3] {
-    let mut seq: Vec<RuntimeKeyAction> =
-        to.to_key_actions().into_iter().map(|action| RuntimeKeyAction::KeyAction(action)).collect();
+    let mut seq: Vec<RuntimeKeyAction> = to_runtime_key_actions(to);
 
     seq.insert(0, RuntimeKeyAction::ReleaseRestoreModifiers(from.modifiers.clone(), KeyModifierFlags::new(), TYPE_UP));
 
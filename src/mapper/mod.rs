@@ -1,14 +1,41 @@
+mod abs_rel_mapper;
+mod axis_mapper;
+mod button_chord_mapper;
 mod chord_mapper;
+mod gesture_mapper;
+mod key_trie;
+mod keymap_file;
+mod macro_recorder;
 mod mapper;
 mod mapper_util;
 mod mapping_functions;
+mod motion;
+mod pointer_mapper;
+mod scroll_mapper;
 mod suffix_tree;
+mod tablet_mapper;
 mod text_mapper;
+mod text_template;
+mod timers;
+mod touchpad_mapper;
 
+pub use abs_rel_mapper::AbsRelMapper;
+pub use axis_mapper::AxisMapper;
+pub use button_chord_mapper::ButtonChordMapper;
 pub use chord_mapper::ChordMapper;
+pub use gesture_mapper::GestureMapper;
 pub use mapper::{KeyMapperSnapshot, Mapper, MapperLink};
 pub use mapping_functions::*;
+pub use pointer_mapper::PointerMapper;
+pub use scroll_mapper::ScrollMapper;
+pub use tablet_mapper::TabletMapper;
 pub use text_mapper::TextMapper;
+pub use touchpad_mapper::TouchpadMapper;
 
 use crate::subscriber::*;
+use key_trie::KeyTrie;
+use keymap_file::*;
+use macro_recorder::*;
 use mapper_util::*;
+use motion::*;
+use timers::Timers;
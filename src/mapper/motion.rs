@@ -0,0 +1,187 @@
+use crate::python::*;
+use crate::*;
+use evdev_rs::enums::EV_REL;
+use std::time::Instant;
+
+/// How often held motion keys move the pointer or scroll.
+pub const MOTION_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MotionMapping {
+    pub axis: EV_REL,
+    // units per step, the sign is the direction
+    pub speed: f64,
+    // units per step added for every second the key is held
+    pub accel: f64,
+    pub max_speed: Option<f64>,
+}
+
+impl MotionMapping {
+    pub fn from_options(options: &HashMap<&str, &PyAny>, axis: EV_REL) -> PyResult<Self> {
+        // infinite speeds saturate the motion and NaN slips past the range checks
+        let number = |name: &str| -> PyResult<Option<f64>> {
+            match options.get(name) {
+                Some(value) => {
+                    let value = value
+                        .extract::<f64>()
+                        .map_err(|_| PyRuntimeError::new_err(format!("'{}' must be of type 'number'", name)))?;
+                    if !value.is_finite() {
+                        return Err(PyRuntimeError::new_err(format!("'{}' must be a finite number", name)));
+                    }
+                    Ok(Some(value))
+                }
+                None => Ok(None),
+            }
+        };
+
+        let speed = number("speed")?.ok_or_else(|| PyRuntimeError::new_err("missing option 'speed'"))?;
+        let accel = number("accel")?.unwrap_or(0.0);
+        if accel < 0.0 {
+            return Err(PyRuntimeError::new_err("'accel' must not be negative"));
+        }
+        let max_speed = number("max_speed")?;
+        if max_speed.map_or(false, |max_speed| max_speed < speed.abs()) {
+            return Err(PyRuntimeError::new_err("'max_speed' must be at least the absolute value of 'speed'"));
+        }
+
+        Ok(MotionMapping { axis, speed, accel, max_speed })
+    }
+
+    // the speed after the key was held for some time
+    fn speed_at(&self, held: Duration) -> f64 {
+        let speed = self.speed.abs() + self.accel * held.as_secs_f64();
+        let speed = match self.max_speed {
+            Some(max_speed) => speed.min(max_speed),
+            None => speed,
+        };
+        speed.copysign(self.speed)
+    }
+
+    pub fn describe(&self, info: &PyDict) -> PyResult<()> {
+        info.set_item("kind", "motion")?;
+        info.set_item("axis", format_motion_axis(self.axis))?;
+        info.set_item("speed", self.speed)?;
+        if self.accel != 0.0 {
+            info.set_item("accel", self.accel)?;
+        }
+        if let Some(max_speed) = self.max_speed {
+            info.set_item("max_speed", max_speed)?;
+        }
+        Ok(())
+    }
+}
+
+/// The motion keys currently held, all of them move along a shared timer.
+#[derive(Default)]
+pub struct MotionState {
+    held: Vec<(Key, MotionMapping, Instant)>,
+    // fractions of a unit not emitted yet, carried over to the next step
+    remainders: Vec<(EV_REL, f64)>,
+}
+
+impl MotionState {
+    pub fn press(&mut self, key: Key, motion: MotionMapping) {
+        if !self.is_held(&key) {
+            self.held.push((key, motion, Instant::now()));
+        }
+    }
+
+    pub fn release(&mut self, key: &Key) -> bool {
+        let len = self.held.len();
+        self.held.retain(|(held, _, _)| held != key);
+        if self.held.is_empty() {
+            self.remainders.clear();
+        }
+        self.held.len() != len
+    }
+
+    pub fn is_held(&self, key: &Key) -> bool {
+        self.held.iter().any(|(held, _, _)| held == key)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.held.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.held.clear();
+        self.remainders.clear();
+    }
+
+    /// The events moving all axes by one step, at the speed they have reached right now.
+    pub fn step(&mut self) -> Vec<EvdevInputEvent> {
+        let now = Instant::now();
+        let mut velocity: Vec<(EV_REL, f64)> = vec![];
+        for (_, motion, since) in self.held.iter() {
+            let speed = motion.speed_at(now.duration_since(*since));
+            match velocity.iter_mut().find(|(axis, _)| *axis == motion.axis) {
+                Some((_, value)) => *value += speed,
+                None => velocity.push((motion.axis, speed)),
+            }
+        }
+
+        // moving diagonally is as fast as moving along the faster of the two axes
+        let get = |velocity: &[(EV_REL, f64)], axis| velocity.iter().find(|(other, _)| *other == axis).map(|x| x.1);
+        if let (Some(x), Some(y)) = (get(&velocity, REL_X), get(&velocity, REL_Y)) {
+            if x != 0.0 && y != 0.0 {
+                let scale = x.abs().max(y.abs()) / x.hypot(y);
+                velocity.iter_mut().filter(|(axis, _)| matches!(axis, REL_X | REL_Y)).for_each(|(_, v)| *v *= scale);
+            }
+        }
+
+        let mut events = vec![];
+        for (axis, value) in velocity {
+            // opposite directions cancel out
+            if value == 0.0 {
+                self.remainders.retain(|(other, _)| *other != axis);
+                continue;
+            }
+            let value = value + get(&self.remainders, axis).unwrap_or(0.0);
+            let whole = value.trunc();
+            self.remainders.retain(|(other, _)| *other != axis);
+            self.remainders.push((axis, value - whole));
+            if whole != 0.0 {
+                events.push(EvdevInputEvent {
+                    event_code: EventCode::EV_REL(axis),
+                    value: whole as i32,
+                    time: INPUT_EV_DUMMY_TIME,
+                });
+            }
+        }
+        events
+    }
+}
+
+pub fn parse_motion_axis(options: &HashMap<&str, &PyAny>) -> PyResult<EV_REL> {
+    let axis = match options.get("axis").map(|axis| axis.extract::<String>()) {
+        Some(Ok(axis)) => axis,
+        Some(Err(_)) => return Err(PyRuntimeError::new_err("'axis' must be of type 'string'")),
+        None => return Err(PyRuntimeError::new_err("missing option 'axis'")),
+    };
+    match axis.as_str() {
+        "x" => Ok(REL_X),
+        "y" => Ok(REL_Y),
+        "wheel" => Ok(REL_WHEEL),
+        "hwheel" => Ok(REL_HWHEEL),
+        _ => Err(PyRuntimeError::new_err("'axis' must be one of: 'x', 'y', 'wheel', 'hwheel'")),
+    }
+}
+
+pub fn parse_scroll_axis(options: &HashMap<&str, &PyAny>) -> PyResult<EV_REL> {
+    match options.get("axis").map(|axis| axis.extract::<String>()) {
+        None => Ok(REL_WHEEL),
+        Some(Ok(axis)) if axis == "vertical" => Ok(REL_WHEEL),
+        Some(Ok(axis)) if axis == "horizontal" => Ok(REL_HWHEEL),
+        Some(_) => Err(PyRuntimeError::new_err("'axis' must be one of: 'vertical', 'horizontal'")),
+    }
+}
+
+fn format_motion_axis(axis: EV_REL) -> &'static str {
+    match axis {
+        REL_X => "x",
+        REL_Y => "y",
+        REL_WHEEL => "wheel",
+        REL_HWHEEL => "hwheel",
+        _ => "unknown",
+    }
+}
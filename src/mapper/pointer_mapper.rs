@@ -0,0 +1,557 @@
+use super::scroll_mapper::WheelAxis;
+use super::*;
+use crate::python::*;
+use crate::*;
+use evdev_rs::enums::EV_REL;
+use evdev_rs::TimeVal;
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex;
+
+#[derive(Default)]
+struct State {
+    prev: HashMap<Uuid, Arc<dyn LinkSrc>>,
+    next: HashMap<Uuid, Arc<dyn LinkDst>>,
+    settings: PointerSettings,
+    // the motion of the current frame, processed once the frame ends
+    frame: Option<(f64, f64, TimeVal)>,
+    // fractions of a unit not emitted yet, carried over to the next frame
+    remainders: (f64, f64),
+    // set while the scroll button is held
+    scroll: Option<ScrollGesture>,
+}
+
+/// Motion is only turned into scrolling after moving this far, less motion still counts as a click.
+const SCROLL_START_DISTANCE: f64 = 3.0;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScrollLock {
+    None,
+    // locks onto the axis that moves the most when scrolling starts
+    Dominant,
+    Vertical,
+    Horizontal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ButtonScroll {
+    button: Key,
+    // high-resolution scroll units per unit of motion
+    speed: f64,
+    lock: ScrollLock,
+}
+
+#[derive(Default)]
+struct ScrollGesture {
+    // the distance moved before scrolling started
+    distance: f64,
+    scrolling: bool,
+    // only scrolls along one axis, the vertical one if true
+    locked: Option<bool>,
+    vertical: WheelAxis,
+    horizontal: WheelAxis,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum AccelProfile {
+    Flat,
+    // faster motion moves the pointer further, the gain grows linearly above the threshold
+    Adaptive { accel: f64, threshold: f64, max_gain: Option<f64> },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct PointerSettings {
+    sensitivity: f64,
+    profile: AccelProfile,
+    invert_x: bool,
+    invert_y: bool,
+    swap_axes: bool,
+    // degrees, clockwise
+    rotation: f64,
+    scroll: Option<ButtonScroll>,
+}
+
+impl Default for PointerSettings {
+    fn default() -> Self {
+        PointerSettings {
+            sensitivity: 1.0,
+            profile: AccelProfile::Flat,
+            invert_x: false,
+            invert_y: false,
+            swap_axes: false,
+            rotation: 0.0,
+            scroll: None,
+        }
+    }
+}
+
+impl PointerSettings {
+    // options that aren't given keep their current value
+    fn update(&self, options: &HashMap<&str, &PyAny>) -> PyResult<Self> {
+        // NaN and infinity would get stuck in the sub-pixel remainders and freeze the pointer
+        let number = |name: &str| -> PyResult<Option<f64>> {
+            match options.get(name) {
+                Some(value) => {
+                    let value = value
+                        .extract::<f64>()
+                        .map_err(|_| PyRuntimeError::new_err(format!("'{}' must be of type 'number'", name)))?;
+                    if !value.is_finite() {
+                        return Err(PyRuntimeError::new_err(format!("'{}' must be a finite number", name)));
+                    }
+                    Ok(Some(value))
+                }
+                None => Ok(None),
+            }
+        };
+        let flag = |name: &str, current: bool| -> PyResult<bool> {
+            match options.get(name) {
+                Some(value) => {
+                    value.extract().map_err(|_| PyRuntimeError::new_err(format!("'{}' must be of type 'bool'", name)))
+                }
+                None => Ok(current),
+            }
+        };
+
+        let mut settings = *self;
+        if let Some(sensitivity) = number("sensitivity")? {
+            if sensitivity <= 0.0 {
+                return Err(PyRuntimeError::new_err("'sensitivity' must be a positive number"));
+            }
+            settings.sensitivity = sensitivity;
+        }
+
+        let adaptive = match options.get("profile").map(|profile| profile.extract::<String>()) {
+            None => matches!(self.profile, AccelProfile::Adaptive { .. }),
+            Some(Ok(profile)) if profile == "flat" => false,
+            Some(Ok(profile)) if profile == "adaptive" => true,
+            Some(_) => return Err(PyRuntimeError::new_err("'profile' must be one of: 'flat', 'adaptive'")),
+        };
+        settings.profile = if adaptive {
+            let (accel, threshold, max_gain) = match self.profile {
+                AccelProfile::Adaptive { accel, threshold, max_gain } => (accel, threshold, max_gain),
+                AccelProfile::Flat => (0.1, 4.0, None),
+            };
+            let accel = number("accel")?.unwrap_or(accel);
+            if accel < 0.0 {
+                return Err(PyRuntimeError::new_err("'accel' must not be negative"));
+            }
+            let threshold = number("threshold")?.unwrap_or(threshold);
+            if threshold < 0.0 {
+                return Err(PyRuntimeError::new_err("'threshold' must not be negative"));
+            }
+            let max_gain = match options.get("max_gain") {
+                Some(_) => number("max_gain")?,
+                None => max_gain,
+            };
+            if max_gain.map_or(false, |max_gain| max_gain < 1.0) {
+                return Err(PyRuntimeError::new_err("'max_gain' must be at least 1"));
+            }
+            AccelProfile::Adaptive { accel, threshold, max_gain }
+        } else {
+            if ["accel", "threshold", "max_gain"].iter().any(|name| options.contains_key(name)) {
+                return Err(PyRuntimeError::new_err(
+                    "'accel', 'threshold' and 'max_gain' require the 'adaptive' profile",
+                ));
+            }
+            AccelProfile::Flat
+        };
+
+        settings.invert_x = flag("invert_x", self.invert_x)?;
+        settings.invert_y = flag("invert_y", self.invert_y)?;
+        settings.swap_axes = flag("swap_axes", self.swap_axes)?;
+        if let Some(rotation) = number("rotation")? {
+            settings.rotation = rotation % 360.0;
+        }
+
+        settings.scroll = match options.get("scroll_button") {
+            Some(button) if button.is_none() => None,
+            Some(button) => {
+                let button = button
+                    .extract::<String>()
+                    .map_err(|_| PyRuntimeError::new_err("'scroll_button' must be of type 'string'"))?;
+                let button = parse_key(&button, None).map_err(|err| {
+                    PyRuntimeError::new_err(format!(
+                        "mapping error on the 'scroll_button' side:\n{}",
+                        ApplicationError::KeyParse(err.to_string()),
+                    ))
+                })?;
+                let (speed, lock) = self.scroll.map_or((10.0, ScrollLock::None), |scroll| (scroll.speed, scroll.lock));
+                Some(ButtonScroll { button, speed, lock })
+            }
+            None => self.scroll,
+        };
+        if let Some(speed) = number("scroll_speed")? {
+            if speed <= 0.0 {
+                return Err(PyRuntimeError::new_err("'scroll_speed' must be a positive number"));
+            }
+            if let Some(scroll) = settings.scroll.as_mut() {
+                scroll.speed = speed;
+            }
+        }
+        if let Some(lock) = options.get("scroll_lock") {
+            let lock = match lock.extract::<String>() {
+                Ok(lock) if lock == "none" => ScrollLock::None,
+                Ok(lock) if lock == "dominant" => ScrollLock::Dominant,
+                Ok(lock) if lock == "vertical" => ScrollLock::Vertical,
+                Ok(lock) if lock == "horizontal" => ScrollLock::Horizontal,
+                _ => {
+                    return Err(PyRuntimeError::new_err(
+                        "'scroll_lock' must be one of: 'none', 'dominant', 'vertical', 'horizontal'",
+                    ))
+                }
+            };
+            if let Some(scroll) = settings.scroll.as_mut() {
+                scroll.lock = lock;
+            }
+        }
+        if settings.scroll.is_none() && (options.contains_key("scroll_speed") || options.contains_key("scroll_lock")) {
+            return Err(PyRuntimeError::new_err("'scroll_speed' and 'scroll_lock' require a 'scroll_button'"));
+        }
+        Ok(settings)
+    }
+
+    // how much the motion of a frame is amplified, `speed` is the distance moved in the frame
+    fn gain(&self, speed: f64) -> f64 {
+        let gain = match self.profile {
+            AccelProfile::Flat => 1.0,
+            AccelProfile::Adaptive { accel, threshold, max_gain } => {
+                let gain = 1.0 + accel * (speed - threshold).max(0.0);
+                match max_gain {
+                    Some(max_gain) => gain.min(max_gain),
+                    None => gain,
+                }
+            }
+        };
+        gain * self.sensitivity
+    }
+
+    // the motion turned to match how the device is held, without sensitivity and acceleration
+    fn orient(&self, x: f64, y: f64) -> (f64, f64) {
+        let (x, y) = if self.swap_axes { (y, x) } else { (x, y) };
+        let x = if self.invert_x { -x } else { x };
+        let y = if self.invert_y { -y } else { y };
+
+        // the y axis points down, so a positive angle turns clockwise on screen
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        (x * cos - y * sin, x * sin + y * cos)
+    }
+
+    fn transform(&self, x: f64, y: f64) -> (f64, f64) {
+        let (x, y) = self.orient(x, y);
+        let gain = self.gain(x.hypot(y));
+        (x * gain, y * gain)
+    }
+
+    fn to_py_dict<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let dict = PyDict::new(py);
+        dict.set_item("sensitivity", self.sensitivity)?;
+        match self.profile {
+            AccelProfile::Flat => dict.set_item("profile", "flat")?,
+            AccelProfile::Adaptive { accel, threshold, max_gain } => {
+                dict.set_item("profile", "adaptive")?;
+                dict.set_item("accel", accel)?;
+                dict.set_item("threshold", threshold)?;
+                dict.set_item("max_gain", max_gain)?;
+            }
+        }
+        dict.set_item("invert_x", self.invert_x)?;
+        dict.set_item("invert_y", self.invert_y)?;
+        dict.set_item("swap_axes", self.swap_axes)?;
+        dict.set_item("rotation", self.rotation)?;
+        if let Some(scroll) = self.scroll {
+            dict.set_item("scroll_button", key_name(&scroll.button))?;
+            dict.set_item("scroll_speed", scroll.speed)?;
+            let lock = match scroll.lock {
+                ScrollLock::None => "none",
+                ScrollLock::Dominant => "dominant",
+                ScrollLock::Vertical => "vertical",
+                ScrollLock::Horizontal => "horizontal",
+            };
+            dict.set_item("scroll_lock", lock)?;
+        }
+        Ok(dict)
+    }
+}
+
+#[pyclass]
+pub struct PointerMapper {
+    pub id: Uuid,
+    pub link: Arc<PointerMapperLink>,
+    state: Arc<Mutex<State>>,
+}
+
+#[pymethods]
+impl PointerMapper {
+    #[new]
+    #[pyo3(signature = (**kwargs))]
+    pub fn new(kwargs: Option<&PyDict>) -> PyResult<Self> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let settings = PointerSettings::default().update(&options)?;
+
+        let id = Uuid::new_v4();
+        let (ev_tx, mut ev_rx) = tokio::sync::mpsc::channel(64);
+        let state = Arc::new(Mutex::new(State { settings, ..Default::default() }));
+        let link = Arc::new(PointerMapperLink { id, ev_tx, state: state.clone() });
+
+        {
+            let state = state.clone();
+            get_runtime().spawn(async move {
+                let mut ev = ev_rx.recv().await;
+                loop {
+                    match ev {
+                        Some(raw_ev) => handle(state.clone(), raw_ev).await,
+                        None => return,
+                    }
+                    ev = match ev_rx.try_recv() {
+                        Ok(next) => Some(next),
+                        // events that don't end with a sync, i.e. from other mappers, are processed once
+                        // no more events are queued
+                        Err(TryRecvError::Empty) => {
+                            state.lock().await.flush();
+                            ev_rx.recv().await
+                        }
+                        Err(TryRecvError::Disconnected) => return,
+                    };
+                }
+            });
+        }
+
+        Ok(Self { id, link, state })
+    }
+
+    #[pyo3(signature = (**kwargs))]
+    pub fn configure(&mut self, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let mut state = self.state.blocking_lock();
+        state.settings = state.settings.update(&options)?;
+        state.remainders = (0.0, 0.0);
+        state.release_scroll_button(None);
+        Ok(())
+    }
+
+    pub fn settings(&self, py: Python) -> PyResult<PyObject> {
+        Ok(self.state.blocking_lock().settings.to_py_dict(py)?.to_object(py))
+    }
+
+    pub fn link_to(&mut self, target: &PyAny) -> PyResult<()> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.link_from(self.link.clone());
+        self.link.link_to(target);
+        Ok(())
+    }
+
+    pub fn unlink_to(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.unlink_from(&self.id);
+        self.link.unlink_to(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_to_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.next.values_mut() {
+            l.unlink_from(&self.id);
+        }
+        state.next.clear();
+    }
+
+    pub fn unlink_from(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_src(target).ok_or_else(|| PyRuntimeError::new_err("expected a source node"))?;
+        target.unlink_to(&self.id);
+        self.link.unlink_from(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_from_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.prev.values_mut() {
+            l.unlink_to(&self.id);
+        }
+        state.prev.clear();
+    }
+
+    pub fn unlink_all(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+}
+
+impl Drop for PointerMapper {
+    fn drop(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+}
+
+#[derive(Clone)]
+pub struct PointerMapperLink {
+    id: Uuid,
+    ev_tx: Sender<InputEvent>,
+    state: Arc<Mutex<State>>,
+}
+
+impl LinkSrc for PointerMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_to(&self, node: Arc<dyn LinkDst>) -> Result<()> {
+        self.state.blocking_lock().next.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_to(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().next.remove(id).is_some())
+    }
+}
+
+impl LinkDst for PointerMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_from(&self, node: Arc<dyn LinkSrc>) -> Result<()> {
+        self.state.blocking_lock().prev.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_from(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().prev.remove(id).is_some())
+    }
+    fn send(&self, ev: InputEvent) -> Result<()> {
+        self.ev_tx.try_send(ev).map_err(|err| ApplicationError::TooManyEvents.into_py())?;
+        Ok(())
+    }
+}
+
+impl State {
+    fn push(&mut self, axis: EV_REL, value: i32, time: TimeVal) {
+        let (x, y, _) = self.frame.unwrap_or((0.0, 0.0, time));
+        self.frame = Some(match axis {
+            REL_X => (x + value as f64, y, time),
+            _ => (x, y + value as f64, time),
+        });
+    }
+
+    // sends the transformed motion of the current frame
+    fn flush(&mut self) {
+        let (x, y, time) = match self.frame.take() {
+            Some(frame) => frame,
+            None => return,
+        };
+        if self.scroll.is_some() {
+            return self.send_scroll(x, y, time);
+        }
+        let (x, y) = self.settings.transform(x, y);
+
+        let x = x + self.remainders.0;
+        let y = y + self.remainders.1;
+        self.remainders = (x.fract(), y.fract());
+
+        for (axis, value) in [(REL_X, x.trunc()), (REL_Y, y.trunc())] {
+            if value != 0.0 {
+                self.next.send_all(InputEvent::Raw(EvdevInputEvent {
+                    event_code: EventCode::EV_REL(axis),
+                    value: value as i32,
+                    time,
+                }));
+            }
+        }
+    }
+
+    fn is_scroll_button(&self, key: &Key) -> bool {
+        self.settings.scroll.map_or(false, |scroll| scroll.button == *key)
+    }
+
+    // turns the motion into scrolling while the scroll button is held
+    fn send_scroll(&mut self, x: f64, y: f64, time: TimeVal) {
+        let settings = match self.settings.scroll {
+            Some(settings) => settings,
+            None => return,
+        };
+        let (x, y) = self.settings.orient(x, y);
+        let gesture = match self.scroll.as_mut() {
+            Some(gesture) => gesture,
+            None => return,
+        };
+
+        if !gesture.scrolling {
+            gesture.distance += x.hypot(y);
+            if gesture.distance < SCROLL_START_DISTANCE {
+                return;
+            }
+            gesture.scrolling = true;
+            gesture.locked = match settings.lock {
+                ScrollLock::None => None,
+                ScrollLock::Dominant => Some(y.abs() >= x.abs()),
+                ScrollLock::Vertical => Some(true),
+                ScrollLock::Horizontal => Some(false),
+            };
+        }
+
+        // moving down scrolls down, the wheel is positive when scrolling up
+        let vertical = if gesture.locked == Some(false) { 0.0 } else { -y * settings.speed };
+        let horizontal = if gesture.locked == Some(true) { 0.0 } else { x * settings.speed };
+        let (wheel_hi_res, wheel) = gesture.vertical.scroll(vertical);
+        let (hwheel_hi_res, hwheel) = gesture.horizontal.scroll(horizontal);
+        for (axis, value) in [
+            (REL_WHEEL_HI_RES, wheel_hi_res),
+            (REL_WHEEL, wheel),
+            (REL_HWHEEL_HI_RES, hwheel_hi_res),
+            (REL_HWHEEL, hwheel),
+        ] {
+            if value != 0 {
+                self.next.send_all(InputEvent::Raw(EvdevInputEvent {
+                    event_code: EventCode::EV_REL(axis),
+                    value,
+                    time,
+                }));
+            }
+        }
+    }
+
+    // a scroll button released without scrolling is sent as a click
+    fn release_scroll_button(&mut self, time: Option<TimeVal>) {
+        let gesture = match self.scroll.take() {
+            Some(gesture) => gesture,
+            None => return,
+        };
+        if gesture.scrolling {
+            return;
+        }
+        let (button, time) = match (self.settings.scroll, time) {
+            (Some(scroll), Some(time)) => (scroll.button, time),
+            _ => return,
+        };
+        for value in [TYPE_DOWN, TYPE_UP] {
+            self.next.send_all(InputEvent::Raw(EvdevInputEvent { event_code: button.event_code, value, time }));
+        }
+    }
+}
+
+async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
+    let mut state = _state.lock().await;
+
+    let ev = match &raw_ev {
+        InputEvent::Raw(ev) => ev,
+    };
+
+    match ev.event_code {
+        EventCode::EV_REL(axis @ (REL_X | REL_Y)) => state.push(axis, ev.value, ev.time),
+        EventCode::EV_KEY(_) if state.is_scroll_button(&Key { event_code: ev.event_code }) => {
+            state.flush();
+            match ev.value {
+                TYPE_DOWN => state.scroll = Some(Default::default()),
+                TYPE_UP => state.release_scroll_button(Some(ev.time)),
+                _ => {}
+            }
+        }
+        // a sync or any other event ends the frame
+        _ => {
+            state.flush();
+            state.next.send_all(raw_ev);
+        }
+    }
+}
@@ -0,0 +1,348 @@
+use super::*;
+use crate::python::*;
+use crate::*;
+use evdev_rs::enums::EV_REL;
+use evdev_rs::TimeVal;
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex;
+
+#[derive(Default)]
+struct State {
+    prev: HashMap<Uuid, Arc<dyn LinkSrc>>,
+    next: HashMap<Uuid, Arc<dyn LinkDst>>,
+    settings: ScrollSettings,
+    // the scrolling of the current frame, processed once the frame ends
+    frame: Option<TimeVal>,
+    vertical: WheelAxis,
+    horizontal: WheelAxis,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScrollOutput {
+    // high-resolution and legacy events, like a device with a high-resolution wheel
+    Both,
+    HiRes,
+    Discrete,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ScrollSettings {
+    output: ScrollOutput,
+    scale: f64,
+    invert: bool,
+}
+
+impl Default for ScrollSettings {
+    fn default() -> Self {
+        ScrollSettings { output: ScrollOutput::Both, scale: 1.0, invert: false }
+    }
+}
+
+impl ScrollSettings {
+    // options that aren't given keep their current value
+    fn update(&self, options: &HashMap<&str, &PyAny>) -> PyResult<Self> {
+        let mut settings = *self;
+        settings.output = match options.get("output").map(|output| output.extract::<String>()) {
+            None => self.output,
+            Some(Ok(output)) if output == "both" => ScrollOutput::Both,
+            Some(Ok(output)) if output == "hi_res" => ScrollOutput::HiRes,
+            Some(Ok(output)) if output == "discrete" => ScrollOutput::Discrete,
+            Some(_) => return Err(PyRuntimeError::new_err("'output' must be one of: 'both', 'hi_res', 'discrete'")),
+        };
+        if let Some(scale) = options.get("scale") {
+            settings.scale = match scale.extract::<f64>() {
+                Ok(scale) if scale > 0.0 && scale.is_finite() => scale,
+                _ => return Err(PyRuntimeError::new_err("'scale' must be a positive number")),
+            };
+        }
+        if let Some(invert) = options.get("invert") {
+            settings.invert =
+                invert.extract().map_err(|_| PyRuntimeError::new_err("'invert' must be of type 'bool'"))?;
+        }
+        Ok(settings)
+    }
+
+    fn to_py_dict<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let dict = PyDict::new(py);
+        let output = match self.output {
+            ScrollOutput::Both => "both",
+            ScrollOutput::HiRes => "hi_res",
+            ScrollOutput::Discrete => "discrete",
+        };
+        dict.set_item("output", output)?;
+        dict.set_item("scale", self.scale)?;
+        dict.set_item("invert", self.invert)?;
+        Ok(dict)
+    }
+}
+
+/// The scrolling along one axis, in high-resolution units.
+#[derive(Default)]
+pub(super) struct WheelAxis {
+    pending: i32,
+    // whether the current frame has a high-resolution event, kept until the frame ends
+    hi_res: bool,
+    // fractions of a high-resolution unit not emitted yet
+    remainder: f64,
+    // high-resolution units that don't add up to a full detent yet
+    detents: i32,
+}
+
+impl WheelAxis {
+    fn push(&mut self, hi_res: bool, value: i32) {
+        // devices with a high-resolution wheel send both kinds, the legacy events are redundant then
+        if hi_res {
+            if !self.hi_res {
+                self.hi_res = true;
+                self.pending = 0;
+            }
+            self.pending += value;
+        } else if !self.hi_res {
+            self.pending += value * HI_RES_PER_DETENT;
+        }
+    }
+
+    // the (high-resolution, legacy) values to send for the pending scrolling
+    fn flush(&mut self, settings: &ScrollSettings) -> (i32, i32) {
+        let value = std::mem::take(&mut self.pending) as f64;
+        let (hi_res, legacy) = self.scroll(value * settings.scale * if settings.invert { -1.0 } else { 1.0 });
+        match settings.output {
+            ScrollOutput::Both => (hi_res, legacy),
+            ScrollOutput::HiRes => (hi_res, 0),
+            ScrollOutput::Discrete => (0, legacy),
+        }
+    }
+
+    /// Scrolls by some high-resolution units, returns the (high-resolution, legacy) values to send.
+    pub(super) fn scroll(&mut self, value: f64) -> (i32, i32) {
+        let value = value + self.remainder;
+        self.remainder = value.fract();
+        let hi_res = value.trunc() as i32;
+
+        // changing the direction starts a new detent
+        if hi_res.signum() * self.detents.signum() < 0 {
+            self.detents = 0;
+        }
+        self.detents += hi_res;
+        let legacy = self.detents / HI_RES_PER_DETENT;
+        self.detents -= legacy * HI_RES_PER_DETENT;
+        (hi_res, legacy)
+    }
+
+    fn clear(&mut self) {
+        *self = Default::default();
+    }
+}
+
+#[pyclass]
+pub struct ScrollMapper {
+    pub id: Uuid,
+    pub link: Arc<ScrollMapperLink>,
+    state: Arc<Mutex<State>>,
+}
+
+#[pymethods]
+impl ScrollMapper {
+    #[new]
+    #[pyo3(signature = (**kwargs))]
+    pub fn new(kwargs: Option<&PyDict>) -> PyResult<Self> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let settings = ScrollSettings::default().update(&options)?;
+
+        let id = Uuid::new_v4();
+        let (ev_tx, mut ev_rx) = tokio::sync::mpsc::channel(64);
+        let state = Arc::new(Mutex::new(State { settings, ..Default::default() }));
+        let link = Arc::new(ScrollMapperLink { id, ev_tx, state: state.clone() });
+
+        {
+            let state = state.clone();
+            get_runtime().spawn(async move {
+                let mut ev = ev_rx.recv().await;
+                loop {
+                    match ev {
+                        Some(raw_ev) => handle(state.clone(), raw_ev).await,
+                        None => return,
+                    }
+                    ev = match ev_rx.try_recv() {
+                        Ok(next) => Some(next),
+                        // events that don't end with a sync, i.e. from other mappers, are processed once
+                        // no more events are queued
+                        Err(TryRecvError::Empty) => {
+                            state.lock().await.flush();
+                            ev_rx.recv().await
+                        }
+                        Err(TryRecvError::Disconnected) => return,
+                    };
+                }
+            });
+        }
+
+        Ok(Self { id, link, state })
+    }
+
+    #[pyo3(signature = (**kwargs))]
+    pub fn configure(&mut self, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let mut state = self.state.blocking_lock();
+        state.settings = state.settings.update(&options)?;
+        state.vertical.clear();
+        state.horizontal.clear();
+        Ok(())
+    }
+
+    pub fn settings(&self, py: Python) -> PyResult<PyObject> {
+        Ok(self.state.blocking_lock().settings.to_py_dict(py)?.to_object(py))
+    }
+
+    pub fn link_to(&mut self, target: &PyAny) -> PyResult<()> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.link_from(self.link.clone());
+        self.link.link_to(target);
+        Ok(())
+    }
+
+    pub fn unlink_to(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.unlink_from(&self.id);
+        self.link.unlink_to(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_to_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.next.values_mut() {
+            l.unlink_from(&self.id);
+        }
+        state.next.clear();
+    }
+
+    pub fn unlink_from(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_src(target).ok_or_else(|| PyRuntimeError::new_err("expected a source node"))?;
+        target.unlink_to(&self.id);
+        self.link.unlink_from(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_from_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.prev.values_mut() {
+            l.unlink_to(&self.id);
+        }
+        state.prev.clear();
+    }
+
+    pub fn unlink_all(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+}
+
+impl Drop for ScrollMapper {
+    fn drop(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+}
+
+#[derive(Clone)]
+pub struct ScrollMapperLink {
+    id: Uuid,
+    ev_tx: Sender<InputEvent>,
+    state: Arc<Mutex<State>>,
+}
+
+impl LinkSrc for ScrollMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_to(&self, node: Arc<dyn LinkDst>) -> Result<()> {
+        self.state.blocking_lock().next.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_to(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().next.remove(id).is_some())
+    }
+}
+
+impl LinkDst for ScrollMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_from(&self, node: Arc<dyn LinkSrc>) -> Result<()> {
+        self.state.blocking_lock().prev.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_from(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().prev.remove(id).is_some())
+    }
+    fn send(&self, ev: InputEvent) -> Result<()> {
+        self.ev_tx.try_send(ev).map_err(|err| ApplicationError::TooManyEvents.into_py())?;
+        Ok(())
+    }
+}
+
+impl State {
+    fn push(&mut self, axis: EV_REL, value: i32, time: TimeVal) {
+        self.frame = Some(time);
+        match axis {
+            REL_WHEEL => self.vertical.push(false, value),
+            REL_WHEEL_HI_RES => self.vertical.push(true, value),
+            REL_HWHEEL => self.horizontal.push(false, value),
+            _ => self.horizontal.push(true, value),
+        }
+    }
+
+    // sends the converted scrolling of the current frame
+    fn flush(&mut self) {
+        let time = match self.frame.take() {
+            Some(time) => time,
+            None => return,
+        };
+
+        let settings = self.settings;
+        let (wheel_hi_res, wheel) = self.vertical.flush(&settings);
+        let (hwheel_hi_res, hwheel) = self.horizontal.flush(&settings);
+        for (axis, value) in [
+            (REL_WHEEL_HI_RES, wheel_hi_res),
+            (REL_WHEEL, wheel),
+            (REL_HWHEEL_HI_RES, hwheel_hi_res),
+            (REL_HWHEEL, hwheel),
+        ] {
+            if value != 0 {
+                self.next.send_all(InputEvent::Raw(EvdevInputEvent {
+                    event_code: EventCode::EV_REL(axis),
+                    value,
+                    time,
+                }));
+            }
+        }
+    }
+}
+
+async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
+    let mut state = _state.lock().await;
+
+    let ev = match &raw_ev {
+        InputEvent::Raw(ev) => ev,
+    };
+
+    match ev.event_code {
+        EventCode::EV_REL(axis @ (REL_WHEEL | REL_WHEEL_HI_RES | REL_HWHEEL | REL_HWHEEL_HI_RES)) => {
+            state.push(axis, ev.value, ev.time)
+        }
+        // a sync or any other event ends the frame
+        _ => {
+            state.flush();
+            state.vertical.hi_res = false;
+            state.horizontal.hi_res = false;
+            state.next.send_all(raw_ev);
+        }
+    }
+}
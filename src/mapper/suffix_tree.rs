@@ -1,5 +1,3 @@
-use nom::Slice;
-
 use crate::*;
 
 pub struct SuffixTree<Value> {
@@ -18,11 +16,32 @@ impl<Value> SuffixTree<Value> {
     }
 
     pub fn insert(&mut self, key: String, value: Value) {
-        self.root.entry(key.chars().last().unwrap()).or_default().insert(&key[0..key.len() - 1], value);
+        let ch = key.chars().last().unwrap();
+        self.root.entry(ch).or_default().insert(without_last(&key, ch), value);
     }
 
     pub fn get(&self, key: &String) -> Option<&Value> {
-        self.root.get(&key.chars().last().unwrap()).and_then(|x| x.get(&key[0..key.len() - 1]))
+        let ch = key.chars().last().unwrap();
+        self.root.get(&ch).and_then(|x| x.get(without_last(key, ch)))
+    }
+
+    pub fn remove(&mut self, key: &String) -> Option<Value> {
+        let ch = key.chars().last()?;
+        let node = self.root.get_mut(&ch)?;
+        let value = node.remove(without_last(key, ch));
+        if node.value.is_none() && node.children.is_empty() {
+            self.root.remove(&ch);
+        }
+        value
+    }
+
+    /// All stored keys with their values.
+    pub fn entries(&self) -> Vec<(String, &Value)> {
+        let mut acc = vec![];
+        for (ch, node) in self.root.iter() {
+            node.collect_entries(&mut vec![*ch], &mut acc);
+        }
+        acc
     }
 }
 
@@ -50,7 +69,7 @@ impl<Value> SuffixTreeNode<Value> {
 
     pub fn insert(&mut self, key: &str, value: Value) {
         if let Some(ch) = key.chars().last() {
-            self.children.entry(ch).or_default().insert(key.slice(0..key.len() - 1), value);
+            self.children.entry(ch).or_default().insert(without_last(key, ch), value);
         } else {
             self.value = Some(value);
         }
@@ -58,11 +77,36 @@ impl<Value> SuffixTreeNode<Value> {
 
     pub fn get(&self, key: &str) -> Option<&Value> {
         if let Some(ch) = key.chars().last() {
-            self.children.get(&ch).and_then(|x| x.get(key.slice(0..key.len() - 1)))
+            self.children.get(&ch).and_then(|x| x.get(without_last(key, ch)))
         } else {
             self.value.as_ref()
         }
     }
+
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        if let Some(ch) = key.chars().last() {
+            let child = self.children.get_mut(&ch)?;
+            let value = child.remove(without_last(key, ch));
+            if child.value.is_none() && child.children.is_empty() {
+                self.children.remove(&ch);
+            }
+            value
+        } else {
+            self.value.take()
+        }
+    }
+
+    // the path is stored back to front, the key is the reversed path
+    fn collect_entries<'a>(&'a self, path: &mut Vec<char>, acc: &mut Vec<(String, &'a Value)>) {
+        if let Some(value) = self.value.as_ref() {
+            acc.push((path.iter().rev().collect(), value));
+        }
+        for (ch, child) in self.children.iter() {
+            path.push(*ch);
+            child.collect_entries(path, acc);
+            path.pop();
+        }
+    }
 }
 
 impl<Value: Clone> Clone for SuffixTreeNode<Value> {
@@ -70,3 +114,8 @@ impl<Value: Clone> Clone for SuffixTreeNode<Value> {
         Self { value: self.value.clone(), children: self.children.clone() }
     }
 }
+
+// the key without its last character, which can take several bytes
+fn without_last(key: &str, ch: char) -> &str {
+    &key[..key.len() - ch.len_utf8()]
+}
@@ -0,0 +1,336 @@
+use super::abs_rel_mapper::{active_area, axis_range, AxisArea};
+use super::axis_mapper::Curve;
+use super::*;
+use crate::python::*;
+use crate::*;
+use evdev_rs::enums::EV_KEY;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex;
+
+use ApplicationError::TooManyEvents;
+
+struct State {
+    prev: HashMap<Uuid, Arc<dyn LinkSrc>>,
+    next: HashMap<Uuid, Arc<dyn LinkDst>>,
+    // the active area and the full range of each axis
+    area: Option<[(AxisArea, AxisArea); 2]>,
+    pressure: Option<(AxisArea, Curve)>,
+    tilt: bool,
+    // button mappings for a tool, or for every tool if none is given
+    buttons: HashMap<(Option<EV_KEY>, Key), Key>,
+    // the tool currently in proximity
+    tool: Option<EV_KEY>,
+    // the target of every held button, released even if the tool changed in the meantime
+    held: HashMap<Key, Key>,
+}
+
+const TOOLS: [(&str, EV_KEY); 7] = [
+    ("pen", BTN_TOOL_PEN),
+    ("rubber", BTN_TOOL_RUBBER),
+    ("brush", BTN_TOOL_BRUSH),
+    ("pencil", BTN_TOOL_PENCIL),
+    ("airbrush", BTN_TOOL_AIRBRUSH),
+    ("mouse", BTN_TOOL_MOUSE),
+    ("lens", BTN_TOOL_LENS),
+];
+
+fn parse_tool(options: &HashMap<&str, &PyAny>) -> PyResult<Option<EV_KEY>> {
+    let tool = match options.get("tool") {
+        Some(tool) => {
+            tool.extract::<String>().map_err(|_| PyRuntimeError::new_err("'tool' must be of type 'string'"))?
+        }
+        None => return Ok(None),
+    };
+    TOOLS.iter().find(|(name, _)| *name == tool).map(|(_, tool)| Some(*tool)).ok_or_else(|| {
+        PyRuntimeError::new_err(format!(
+            "'tool' must be one of: {}",
+            TOOLS.iter().map(|(name, _)| format!("'{}'", name)).collect::<Vec<_>>().join(", ")
+        ))
+    })
+}
+
+fn tool_name(tool: &EV_KEY) -> &'static str {
+    TOOLS.iter().find(|(_, code)| code == tool).unwrap().0
+}
+
+fn parse_button(raw: &str, side: &str) -> PyResult<Key> {
+    let key = parse_key(raw, None).map_err(|err| {
+        PyRuntimeError::new_err(format!(
+            "mapping error on the '{}' side:\n{}",
+            side,
+            ApplicationError::KeyParse(err.to_string()),
+        ))
+    })?;
+    match key.event_code {
+        EventCode::EV_KEY(_) => Ok(key),
+        _ => Err(PyRuntimeError::new_err(format!("mapping error on the '{}' side:\nexpected a button or key", side))),
+    }
+}
+
+#[pyclass]
+pub struct TabletMapper {
+    pub id: Uuid,
+    pub link: Arc<TabletMapperLink>,
+    ev_tx: tokio::sync::mpsc::Sender<InputEvent>,
+    state: Arc<Mutex<State>>,
+}
+
+#[pymethods]
+impl TabletMapper {
+    #[new]
+    #[pyo3(signature = (**kwargs))]
+    pub fn new(kwargs: Option<&PyDict>) -> PyResult<Self> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        // the ranges are only needed for the options that use them
+        let area = if options.contains_key("area") || options.contains_key("aspect") {
+            let x = axis_range(&options, "x_range", ABS_X)?;
+            let y = axis_range(&options, "y_range", ABS_Y)?;
+            let (x_area, y_area) = active_area(&options, x, y)?;
+            Some([(x_area, x), (y_area, y)])
+        } else {
+            None
+        };
+        let pressure = match options.get("pressure_curve") {
+            Some(curve) => {
+                let curve = Curve::from_py(curve, "pressure_curve")?;
+                Some((axis_range(&options, "pressure_range", ABS_PRESSURE)?, curve))
+            }
+            None => None,
+        };
+        let tilt = match options.get("tilt") {
+            Some(tilt) => tilt.extract().map_err(|_| PyRuntimeError::new_err("'tilt' must be of type 'bool'"))?,
+            None => true,
+        };
+
+        let id = Uuid::new_v4();
+        let (ev_tx, mut ev_rx) = tokio::sync::mpsc::channel(64);
+        let state = Arc::new(Mutex::new(State {
+            prev: Default::default(),
+            next: Default::default(),
+            area,
+            pressure,
+            tilt,
+            buttons: Default::default(),
+            tool: None,
+            held: Default::default(),
+        }));
+        let link = Arc::new(TabletMapperLink { id, ev_tx: ev_tx.clone(), state: state.clone() });
+
+        {
+            let state = state.clone();
+            get_runtime().spawn(async move {
+                loop {
+                    let ev = ev_rx.recv().await;
+                    match ev {
+                        Some(ev) => handle(state.clone(), ev).await,
+                        None => return,
+                    }
+                }
+            });
+        }
+
+        Ok(Self { id, link, ev_tx, state })
+    }
+
+    #[pyo3(signature = (from, to, **kwargs))]
+    pub fn map_button(&mut self, from: String, to: String, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let tool = parse_tool(&options)?;
+        let from = parse_button(&from, "from")?;
+        let to = parse_button(&to, "to")?;
+        self.state.blocking_lock().buttons.insert((tool, from), to);
+        Ok(())
+    }
+
+    #[pyo3(signature = (from, **kwargs))]
+    pub fn unmap_button(&mut self, from: String, kwargs: Option<&PyDict>) -> PyResult<bool> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let tool = parse_tool(&options)?;
+        let from = parse_button(&from, "from")?;
+        Ok(self.state.blocking_lock().buttons.remove(&(tool, from)).is_some())
+    }
+
+    pub fn button_mappings(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let state = self.state.blocking_lock();
+        let mut acc: Vec<((String, String), &PyDict)> = vec![];
+        for ((tool, from), to) in state.buttons.iter() {
+            let tool = tool.as_ref().map(tool_name);
+            let info = PyDict::new(py);
+            info.set_item("from", key_name(from))?;
+            info.set_item("to", key_name(to))?;
+            info.set_item("tool", tool)?;
+            acc.push(((key_name(from), tool.unwrap_or_default().to_string()), info));
+        }
+
+        acc.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(acc.into_iter().map(|(_, info)| info.to_object(py)).collect())
+    }
+
+    pub fn link_to(&mut self, target: &PyAny) -> PyResult<()> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.link_from(self.link.clone());
+        self.link.link_to(target);
+        Ok(())
+    }
+
+    pub fn unlink_to(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.unlink_from(&self.id);
+        self.link.unlink_to(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_to_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.next.values_mut() {
+            l.unlink_from(&self.id);
+        }
+        state.next.clear();
+    }
+
+    pub fn unlink_from(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_src(target).ok_or_else(|| PyRuntimeError::new_err("expected a source node"))?;
+        target.unlink_to(&self.id);
+        self.link.unlink_from(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_from_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.prev.values_mut() {
+            l.unlink_to(&self.id);
+        }
+        state.prev.clear();
+    }
+
+    pub fn unlink_all(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+
+    pub fn send(&mut self, val: String) -> PyResult<()> {
+        let actions = parse_key_sequence_without_toggles(val.as_str(), None)
+            .map_err(|err| ApplicationError::KeySequenceParse(err.to_string()).into_py())?
+            .to_key_actions();
+        for action in actions {
+            self.ev_tx.try_send(InputEvent::Raw(action.to_input_ev())).expect(&TooManyEvents.to_string());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TabletMapper {
+    fn drop(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+}
+
+#[derive(Clone)]
+pub struct TabletMapperLink {
+    id: Uuid,
+    ev_tx: Sender<InputEvent>,
+    state: Arc<Mutex<State>>,
+}
+
+impl LinkSrc for TabletMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_to(&self, node: Arc<dyn LinkDst>) -> Result<()> {
+        self.state.blocking_lock().next.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_to(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().next.remove(id).is_some())
+    }
+}
+
+impl LinkDst for TabletMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_from(&self, node: Arc<dyn LinkSrc>) -> Result<()> {
+        self.state.blocking_lock().prev.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_from(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().prev.remove(id).is_some())
+    }
+    fn send(&self, ev: InputEvent) -> Result<()> {
+        self.ev_tx.try_send(ev).map_err(|err| ApplicationError::TooManyEvents.into_py())?;
+        Ok(())
+    }
+}
+
+impl State {
+    // the button to send for a tablet button, mappings for the current tool come first
+    fn map_button(&mut self, button: Key, value: i32) -> Key {
+        if value == TYPE_DOWN {
+            let to = self
+                .tool
+                .and_then(|tool| self.buttons.get(&(Some(tool), button)))
+                .or_else(|| self.buttons.get(&(None, button)))
+                .copied()
+                .unwrap_or(button);
+            self.held.insert(button, to);
+            return to;
+        }
+        match value {
+            TYPE_UP => self.held.remove(&button),
+            _ => self.held.get(&button).copied(),
+        }
+        .unwrap_or(button)
+    }
+}
+
+async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
+    let mut state = _state.lock().await;
+
+    let ev = match &raw_ev {
+        InputEvent::Raw(ev) => ev,
+    };
+
+    let value = match ev.event_code {
+        // the active area is stretched to the full range
+        EventCode::EV_ABS(axis @ (ABS_X | ABS_Y)) if state.area.is_some() => {
+            let (area, range) = state.area.unwrap()[if axis == ABS_X { 0 } else { 1 }];
+            area.rescale(ev.value as f64, &range).round() as i32
+        }
+        EventCode::EV_ABS(ABS_PRESSURE) if state.pressure.is_some() => {
+            let (range, curve) = state.pressure.as_ref().unwrap();
+            let pressure = (range.clamp(ev.value as f64) - range.min) / range.len();
+            (range.min + curve.apply(pressure) * range.len()).round() as i32
+        }
+        EventCode::EV_ABS(ABS_TILT_X | ABS_TILT_Y) if !state.tilt => return,
+        EventCode::EV_KEY(tool) if TOOLS.iter().any(|(_, code)| *code == tool) => {
+            if ev.value == TYPE_DOWN {
+                state.tool = Some(tool);
+            } else if ev.value == TYPE_UP && state.tool == Some(tool) {
+                state.tool = None;
+            }
+            ev.value
+        }
+        EventCode::EV_KEY(_) => {
+            let to = state.map_button(Key { event_code: ev.event_code }, ev.value);
+            return state.next.send_all(InputEvent::Raw(EvdevInputEvent {
+                event_code: to.event_code,
+                value: ev.value,
+                time: ev.time,
+            }));
+        }
+        _ => ev.value,
+    };
+
+    state.next.send_all(InputEvent::Raw(EvdevInputEvent { event_code: ev.event_code, value, time: ev.time }));
+}
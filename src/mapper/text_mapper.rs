@@ -1,18 +1,26 @@
 use super::suffix_tree::SuffixTree;
+use super::text_template::{Captures, Template};
 use super::*;
+use crate::event_loop::PythonArgument;
 use crate::mapper::mapping_functions::*;
 use crate::mapper::RuntimeKeyAction;
 use crate::python::*;
+use crate::window::watch_active_window;
 use crate::xkb::XKBTransformer;
 use crate::xkb_transformer_registry::{TransformerParams, XKB_TRANSFORMER_REGISTRY};
 use crate::*;
 use nom::Slice;
+use regex::{Regex, RegexBuilder};
 use tokio::sync::mpsc::Sender;
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 
 use ApplicationError::TooManyEvents;
 
-type Mappings = SuffixTree<RuntimeAction>;
+type Mappings = SuffixTree<TextMapping>;
+
+// space, tab, enter and punctuation
+const DEFAULT_TERMINATORS: &str = " \t\r.,;:!?";
 
 #[derive(Default)]
 struct State {
@@ -22,6 +30,127 @@ struct State {
     mappings: Mappings,
     modifiers: Arc<KeyModifierState>,
     window: Vec<char>,
+    // the longest trigger
+    window_size: usize,
+    case_sensitive: bool,
+    // triggers only fire for whole words, once a terminator is typed after them
+    word: bool,
+    terminators: Vec<char>,
+    // matched after the plain mappings, in the order they were added
+    regexes: Vec<RegexMapping>,
+    reset_on: ResetTriggers,
+    window_watch: Option<JoinHandle<()>>,
+}
+
+/// What clears the typed text, moving the cursor or focus elsewhere would cause false expansions otherwise.
+#[derive(Clone, Copy, Default)]
+struct ResetTriggers {
+    // arrow keys, home, end, page up and page down
+    navigation: bool,
+    // mouse and other BTN_* presses
+    click: bool,
+    // keys pressed while ctrl, alt or meta is held
+    shortcut: bool,
+    // active window changes
+    window: bool,
+}
+
+const RESET_TRIGGERS: [&str; 4] = ["navigation", "click", "shortcut", "window"];
+
+impl ResetTriggers {
+    fn from_py(value: &PyAny) -> PyResult<Self> {
+        let err = || {
+            PyRuntimeError::new_err(format!(
+                "'reset_on' must be a list containing any of: {}",
+                RESET_TRIGGERS.map(|name| format!("'{}'", name)).join(", ")
+            ))
+        };
+        let mut triggers = ResetTriggers::default();
+        for name in value.extract::<Vec<String>>().map_err(|_| err())? {
+            match name.as_str() {
+                "navigation" => triggers.navigation = true,
+                "click" => triggers.click = true,
+                "shortcut" => triggers.shortcut = true,
+                "window" => triggers.window = true,
+                _ => return Err(err()),
+            }
+        }
+        Ok(triggers)
+    }
+
+    // watching the active window needs a supported window manager, so it has to be asked for
+    fn defaults() -> Self {
+        ResetTriggers { navigation: true, click: true, shortcut: true, window: false }
+    }
+}
+
+#[derive(Clone)]
+struct TextMapping {
+    action: RuntimeAction,
+    // the 'to' side of sequences, rendered again for triggers typed in a different case
+    text: Option<String>,
+    // set if the 'to' side has placeholders, it is rendered every time the mapping fires
+    template: Option<Arc<Template>>,
+}
+
+#[derive(Clone)]
+struct RegexMapping {
+    pattern: String,
+    regex: Regex,
+    mapping: TextMapping,
+}
+
+/// A trigger at the end of the typed text.
+struct Hit {
+    mapping: TextMapping,
+    typed: String,
+    // the trigger as stored or the regex pattern
+    from: String,
+    // the terminator typed after the trigger
+    terminator: Option<char>,
+    captures: Captures,
+    regex: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TypedCase {
+    AsIs,
+    Capitalized,
+    Upper,
+}
+
+impl TypedCase {
+    // "Btw" capitalizes the output and "BTW" upper cases it, any other capitalisation keeps the output as it is
+    fn of(typed: &str) -> Self {
+        let letters: Vec<char> = typed.chars().filter(|ch| ch.is_alphabetic()).collect();
+        match letters.as_slice() {
+            [_, _, ..] if letters.iter().all(|ch| ch.is_uppercase()) => TypedCase::Upper,
+            [first, ..] if first.is_uppercase() => TypedCase::Capitalized,
+            _ => TypedCase::AsIs,
+        }
+    }
+
+    // changes the case of the text outside of {special keys}
+    fn apply(&self, text: &str) -> String {
+        let mut acc = String::new();
+        let mut in_key = false;
+        let mut capitalized = false;
+        for ch in text.chars() {
+            match ch {
+                '{' => in_key = true,
+                '}' => in_key = false,
+                ch if in_key || !ch.is_alphabetic() => {}
+                ch if *self == TypedCase::Upper || (*self == TypedCase::Capitalized && !capitalized) => {
+                    capitalized = true;
+                    acc.extend(ch.to_uppercase());
+                    continue;
+                }
+                _ => capitalized = true,
+            }
+            acc.push(ch);
+        }
+        acc
+    }
 }
 
 #[pyclass]
@@ -50,9 +179,47 @@ impl TextMapper {
             .get(&TransformerParams::new(kbd_model, kbd_layout, kbd_variant, kbd_options))
             .map_err(err_to_py)?;
 
+        let window_size = match options.get("window_size") {
+            Some(window_size) => match window_size.extract::<usize>() {
+                Ok(window_size) if window_size > 0 => window_size,
+                _ => return Err(PyRuntimeError::new_err("'window_size' must be a positive integer")),
+            },
+            None => 32,
+        };
+        let case_sensitive = match options.get("case_sensitive") {
+            Some(case_sensitive) => case_sensitive
+                .extract()
+                .map_err(|_| PyRuntimeError::new_err("'case_sensitive' must be of type 'bool'"))?,
+            None => true,
+        };
+        let word = match options.get("word") {
+            Some(word) => word.extract().map_err(|_| PyRuntimeError::new_err("'word' must be of type 'bool'"))?,
+            None => false,
+        };
+        let terminators: Vec<char> = match options.get("terminators") {
+            Some(terminators) => match terminators.extract::<String>() {
+                Ok(terminators) if !terminators.is_empty() => terminators.chars().collect(),
+                _ => return Err(PyRuntimeError::new_err("'terminators' must be a non-empty string")),
+            },
+            None => DEFAULT_TERMINATORS.chars().collect(),
+        };
+        let reset_on = match options.get("reset_on") {
+            Some(reset_on) => ResetTriggers::from_py(reset_on)?,
+            None => ResetTriggers::defaults(),
+        };
+        let window_rx = if reset_on.window { Some(watch_active_window().map_err(err_to_py)?) } else { None };
+
         let id = Uuid::new_v4();
         let (ev_tx, mut ev_rx) = tokio::sync::mpsc::channel(64);
-        let state = Arc::new(Mutex::new(State { transformer, ..Default::default() }));
+        let state = Arc::new(Mutex::new(State {
+            transformer,
+            window_size,
+            case_sensitive,
+            word,
+            terminators,
+            reset_on,
+            ..Default::default()
+        }));
         let link = Arc::new(TextMapperLink { id, ev_tx: ev_tx.clone(), state: state.clone() });
 
         {
@@ -68,13 +235,37 @@ impl TextMapper {
             });
         }
 
+        if let Some(mut window_rx) = window_rx {
+            let _state = state.clone();
+            state.blocking_lock().window_watch = Some(get_runtime().spawn(async move {
+                while window_rx.changed().await.is_ok() {
+                    _state.lock().await.window.clear();
+                }
+            }));
+        }
+
         Ok(Self { id, link, ev_tx, state })
     }
 
-    pub fn map(&mut self, py: Python, from: String, to: PyObject) -> PyResult<()> {
+    #[pyo3(signature = (from, to, **kwargs))]
+    pub fn map(&mut self, py: Python, from: String, to: PyObject, kwargs: Option<&PyDict>) -> PyResult<()> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+        let template = match options.get("template") {
+            Some(template) => {
+                template.extract().map_err(|_| PyRuntimeError::new_err("'template' must be of type 'bool'"))?
+            }
+            None => false,
+        };
+
         let mut state = self.state.blocking_lock();
-        if from.len() > 32 {
-            return Err(PyRuntimeError::new_err("'from' side cannot be longer than 32 character"));
+        if from.chars().count() > state.window_size {
+            return Err(PyRuntimeError::new_err(format!(
+                "'from' side cannot be longer than {} characters",
+                state.window_size
+            )));
         }
 
         let from_seq: Vec<KeyClickActionWithMods> = parse_key_sequence(&from, Some(&state.transformer))
@@ -92,30 +283,83 @@ impl TextMapper {
             .collect::<Option<Vec<_>>>()
             .ok_or_else(|| PyRuntimeError::new_err("invalid key sequence"))?;
 
-        let to = if to.as_ref(py).is_callable() {
-            RuntimeAction::PythonCallback(Default::default(), Arc::new(to))
-        } else {
-            let to = to.extract::<String>(py).map_err(|err| {
-                PyRuntimeError::new_err(format!(
-                    "mapping error on the 'to' side:\n{}",
-                    ApplicationError::InvalidInputType { type_: "String".to_string() }
-                ))
-            })?;
-            let to = parse_key_sequence(&to, Some(&state.transformer)).map_err(|err| {
-                PyRuntimeError::new_err(format!(
-                    "mapping error on the 'to' side:\n{}",
-                    ApplicationError::KeySequenceParse(err.to_string()),
-                ))
-            })?;
+        let mapping = state.text_mapping(py, to, template, None)?;
 
-            let mut to: Vec<RuntimeKeyAction> =
-                to.to_key_actions().into_iter().map(|action| RuntimeKeyAction::KeyAction(action)).collect();
+        let from = state.trigger(from);
+        state.mappings.insert(from, mapping);
 
-            RuntimeAction::ActionSequence(to)
-        };
+        Ok(())
+    }
 
-        state.mappings.insert(from, to);
+    pub fn map_regex(&mut self, py: Python, pattern: String, to: PyObject) -> PyResult<()> {
+        let mut state = self.state.blocking_lock();
+        // the pattern has to match the whole end of the typed text
+        let regex = RegexBuilder::new(&format!("^(?:{})$", pattern))
+            .case_insensitive(!state.case_sensitive)
+            .build()
+            .map_err(|err| PyRuntimeError::new_err(format!("mapping error on the 'from' side:\n{}", err)))?;
+        let mapping = state.text_mapping(py, to, true, Some(&regex))?;
+
+        match state.regexes.iter_mut().find(|existing| existing.pattern == pattern) {
+            Some(existing) => *existing = RegexMapping { pattern, regex, mapping },
+            None => state.regexes.push(RegexMapping { pattern, regex, mapping }),
+        }
+        Ok(())
+    }
+
+    pub fn unmap(&mut self, from: String) -> bool {
+        let mut state = self.state.blocking_lock();
+        let from = state.trigger(from);
+        state.mappings.remove(&from).is_some()
+    }
+
+    pub fn unmap_regex(&mut self, pattern: String) -> bool {
+        let mut state = self.state.blocking_lock();
+        let len = state.regexes.len();
+        state.regexes.retain(|existing| existing.pattern != pattern);
+        state.regexes.len() != len
+    }
+
+    pub fn mappings(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let state = self.state.blocking_lock();
+        let mut entries = state.mappings.entries();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut acc = vec![];
+        for (from, mapping) in entries {
+            let info = mapping_info(py, from, &mapping.action)?;
+            info.set_item("regex", false)?;
+            acc.push(info.to_object(py));
+        }
+        for RegexMapping { pattern, mapping, .. } in state.regexes.iter() {
+            let info = mapping_info(py, pattern, &mapping.action)?;
+            info.set_item("regex", true)?;
+            acc.push(info.to_object(py));
+        }
+        Ok(acc)
+    }
+
+    // the mapping typing `text` would trigger, the longest matching suffix wins
+    pub fn resolve(&self, py: Python, text: String) -> PyResult<Option<PyObject>> {
+        let state = self.state.blocking_lock();
+        let chars: Vec<char> = text.chars().collect();
+        match state.lookup(&chars) {
+            Some(hit) => {
+                let info = mapping_info(py, hit.from, &hit.mapping.action)?;
+                info.set_item("regex", hit.regex)?;
+                Ok(Some(info.to_object(py)))
+            }
+            None => Ok(None),
+        }
+    }
 
+    pub fn load(&mut self, py: Python, path: String) -> PyResult<()> {
+        let entries = read_keymap(&path).map_err(err_to_py)?;
+        for (idx, entry) in entries.iter().enumerate() {
+            self.load_entry(py, entry).map_err(|err| {
+                PyRuntimeError::new_err(format!("keymap '{}', entry {}:\n{}", path, idx + 1, err.value(py)))
+            })?;
+        }
         Ok(())
     }
 
@@ -123,9 +367,10 @@ impl TextMapper {
         let mut state = self.state.blocking_lock();
         if let Some(existing) = existing {
             state.mappings = existing.mappings.clone();
+            state.regexes = existing.regexes.clone();
             return None;
         }
-        Some(TextMapperSnapshot { mappings: state.mappings.clone() })
+        Some(TextMapperSnapshot { mappings: state.mappings.clone(), regexes: state.regexes.clone() })
     }
 
     pub fn link_to(&mut self, target: &PyAny) -> PyResult<()> {
@@ -172,7 +417,7 @@ impl TextMapper {
 
     pub fn send(&mut self, val: String) -> PyResult<()> {
         let mut state = self.state.blocking_lock();
-        let actions = parse_key_sequence(val.as_str(), Some(&state.transformer))
+        let actions = parse_key_sequence_without_toggles(val.as_str(), Some(&state.transformer))
             .map_err(|err| ApplicationError::KeySequenceParse(err.to_string()).into_py())?
             .to_key_actions();
         for action in actions {
@@ -184,6 +429,9 @@ impl TextMapper {
 
 impl Drop for TextMapper {
     fn drop(&mut self) {
+        if let Some(window_watch) = self.state.blocking_lock().window_watch.take() {
+            window_watch.abort();
+        }
         self.unlink_from_all();
         self.unlink_to_all();
     }
@@ -226,9 +474,64 @@ impl LinkDst for TextMapperLink {
     }
 }
 
+impl TextMapper {
+    fn load_entry(&mut self, py: Python, entry: &serde_json::Value) -> PyResult<()> {
+        let entry = KeymapEntry::from_json(py, entry)?;
+        if entry.kind == "record" || entry.kind == "replay" {
+            return Err(PyRuntimeError::new_err(format!("'{}' mappings are only supported by a Mapper", entry.kind)));
+        }
+        if entry.kind != "sequence" {
+            return Err(PyRuntimeError::new_err(format!("unsupported kind '{}', expected 'sequence'", entry.kind)));
+        }
+        let from =
+            entry.from.extract::<String>().map_err(|_| PyRuntimeError::new_err("'from' must be of type 'string'"))?;
+        let regex = match entry.options.get_item("regex")? {
+            Some(regex) => {
+                regex.extract::<bool>().map_err(|_| PyRuntimeError::new_err("'regex' must be of type 'bool'"))?
+            }
+            None => false,
+        };
+        if regex {
+            self.map_regex(py, from, entry.to()?.to_object(py))
+        } else {
+            self.map(py, from, entry.to()?.to_object(py), Some(entry.options))
+        }
+    }
+}
+
 #[pyclass]
 pub struct TextMapperSnapshot {
     mappings: Mappings,
+    regexes: Vec<RegexMapping>,
+}
+
+#[pymethods]
+impl TextMapperSnapshot {
+    pub fn to_file(&self, py: Python, path: String) -> PyResult<()> {
+        let mut entries = vec![];
+        for (from, mapping) in self.mappings.entries() {
+            entries.extend(keymap_entry(py, &from, mapping, false)?);
+        }
+        for RegexMapping { pattern, mapping, .. } in self.regexes.iter() {
+            entries.extend(keymap_entry(py, pattern, mapping, true)?);
+        }
+        write_keymap(&path, entries).map_err(err_to_py)
+    }
+}
+
+fn keymap_entry(py: Python, from: &str, mapping: &TextMapping, regex: bool) -> PyResult<Option<serde_json::Value>> {
+    let info = mapping_info(py, from, &mapping.action)?;
+    // the text as mapped keeps the placeholders, a literal `$` of a regex mapping is escaped again
+    if let Some(text) = &mapping.text {
+        let text = if regex && mapping.template.is_none() { text.replace('$', "$$") } else { text.clone() };
+        info.set_item("to", text)?;
+    }
+    if regex {
+        info.set_item("regex", true)?;
+    } else if mapping.template.is_some() {
+        info.set_item("template", true)?;
+    }
+    to_keymap_entry(info)
 }
 
 fn _map(from: &KeyClickActionWithMods, to: Vec<ParsedKeyAction>) -> Vec<RuntimeKeyAction> {
@@ -238,6 +541,154 @@ fn _map(from: &KeyClickActionWithMods, to: Vec<ParsedKeyAction>) -> Vec<RuntimeK
     seq
 }
 
+impl State {
+    // whether the event moves the cursor elsewhere, the typed text doesn't lead up to it anymore
+    fn resets_window(&self, ev: &EvdevInputEvent) -> bool {
+        let key = Key { event_code: ev.event_code };
+        let navigation = matches!(
+            ev.event_code,
+            EventCode::EV_KEY(
+                KEY_UP | KEY_DOWN | KEY_LEFT | KEY_RIGHT | KEY_HOME | KEY_END | KEY_PAGEUP | KEY_PAGEDOWN
+            )
+        );
+        // the release is covered as well, so the shortcut key isn't added to the window
+        let shortcut =
+            !key.is_modifier() && (self.modifiers.is_ctrl() || self.modifiers.is_alt() || self.modifiers.is_meta());
+
+        (self.reset_on.navigation && navigation && ev.value == TYPE_DOWN)
+            || (self.reset_on.click && key.is_button() && ev.value == TYPE_DOWN)
+            || (self.reset_on.shortcut && shortcut)
+    }
+
+    // triggers are stored and looked up in lower case unless the mapper is case sensitive
+    fn trigger(&self, text: String) -> String {
+        if self.case_sensitive {
+            text
+        } else {
+            text.to_lowercase()
+        }
+    }
+
+    // the longest trigger at the end of the window
+    fn find_hit(&self) -> Option<Hit> {
+        if !self.word {
+            return self.lookup(&self.window);
+        }
+        match self.window.split_last() {
+            Some((last, rest)) if self.terminators.contains(last) => {
+                self.lookup(rest).map(|hit| Hit { terminator: Some(*last), ..hit })
+            }
+            _ => None,
+        }
+    }
+
+    // the longest trigger the text ends with, plain mappings win over regex mappings of the same length
+    fn lookup(&self, chars: &[char]) -> Option<Hit> {
+        let mut hit = None;
+        for i in (0..chars.len()).rev() {
+            if self.word && i > 0 && !self.terminators.contains(&chars[i - 1]) {
+                continue;
+            }
+            let typed: String = chars[i..].iter().collect();
+            let from = self.trigger(typed.clone());
+            if let Some(mapping) = self.mappings.get(&from) {
+                let captures = Captures::literal(&typed);
+                hit = Some(Hit { from, mapping: mapping.clone(), typed, terminator: None, captures, regex: false });
+                continue;
+            }
+            for RegexMapping { pattern, regex, mapping } in self.regexes.iter() {
+                if let Some(captures) = regex.captures(&typed) {
+                    let captures = Captures::from_regex(regex, &captures);
+                    hit = Some(Hit {
+                        from: pattern.clone(),
+                        mapping: mapping.clone(),
+                        typed,
+                        terminator: None,
+                        captures,
+                        regex: true,
+                    });
+                    break;
+                }
+            }
+        }
+        hit
+    }
+
+    // parses the 'to' side of a mapping, placeholders are only filled in for templates, captures refer to the groups
+    // of the regex if given
+    fn text_mapping(&self, py: Python, to: PyObject, template: bool, regex: Option<&Regex>) -> PyResult<TextMapping> {
+        if to.as_ref(py).is_callable() {
+            let action = RuntimeAction::PythonCallback(Default::default(), Arc::new(to));
+            return Ok(TextMapping { action, text: None, template: None });
+        }
+
+        let text = to.extract::<String>(py).map_err(|_| {
+            PyRuntimeError::new_err(format!(
+                "mapping error on the 'to' side:\n{}",
+                ApplicationError::InvalidInputType { type_: "String".to_string() }
+            ))
+        })?;
+        let (text, template) = if template {
+            let template = Template::parse(&text, regex)
+                .map_err(|err| PyRuntimeError::new_err(format!("mapping error on the 'to' side:\n{}", err)))?;
+            // text with placeholders is parsed as is to validate it, the placeholders are filled in when firing
+            match template.literal() {
+                Some(literal) => (literal, None),
+                None => (text, Some(Arc::new(template))),
+            }
+        } else {
+            (text, None)
+        };
+
+        let to = parse_key_sequence_without_toggles(&text, Some(&self.transformer)).map_err(|err| {
+            PyRuntimeError::new_err(format!(
+                "mapping error on the 'to' side:\n{}",
+                ApplicationError::KeySequenceParse(err.to_string()),
+            ))
+        })?;
+        let to: Vec<RuntimeKeyAction> =
+            to.to_key_actions().into_iter().map(|action| RuntimeKeyAction::KeyAction(action)).collect();
+
+        Ok(TextMapping { action: RuntimeAction::ActionSequence(to), text: Some(text), template })
+    }
+
+    // the output of a hit, following the capitalisation of the typed trigger if case insensitive
+    fn render(&self, hit: &Hit) -> RuntimeAction {
+        let text = match &hit.mapping.text {
+            Some(text) if !self.case_sensitive => text,
+            _ => return hit.mapping.action.clone(),
+        };
+        let case = TypedCase::of(&hit.typed);
+        if case == TypedCase::AsIs {
+            return hit.mapping.action.clone();
+        }
+        match parse_key_sequence_without_toggles(&case.apply(text), Some(&self.transformer)) {
+            Ok(to) => RuntimeAction::ActionSequence(
+                to.to_key_actions().into_iter().map(|action| RuntimeKeyAction::KeyAction(action)).collect(),
+            ),
+            Err(_) => hit.mapping.action.clone(),
+        }
+    }
+}
+
+// fills in the placeholders of a template off the async runtime, reading the clipboard blocks
+async fn render_template(
+    template: Arc<Template>,
+    captures: Captures,
+    terminator: Option<char>,
+    transformer: Arc<XKBTransformer>,
+) -> RuntimeAction {
+    let rendered = tokio::task::spawn_blocking(move || template.render(&captures, terminator, &transformer)).await;
+    match rendered {
+        Ok(Ok(seq)) => RuntimeAction::ActionSequence(seq),
+        Ok(Err(err)) => {
+            eprintln!("text mapping error:\n{}", err);
+            RuntimeAction::NOP
+        }
+        Err(_) => RuntimeAction::NOP,
+    }
+}
+
 async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
     let mut _state = _state.lock().await;
     let mut state = &mut *_state;
@@ -248,6 +699,11 @@ async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
         InputEvent::Raw(ev) => ev,
     };
 
+    if matches!(ev.event_code, EventCode::EV_KEY(_)) && state.resets_window(&ev) {
+        state.window.clear();
+        return;
+    }
+
     match ev {
         EvdevInputEvent { event_code: EventCode::EV_KEY(KEY_BACKSPACE), value: 1, .. } => {
             state.window.pop();
@@ -262,22 +718,18 @@ async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
 
                 if let Some(key) = key {
                     state.window.push(key.chars().next().unwrap());
-                    // TODO set window size dynamically
-                    if state.window.len() > 32 {
-                        state.window.remove(0);
-                    }
-
-                    let mut hit = None;
-
-                    for i in (0..state.window.len()).rev() {
-                        let search: String = state.window.iter().skip(i).collect();
-                        if let Some(x) = state.mappings.get(&search) {
-                            hit = Some((x, search.len()));
-                        }
+                    // leaves room for the word boundary and the terminator
+                    let window_size = state.window_size + 2;
+                    if state.window.len() > window_size {
+                        let excess = state.window.len() - window_size;
+                        state.window.drain(..excess);
                     }
 
-                    if let Some((to, from_len)) = hit {
+                    if let Some(hit) = state.find_hit() {
                         state.window.clear();
+                        // the terminator was already sent, it is erased and typed again after the output
+                        let from_len = hit.typed.chars().count() + hit.terminator.is_some() as usize;
+                        let terminator = hit.terminator.map(|_| Key { event_code: ev.event_code });
 
                         if !state.next.is_empty() {
                             for _ in 0..from_len {
@@ -286,6 +738,25 @@ async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
                             }
                         }
 
+                        // rendered without holding the state, reading the clipboard blocks
+                        if let Some(template) = hit.mapping.template.clone() {
+                            let transformer = state.transformer.clone();
+                            let next: Vec<Arc<dyn LinkDst>> = state.next.values().cloned().collect();
+                            drop(state);
+                            drop(_state);
+                            // templates write the terminator themselves, before moving the cursor
+                            let to = render_template(template, hit.captures, hit.terminator, transformer).await;
+                            if let RuntimeAction::ActionSequence(seq) = to {
+                                for action in seq {
+                                    if let RuntimeKeyAction::KeyAction(key_action) = action {
+                                        next.send_all(InputEvent::Raw(key_action.to_input_ev()));
+                                    }
+                                }
+                            }
+                            return;
+                        }
+
+                        let to = state.render(&hit);
                         match to {
                             RuntimeAction::ActionSequence(seq) => {
                                 for action in seq {
@@ -306,6 +777,8 @@ async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
                                                 }
                                             }
                                         }
+                                        // rejected when mapping, only the key mapper keeps latched keys and macros
+                                        RuntimeKeyAction::Toggle(_) | RuntimeKeyAction::Macro(_) => {}
                                     }
                                 }
                             }
@@ -327,14 +800,28 @@ async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
 
                                 let handler = handler.clone();
                                 let transformer = state.transformer.clone();
-                                let next = state.next.values().cloned().collect();
+                                let next: Vec<Arc<dyn LinkDst>> = state.next.values().cloned().collect();
                                 drop(state);
                                 drop(_state);
-                                run_python_handler(handler, None, ev, transformer, next).await;
+                                // regex callbacks receive the capture groups
+                                let args = hit.regex.then(|| {
+                                    hit.captures.groups[1..].iter().cloned().map(PythonArgument::String).collect()
+                                });
+                                run_python_handler(handler, args, ev, transformer, next.clone()).await;
+                                if let Some(terminator) = terminator {
+                                    next.send_all(InputEvent::Raw(terminator.to_input_ev(TYPE_DOWN)));
+                                    next.send_all(InputEvent::Raw(terminator.to_input_ev(TYPE_UP)));
+                                }
+                                return;
                             }
                             RuntimeAction::NOP => {}
                         }
 
+                        if let Some(terminator) = terminator {
+                            state.next.send_all(InputEvent::Raw(terminator.to_input_ev(TYPE_DOWN)));
+                            state.next.send_all(InputEvent::Raw(terminator.to_input_ev(TYPE_UP)));
+                        }
+
                         // return after handled match
                         return;
                     }
@@ -0,0 +1,226 @@
+use regex::Regex;
+use std::ffi::CString;
+
+use crate::mapper::RuntimeKeyAction;
+use crate::platform::read_clipboard;
+use crate::xkb::XKBTransformer;
+use crate::*;
+
+/// The 'to' side of a text mapping with `$...$` placeholders, filled in whenever the mapping fires.
+#[derive(Debug)]
+pub(super) struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Debug)]
+enum TemplatePart {
+    Text(String),
+    // `$|$`, the cursor is moved back here after writing the output
+    Cursor,
+    // `$clipboard$`
+    Clipboard,
+    // `$date$`, `$time$` or a custom strftime format, i.e. `$date:%d.%m.%Y$`
+    Time(String),
+    // `$1$` or `$name$`
+    Capture(CaptureRef),
+}
+
+#[derive(Debug)]
+enum CaptureRef {
+    Index(usize),
+    Name(String),
+}
+
+/// The text that fired a mapping, regex mappings also provide their capture groups.
+#[derive(Clone, Debug, Default)]
+pub(super) struct Captures {
+    // the whole match first, unmatched groups are empty
+    pub groups: Vec<String>,
+    pub named: HashMap<String, String>,
+}
+
+impl Captures {
+    pub fn literal(typed: &str) -> Self {
+        Captures { groups: vec![typed.to_string()], named: HashMap::new() }
+    }
+
+    pub fn from_regex(regex: &Regex, captures: &regex::Captures) -> Self {
+        let group = |m: Option<regex::Match>| m.map(|m| m.as_str().to_string()).unwrap_or_default();
+        Captures {
+            groups: captures.iter().map(group).collect(),
+            named: regex.capture_names().flatten().map(|name| (name.to_string(), group(captures.name(name)))).collect(),
+        }
+    }
+
+    fn get(&self, capture: &CaptureRef) -> &str {
+        match capture {
+            CaptureRef::Index(idx) => self.groups.get(*idx),
+            CaptureRef::Name(name) => self.named.get(name),
+        }
+        .map(String::as_str)
+        .unwrap_or_default()
+    }
+}
+
+impl Template {
+    /// Parses the placeholders of the text, a `$` that doesn't start a placeholder is written as is.
+    pub fn parse(text: &str, regex: Option<&Regex>) -> Result<Self> {
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut rest = text;
+
+        while let Some(start) = rest.find('$') {
+            literal.push_str(&rest[..start]);
+            rest = &rest[start + 1..];
+
+            let placeholder = match rest.find('$') {
+                Some(end) => &rest[..end],
+                None => {
+                    literal.push('$');
+                    continue;
+                }
+            };
+            let part = match placeholder {
+                // `$$` writes a single `$`
+                "" => {
+                    literal.push('$');
+                    rest = &rest[1..];
+                    continue;
+                }
+                "|" if parts.iter().any(|part| matches!(part, TemplatePart::Cursor)) => {
+                    return Err(anyhow!("the cursor can only be placed once"));
+                }
+                "|" => TemplatePart::Cursor,
+                "clipboard" => TemplatePart::Clipboard,
+                "date" => TemplatePart::Time("%Y-%m-%d".to_string()),
+                "time" => TemplatePart::Time("%H:%M".to_string()),
+                _ if placeholder.starts_with("date:") || placeholder.starts_with("time:") => {
+                    TemplatePart::Time(placeholder[5..].to_string())
+                }
+                _ if !placeholder.is_empty() && placeholder.chars().all(|ch| ch.is_ascii_digit()) => {
+                    let idx: usize = placeholder.parse()?;
+                    let groups = regex.map(|regex| regex.captures_len()).unwrap_or(1);
+                    if idx >= groups {
+                        return Err(anyhow!("capture group {} doesn't exist", idx));
+                    }
+                    TemplatePart::Capture(CaptureRef::Index(idx))
+                }
+                _ if regex.map_or(false, |regex| regex.capture_names().flatten().any(|name| name == placeholder)) => {
+                    TemplatePart::Capture(CaptureRef::Name(placeholder.to_string()))
+                }
+                // not a placeholder, the closing `$` might start the next one
+                _ => {
+                    literal.push('$');
+                    continue;
+                }
+            };
+
+            if !literal.is_empty() {
+                parts.push(TemplatePart::Text(std::mem::take(&mut literal)));
+            }
+            parts.push(part);
+            rest = &rest[placeholder.len() + 1..];
+        }
+        literal.push_str(rest);
+
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Text(literal));
+        }
+        Ok(Template { parts })
+    }
+
+    /// The text to write if there are no placeholders.
+    pub fn literal(&self) -> Option<String> {
+        match self.parts.as_slice() {
+            [] => Some(String::new()),
+            [TemplatePart::Text(text)] => Some(text.clone()),
+            _ => None,
+        }
+    }
+
+    /// Fills in the placeholders, reading the clipboard blocks. The terminator of a word trigger is written after the
+    /// text, before the cursor is moved.
+    pub fn render(
+        &self,
+        captures: &Captures,
+        terminator: Option<char>,
+        transformer: &XKBTransformer,
+    ) -> Result<Vec<RuntimeKeyAction>> {
+        let mut before = String::new();
+        let mut after = String::new();
+        let mut cursor = false;
+
+        for part in self.parts.iter() {
+            let value = match part {
+                TemplatePart::Cursor => {
+                    cursor = true;
+                    continue;
+                }
+                TemplatePart::Text(text) => text.clone(),
+                TemplatePart::Clipboard => escape(&read_clipboard()?),
+                TemplatePart::Time(format) => escape(&strftime(format)?),
+                TemplatePart::Capture(capture) => escape(captures.get(capture)),
+            };
+            if cursor {
+                after.push_str(&value);
+            } else {
+                before.push_str(&value);
+            }
+        }
+
+        if let Some(terminator) = terminator {
+            let terminator = match terminator {
+                '\t' => "{tab}".to_string(),
+                '\r' | '\n' => "{enter}".to_string(),
+                ch => escape(&ch.to_string()),
+            };
+            if cursor {
+                after.push_str(&terminator);
+            } else {
+                before.push_str(&terminator);
+            }
+        }
+
+        // moves the cursor back over everything written after it
+        if !after.is_empty() {
+            let back = parse_key_sequence_without_toggles(&after, Some(transformer))?.len();
+            before.push_str(&after);
+            before.push_str(&"{left}".repeat(back));
+        }
+
+        Ok(parse_key_sequence_without_toggles(&before, Some(transformer))?
+            .to_key_actions()
+            .into_iter()
+            .map(|action| RuntimeKeyAction::KeyAction(action))
+            .collect())
+    }
+}
+
+// dynamic values are written as text, braces shouldn't start special keys
+fn escape(text: &str) -> String {
+    let mut acc = String::new();
+    for ch in text.chars() {
+        if matches!(ch, '\\' | '{' | '}') {
+            acc.push('\\');
+        }
+        acc.push(ch);
+    }
+    acc
+}
+
+// formats the local time, python isn't used since the GIL must not be taken while the mapper's state is locked
+fn strftime(format: &str) -> Result<String> {
+    let format = CString::new(format).map_err(|_| anyhow!("the time format can't contain null characters"))?;
+    let mut buf = vec![0u8; 256];
+    let len = unsafe {
+        libc::tzset();
+        let now = libc::time(std::ptr::null_mut());
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&now, &mut tm).is_null() {
+            return Err(anyhow!("failed to read the local time"));
+        }
+        libc::strftime(buf.as_mut_ptr() as *mut libc::c_char, buf.len(), format.as_ptr(), &tm)
+    };
+    buf.truncate(len);
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
@@ -0,0 +1,78 @@
+use crate::python::get_runtime;
+use crate::*;
+use std::future::Future;
+use std::hash::Hash;
+use tokio::task::JoinHandle;
+
+/// Pending timer tasks, at most one per key.
+///
+/// Spawning a timer for a key that already has one aborts the previous timer. A timer that fires
+/// should call [`Timers::forget`] on its own key, aborting the running task would cancel it at the
+/// next await point.
+pub struct Timers<K> {
+    handles: HashMap<K, JoinHandle<()>>,
+}
+
+impl<K> Default for Timers<K> {
+    fn default() -> Self {
+        Self { handles: HashMap::new() }
+    }
+}
+
+impl<K: Eq + Hash> Timers<K> {
+    pub fn spawn(&mut self, key: K, task: impl Future<Output = ()> + Send + 'static) {
+        if let Some(handle) = self.handles.insert(key, get_runtime().spawn(task)) {
+            handle.abort();
+        }
+    }
+
+    /// Spawns a task that runs `task` once `delay` has passed.
+    pub fn spawn_after(&mut self, key: K, delay: Duration, task: impl Future<Output = ()> + Send + 'static) {
+        self.spawn(key, async move {
+            tokio::time::sleep(delay).await;
+            task.await;
+        });
+    }
+
+    pub fn cancel(&mut self, key: &K) -> bool {
+        match self.handles.remove(key) {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn cancel_matching(&mut self, predicate: impl Fn(&K) -> bool) {
+        self.handles.retain(|key, handle| {
+            if predicate(key) {
+                handle.abort();
+                return false;
+            }
+            true
+        });
+    }
+
+    pub fn cancel_all(&mut self) {
+        for (_, handle) in self.handles.drain() {
+            handle.abort();
+        }
+    }
+
+    pub fn forget(&mut self, key: &K) {
+        self.handles.remove(key);
+    }
+
+    pub fn is_pending(&self, key: &K) -> bool {
+        self.handles.get(key).map(|handle| !handle.is_finished()).unwrap_or(false)
+    }
+}
+
+impl<K> Drop for Timers<K> {
+    fn drop(&mut self) {
+        for (_, handle) in self.handles.drain() {
+            handle.abort();
+        }
+    }
+}
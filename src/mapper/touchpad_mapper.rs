@@ -0,0 +1,484 @@
+use super::*;
+use crate::mapper::mapping_functions::*;
+use crate::python::*;
+use crate::xkb::XKBTransformer;
+use crate::xkb_transformer_registry::{TransformerParams, XKB_TRANSFORMER_REGISTRY};
+use crate::*;
+use evdev_rs::enums::{EV_ABS, EV_SYN};
+use std::time::Instant;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Mutex;
+
+struct State {
+    transformer: Arc<XKBTransformer>,
+    prev: HashMap<Uuid, Arc<dyn LinkSrc>>,
+    next: HashMap<Uuid, Arc<dyn LinkDst>>,
+    swipe_distance: f64,
+    pinch_distance: f64,
+    tap_timeout: Duration,
+    passthrough: bool,
+    mappings: HashMap<TouchGesture, RuntimeAction>,
+    // the slot that ABS_MT_* events refer to
+    slot: i32,
+    contacts: HashMap<i32, Contact>,
+    // set while at least one finger touches the touchpad
+    session: Option<Session>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum TouchGesture {
+    Swipe(usize, SwipeDirection),
+    // true if the fingers move apart
+    Pinch(usize, bool),
+    Tap(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum SwipeDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+const MAX_FINGERS: usize = 5;
+
+impl TouchGesture {
+    // i.e. "3 finger swipe left", "2 finger pinch in" or "1 finger tap"
+    fn parse(raw: &str) -> PyResult<Self> {
+        let err = || {
+            PyRuntimeError::new_err(format!(
+                "mapping error on the 'from' side:\ninvalid gesture '{}', expected i.e. '3 finger swipe left', \
+                 '2 finger pinch in' or '2 finger tap'",
+                raw
+            ))
+        };
+
+        let raw_lower = raw.to_lowercase();
+        let parts: Vec<&str> = raw_lower.split_whitespace().collect();
+        let (fingers, kind, direction) = match parts.as_slice() {
+            [fingers, "finger" | "fingers", kind] => (fingers, *kind, None),
+            [fingers, "finger" | "fingers", kind, direction] => (fingers, *kind, Some(*direction)),
+            _ => return Err(err()),
+        };
+        let fingers = match fingers.parse::<usize>() {
+            Ok(fingers) if (1..=MAX_FINGERS).contains(&fingers) => fingers,
+            _ => {
+                return Err(PyRuntimeError::new_err(format!(
+                    "mapping error on the 'from' side:\nthe number of fingers must be between 1 and {}",
+                    MAX_FINGERS
+                )))
+            }
+        };
+
+        match (kind, direction) {
+            ("swipe", Some("up")) => Ok(TouchGesture::Swipe(fingers, SwipeDirection::Up)),
+            ("swipe", Some("down")) => Ok(TouchGesture::Swipe(fingers, SwipeDirection::Down)),
+            ("swipe", Some("left")) => Ok(TouchGesture::Swipe(fingers, SwipeDirection::Left)),
+            ("swipe", Some("right")) => Ok(TouchGesture::Swipe(fingers, SwipeDirection::Right)),
+            ("pinch", Some(_)) if fingers < 2 => {
+                Err(PyRuntimeError::new_err("mapping error on the 'from' side:\npinching needs at least 2 fingers"))
+            }
+            ("pinch", Some("in")) => Ok(TouchGesture::Pinch(fingers, false)),
+            ("pinch", Some("out")) => Ok(TouchGesture::Pinch(fingers, true)),
+            ("tap", None) => Ok(TouchGesture::Tap(fingers)),
+            _ => Err(err()),
+        }
+    }
+
+    fn format(&self) -> String {
+        match self {
+            TouchGesture::Swipe(fingers, direction) => {
+                let direction = match direction {
+                    SwipeDirection::Up => "up",
+                    SwipeDirection::Down => "down",
+                    SwipeDirection::Left => "left",
+                    SwipeDirection::Right => "right",
+                };
+                format!("{} finger swipe {}", fingers, direction)
+            }
+            TouchGesture::Pinch(fingers, out) => {
+                format!("{} finger pinch {}", fingers, if *out { "out" } else { "in" })
+            }
+            TouchGesture::Tap(fingers) => format!("{} finger tap", fingers),
+        }
+    }
+}
+
+/// A finger on the touchpad, the position is unknown until the device reports it.
+#[derive(Default)]
+struct Contact {
+    x: Option<i32>,
+    y: Option<i32>,
+}
+
+/// The center of the fingers and their average distance to it.
+#[derive(Clone, Copy)]
+struct Snapshot {
+    x: f64,
+    y: f64,
+    spread: f64,
+}
+
+/// The contacts from the first finger touching until all fingers are lifted.
+struct Session {
+    start: Instant,
+    fingers: usize,
+    // snapshots of the frames where all fingers touch
+    first: Option<Snapshot>,
+    last: Option<Snapshot>,
+}
+
+#[pyclass]
+pub struct TouchpadMapper {
+    pub id: Uuid,
+    pub link: Arc<TouchpadMapperLink>,
+    state: Arc<Mutex<State>>,
+}
+
+#[pymethods]
+impl TouchpadMapper {
+    #[new]
+    #[pyo3(signature = (**kwargs))]
+    pub fn new(kwargs: Option<&PyDict>) -> PyResult<Self> {
+        let options: HashMap<&str, &PyAny> = match kwargs {
+            Some(py_dict) => py_dict.extract().unwrap(),
+            None => HashMap::new(),
+        };
+
+        let kbd_model = options.get("model").and_then(|x| x.extract().ok());
+        let kbd_layout = options.get("layout").and_then(|x| x.extract().ok());
+        let kbd_variant = options.get("variant").and_then(|x| x.extract().ok());
+        let kbd_options = options.get("options").and_then(|x| x.extract().ok());
+        let transformer = XKB_TRANSFORMER_REGISTRY
+            .get(&TransformerParams::new(kbd_model, kbd_layout, kbd_variant, kbd_options))
+            .map_err(err_to_py)?;
+
+        let swipe_distance = match options.get("swipe_distance") {
+            Some(swipe_distance) => match swipe_distance.extract::<f64>() {
+                Ok(swipe_distance) if swipe_distance > 0.0 => swipe_distance,
+                _ => return Err(PyRuntimeError::new_err("'swipe_distance' must be a positive number")),
+            },
+            None => 300.0,
+        };
+        let pinch_distance = match options.get("pinch_distance") {
+            Some(pinch_distance) => match pinch_distance.extract::<f64>() {
+                Ok(pinch_distance) if pinch_distance > 0.0 => pinch_distance,
+                _ => return Err(PyRuntimeError::new_err("'pinch_distance' must be a positive number")),
+            },
+            None => 150.0,
+        };
+        let tap_timeout = match options.get("tap_timeout_ms") {
+            Some(tap_timeout_ms) => Duration::from_millis(
+                tap_timeout_ms
+                    .extract::<u64>()
+                    .map_err(|_| PyRuntimeError::new_err("'tap_timeout_ms' must be of type 'int'"))?,
+            ),
+            None => Duration::from_millis(200),
+        };
+        let passthrough = match options.get("passthrough") {
+            Some(passthrough) => {
+                passthrough.extract().map_err(|_| PyRuntimeError::new_err("'passthrough' must be of type 'bool'"))?
+            }
+            None => true,
+        };
+
+        let id = Uuid::new_v4();
+        let (ev_tx, mut ev_rx) = tokio::sync::mpsc::channel(64);
+        let state = Arc::new(Mutex::new(State {
+            transformer,
+            prev: Default::default(),
+            next: Default::default(),
+            swipe_distance,
+            pinch_distance,
+            tap_timeout,
+            passthrough,
+            mappings: Default::default(),
+            slot: 0,
+            contacts: Default::default(),
+            session: None,
+        }));
+        let link = Arc::new(TouchpadMapperLink { id, ev_tx, state: state.clone() });
+
+        {
+            let state = state.clone();
+            get_runtime().spawn(async move {
+                loop {
+                    let ev = ev_rx.recv().await;
+                    match ev {
+                        Some(ev) => handle(state.clone(), ev).await,
+                        None => return,
+                    }
+                }
+            });
+        }
+
+        Ok(Self { id, link, state })
+    }
+
+    pub fn map(&mut self, py: Python, gesture: String, to: PyObject) -> PyResult<()> {
+        let mut state = self.state.blocking_lock();
+        let gesture = TouchGesture::parse(&gesture)?;
+
+        let to = if to.as_ref(py).is_callable() {
+            RuntimeAction::PythonCallback(Default::default(), Arc::new(to))
+        } else {
+            let to = to.extract::<String>(py).map_err(|_| {
+                PyRuntimeError::new_err(format!(
+                    "mapping error on the 'to' side:\n{}",
+                    ApplicationError::InvalidInputType { type_: "String".to_string() }
+                ))
+            })?;
+            let to = parse_key_sequence_without_toggles(&to, Some(&state.transformer)).map_err(|err| {
+                PyRuntimeError::new_err(format!(
+                    "mapping error on the 'to' side:\n{}",
+                    ApplicationError::KeySequenceParse(err.to_string()),
+                ))
+            })?;
+            RuntimeAction::ActionSequence(to.to_key_actions().into_iter().map(RuntimeKeyAction::KeyAction).collect())
+        };
+
+        state.mappings.insert(gesture, to);
+        Ok(())
+    }
+
+    pub fn unmap(&mut self, gesture: String) -> PyResult<bool> {
+        let gesture = TouchGesture::parse(&gesture)?;
+        Ok(self.state.blocking_lock().mappings.remove(&gesture).is_some())
+    }
+
+    pub fn mappings(&self, py: Python) -> PyResult<Vec<PyObject>> {
+        let state = self.state.blocking_lock();
+        let mut acc: Vec<(String, &PyDict)> = vec![];
+        for (gesture, action) in state.mappings.iter() {
+            let from = gesture.format();
+            acc.push((from.clone(), mapping_info(py, from, action)?));
+        }
+
+        acc.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(acc.into_iter().map(|(_, info)| info.to_object(py)).collect())
+    }
+
+    pub fn link_to(&mut self, target: &PyAny) -> PyResult<()> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.link_from(self.link.clone());
+        self.link.link_to(target);
+        Ok(())
+    }
+
+    pub fn unlink_to(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_dst(target).ok_or_else(|| PyRuntimeError::new_err("expected a destination node"))?;
+        target.unlink_from(&self.id);
+        self.link.unlink_to(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_to_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.next.values_mut() {
+            l.unlink_from(&self.id);
+        }
+        state.next.clear();
+    }
+
+    pub fn unlink_from(&mut self, target: &PyAny) -> PyResult<bool> {
+        let target = node_to_link_src(target).ok_or_else(|| PyRuntimeError::new_err("expected a source node"))?;
+        target.unlink_to(&self.id);
+        self.link.unlink_from(target.id()).map_err(err_to_py)
+    }
+
+    pub fn unlink_from_all(&mut self) {
+        let mut state = self.state.blocking_lock();
+        for l in state.prev.values_mut() {
+            l.unlink_to(&self.id);
+        }
+        state.prev.clear();
+    }
+
+    pub fn unlink_all(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+
+    pub fn send(&mut self, val: String) -> PyResult<()> {
+        let state = self.state.blocking_lock();
+        let actions = parse_key_sequence_without_toggles(val.as_str(), Some(&state.transformer))
+            .map_err(|err| ApplicationError::KeySequenceParse(err.to_string()).into_py())?
+            .to_key_actions();
+        // sent to the next nodes right away, without passthrough the events from the touchpad are dropped
+        for action in actions {
+            state.next.send_all(InputEvent::Raw(action.to_input_ev()));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TouchpadMapper {
+    fn drop(&mut self) {
+        self.unlink_from_all();
+        self.unlink_to_all();
+    }
+}
+
+#[derive(Clone)]
+pub struct TouchpadMapperLink {
+    id: Uuid,
+    ev_tx: Sender<InputEvent>,
+    state: Arc<Mutex<State>>,
+}
+
+impl LinkSrc for TouchpadMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_to(&self, node: Arc<dyn LinkDst>) -> Result<()> {
+        self.state.blocking_lock().next.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_to(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().next.remove(id).is_some())
+    }
+}
+
+impl LinkDst for TouchpadMapperLink {
+    fn id(&self) -> &Uuid {
+        &self.id
+    }
+    fn link_from(&self, node: Arc<dyn LinkSrc>) -> Result<()> {
+        self.state.blocking_lock().prev.insert(*node.id(), node);
+        Ok(())
+    }
+    fn unlink_from(&self, id: &Uuid) -> Result<bool> {
+        Ok(self.state.blocking_lock().prev.remove(id).is_some())
+    }
+    fn send(&self, ev: InputEvent) -> Result<()> {
+        self.ev_tx.try_send(ev).map_err(|err| ApplicationError::TooManyEvents.into_py())?;
+        Ok(())
+    }
+}
+
+impl State {
+    fn update_contact(&mut self, axis: EV_ABS, value: i32) {
+        match axis {
+            ABS_MT_SLOT => self.slot = value,
+            // a negative tracking id lifts the finger, any other id is a new finger
+            ABS_MT_TRACKING_ID if value < 0 => {
+                self.contacts.remove(&self.slot);
+            }
+            ABS_MT_TRACKING_ID => {
+                self.contacts.insert(self.slot, Contact::default());
+            }
+            ABS_MT_POSITION_X => {
+                if let Some(contact) = self.contacts.get_mut(&self.slot) {
+                    contact.x = Some(value);
+                }
+            }
+            ABS_MT_POSITION_Y => {
+                if let Some(contact) = self.contacts.get_mut(&self.slot) {
+                    contact.y = Some(value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // called once the device reported all contacts of a frame, returns the gesture once all fingers are lifted
+    fn end_frame(&mut self) -> Option<TouchGesture> {
+        if self.contacts.is_empty() {
+            let session = self.session.take()?;
+            return self.recognize(session);
+        }
+
+        let session =
+            self.session.get_or_insert_with(|| Session { start: Instant::now(), fingers: 0, first: None, last: None });
+        // another finger joined, the gesture starts over
+        if self.contacts.len() > session.fingers {
+            session.fingers = self.contacts.len();
+            session.first = None;
+            session.last = None;
+        }
+        if self.contacts.len() < session.fingers {
+            return None;
+        }
+
+        let positions: Vec<(f64, f64)> =
+            self.contacts.values().filter_map(|contact| Some((contact.x? as f64, contact.y? as f64))).collect();
+        if positions.len() < session.fingers {
+            return None;
+        }
+        let count = positions.len() as f64;
+        let x = positions.iter().map(|(x, _)| x).sum::<f64>() / count;
+        let y = positions.iter().map(|(_, y)| y).sum::<f64>() / count;
+        let spread = positions.iter().map(|(px, py)| (px - x).hypot(py - y)).sum::<f64>() / count;
+
+        let snapshot = Snapshot { x, y, spread };
+        session.first.get_or_insert(snapshot);
+        session.last = Some(snapshot);
+        None
+    }
+
+    fn recognize(&self, session: Session) -> Option<TouchGesture> {
+        let (first, last) = (session.first?, session.last?);
+        let (x, y) = (last.x - first.x, last.y - first.y);
+        let distance = x.hypot(y);
+        let spread = last.spread - first.spread;
+
+        if session.fingers >= 2 && spread.abs() >= self.pinch_distance && spread.abs() > distance {
+            return Some(TouchGesture::Pinch(session.fingers, spread > 0.0));
+        }
+        if distance >= self.swipe_distance {
+            let direction = match (x.abs() > y.abs(), x > 0.0, y > 0.0) {
+                (true, true, _) => SwipeDirection::Right,
+                (true, false, _) => SwipeDirection::Left,
+                (false, _, true) => SwipeDirection::Down,
+                (false, _, false) => SwipeDirection::Up,
+            };
+            return Some(TouchGesture::Swipe(session.fingers, direction));
+        }
+        if session.start.elapsed() <= self.tap_timeout {
+            return Some(TouchGesture::Tap(session.fingers));
+        }
+        None
+    }
+}
+
+async fn handle(_state: Arc<Mutex<State>>, raw_ev: InputEvent) {
+    let mut state = _state.lock().await;
+
+    let ev = match &raw_ev {
+        InputEvent::Raw(ev) => ev.clone(),
+    };
+
+    let gesture = match ev.event_code {
+        EventCode::EV_ABS(axis) => {
+            state.update_contact(axis, ev.value);
+            None
+        }
+        EventCode::EV_SYN(EV_SYN::SYN_REPORT) => state.end_frame(),
+        _ => None,
+    };
+
+    if state.passthrough {
+        state.next.send_all(raw_ev);
+    }
+
+    let action = match gesture.and_then(|gesture| state.mappings.get(&gesture)) {
+        Some(action) => action.clone(),
+        None => return,
+    };
+    match action {
+        RuntimeAction::ActionSequence(seq) => {
+            for action in seq {
+                if let RuntimeKeyAction::KeyAction(action) = action {
+                    state.next.send_all(InputEvent::Raw(action.to_input_ev()));
+                }
+            }
+        }
+        RuntimeAction::PythonCallback(_, handler) => {
+            let transformer = state.transformer.clone();
+            let next = state.next.values().cloned().collect();
+            drop(state);
+            run_python_handler(handler, None, ev, transformer, next).await;
+        }
+        RuntimeAction::NOP => {}
+    }
+}
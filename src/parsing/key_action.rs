@@ -1,5 +1,6 @@
 use crate::parsing::action_state::*;
 use crate::xkb::XKBTransformer;
+use nom::combinator::not;
 
 use super::*;
 
@@ -8,6 +9,8 @@ pub enum ParsedKeyAction {
     KeyAction(KeyActionWithMods),
     KeyClickAction(KeyClickActionWithMods),
     Action(KeyAction),
+    // latches the key down, or releases it if it's latched - {toggle shift}
+    Toggle(Key),
 }
 
 pub trait ParsedKeyActionVecExt {
@@ -92,6 +95,8 @@ impl ParsedKeyActionVecExt for Vec<ParsedKeyAction> {
                 acc.push(action);
                 acc
             }
+            // toggles depend on runtime state, only mappers can apply them
+            ParsedKeyAction::Toggle(_) => acc,
         })
     }
 
@@ -137,6 +142,7 @@ pub fn single_key_action_utf_with_flags_utf<'a>(
                     ParsedKeyAction::KeyClickAction(action) => action.modifiers.apply_from(&flags),
                     // TODO figure out how to not accept flags on this
                     ParsedKeyAction::Action(_) => {}
+                    ParsedKeyAction::Toggle(_) => {}
                 }
 
                 Ok::<ParsedKeyAction, CustomError<&str>>(action)
@@ -145,6 +151,21 @@ pub fn single_key_action_utf_with_flags_utf<'a>(
     })
 }
 
+pub fn key_trigger_utf<'a>(
+    transformer: Option<&'a XKBTransformer>,
+) -> impl Fn(&'a str) -> ParseResult<&str, (ParsedKeyAction, bool)> {
+    move |input: &str| {
+        alt((
+            // wildcard trigger, matches regardless of modifiers - *a
+            map(
+                tuple((tag_custom("*"), not(multispace1), single_key_action_utf_with_flags_utf(transformer))),
+                |(_, _, action)| (action, true),
+            ),
+            map(single_key_action_utf_with_flags_utf(transformer), |action| (action, false)),
+        ))(input)
+    }
+}
+
 pub fn single_key_action_utf<'a>(
     transformer: Option<&'a XKBTransformer>,
 ) -> impl Fn(&'a str) -> ParseResult<&str, ParsedKeyAction> {
@@ -190,6 +211,25 @@ pub fn key_action(input: &str) -> ParseResult<&str, ParsedKeyAction> {
 
 pub fn key_action_utf<'a>(
     transformer: Option<&'a XKBTransformer>,
+) -> impl Fn(&'a str) -> ParseResult<&str, ParsedKeyAction> {
+    move |input: &str| {
+        alt((
+            // toggle action - {toggle shift}
+            map(
+                surrounded_group(
+                    "{",
+                    "}",
+                    tuple((tag_custom_no_case("toggle"), multispace1, key_utf(transformer))),
+                ),
+                |(_, _, (key, _))| ParsedKeyAction::Toggle(key),
+            ),
+            key_action_without_toggle_utf(transformer),
+        ))(input)
+    }
+}
+
+fn key_action_without_toggle_utf<'a>(
+    transformer: Option<&'a XKBTransformer>,
 ) -> impl Fn(&'a str) -> ParseResult<&str, ParsedKeyAction> {
     move |input: &str| {
         map_res(
@@ -269,6 +309,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn toggle_action() {
+        assert_eq!(key_action("{toggle shift}"), nom_ok(ParsedKeyAction::Toggle(KEY_LEFTSHIFT.into())));
+        assert_eq!(key_action("{TOGGLE btn_left}"), nom_ok(ParsedKeyAction::Toggle(BTN_LEFT.into())));
+        assert!(key_action("{toggle}").is_err());
+    }
+
     #[test]
     fn action_with_mods() {
         assert_eq!(
@@ -352,6 +399,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn wildcard_trigger() {
+        let t = XKBTransformer::new("pc105", "us", None, None).unwrap();
+
+        assert_eq!(
+            key_trigger_utf(Some(&t))("*a"),
+            nom_ok((ParsedKeyAction::KeyClickAction(KeyClickActionWithMods::new(KEY_A.into())), true))
+        );
+
+        assert_eq!(
+            key_trigger_utf(Some(&t))("*a down"),
+            nom_ok((
+                ParsedKeyAction::KeyAction(KeyActionWithMods::new(KEY_A.into(), TYPE_DOWN, KeyModifierFlags::new())),
+                true
+            ))
+        );
+
+        assert_eq!(
+            key_trigger_utf(Some(&t))("a"),
+            nom_ok((ParsedKeyAction::KeyClickAction(KeyClickActionWithMods::new(KEY_A.into())), false))
+        );
+
+        // a lone asterisk is still the asterisk key
+        assert_eq!(
+            key_trigger_utf(Some(&t))("*"),
+            nom_ok((
+                ParsedKeyAction::KeyClickAction(KeyClickActionWithMods::new_with_mods(
+                    KEY_8.into(),
+                    KeyModifierFlags::new().tap_mut(|x| x.shift())
+                )),
+                false
+            ))
+        );
+    }
+
     #[test]
     fn invalid_action_multiple_keys_in_special_group() {
         let t = XKBTransformer::new("pc105", "us", None, None).unwrap();
@@ -15,8 +15,10 @@ pub fn rel_tag(input: &str) -> ParseResult<&str, EV_REL> {
         map(tag_no_case("RX"), |_| REL_RX),
         map(tag_no_case("RY"), |_| REL_RY),
         map(tag_no_case("RZ"), |_| REL_RZ),
+        map(tag_no_case("HWHEEL_HI_RES"), |_| REL_HWHEEL_HI_RES),
         map(tag_no_case("HWHEEL"), |_| REL_HWHEEL),
         map(tag_no_case("DIAL"), |_| REL_DIAL),
+        map(tag_no_case("WHEEL_HI_RES"), |_| REL_WHEEL_HI_RES),
         map(tag_no_case("WHEEL"), |_| REL_WHEEL),
         map(tag_no_case("MISC"), |_| REL_MISC),
         map(tag_no_case("MAX"), |_| REL_MAX),
@@ -86,6 +88,8 @@ pub fn motion_action(input: &str) -> ParseResult<&str, KeyAction> {
                 "HWHEEL" => EventCode::EV_REL(REL_HWHEEL),
                 "DIAL" => EventCode::EV_REL(REL_DIAL),
                 "WHEEL" => EventCode::EV_REL(REL_WHEEL),
+                "WHEEL_HI_RES" => EventCode::EV_REL(REL_WHEEL_HI_RES),
+                "HWHEEL_HI_RES" => EventCode::EV_REL(REL_HWHEEL_HI_RES),
                 "MISC" => EventCode::EV_REL(REL_MISC),
                 "MAX" => EventCode::EV_REL(REL_MAX),
                 _ => return Err(make_generic_nom_err_new(input)),
@@ -158,6 +162,11 @@ mod tests {
             nom_ok(KeyAction { key: Key { event_code: EventCode::EV_REL(REL_Y) }, value: 99 })
         );
 
+        assert_eq!(
+            motion_action("relative wheel_hi_res -120"),
+            nom_ok(KeyAction { key: Key { event_code: EventCode::EV_REL(REL_WHEEL_HI_RES) }, value: -120 })
+        );
+
         assert_eq!(
             motion_action("absolute Z 99"),
             nom_ok(KeyAction { key: Key { event_code: EventCode::EV_ABS(ABS_Z) }, value: 99 })
@@ -28,6 +28,17 @@ pub fn parse_key_action_with_mods(raw: &str, transformer: Option<&XKBTransformer
     Ok(from)
 }
 
+/// Parses a mapping trigger, a leading `*` marks the trigger as modifier-agnostic (wildcard).
+pub fn parse_key_trigger(raw: &str, transformer: Option<&XKBTransformer>) -> Result<(ParsedKeyAction, bool)> {
+    let (rest, from) = key_trigger_utf(transformer)(raw).map_err(|err| format_err(err, raw, 0))?;
+
+    if !rest.is_empty() {
+        return Err(anyhow!("expected exactly 1 key action from input '{}'", raw));
+    }
+
+    Ok(from)
+}
+
 pub fn parse_key_sequence(raw: &str, transformer: Option<&XKBTransformer>) -> Result<Vec<ParsedKeyAction>> {
     let (rest, (res, last_err)) = key_sequence_utf(transformer)(raw).map_err(|err| format_err(err, raw, 0))?;
 
@@ -38,6 +49,18 @@ pub fn parse_key_sequence(raw: &str, transformer: Option<&XKBTransformer>) -> Re
     Ok(res)
 }
 
+/// Parses a key sequence for nodes that only write key events, toggles need the state of a `Mapper`.
+pub fn parse_key_sequence_without_toggles(
+    raw: &str,
+    transformer: Option<&XKBTransformer>,
+) -> Result<Vec<ParsedKeyAction>> {
+    let seq = parse_key_sequence(raw, transformer)?;
+    if seq.iter().any(|action| matches!(action, ParsedKeyAction::Toggle(_))) {
+        return Err(anyhow!("{}\ntoggles are only supported in the mappings of a Mapper", raw));
+    }
+    Ok(seq)
+}
+
 pub fn parse_key(raw: &str, transformer: Option<&XKBTransformer>) -> Result<Key> {
     let (rest, ((key, flags))) = key_utf(transformer)(raw).map_err(|err| format_err(err, raw, 0))?;
 
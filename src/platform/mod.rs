@@ -1,5 +1,7 @@
 use std::process::Command;
 
+use crate::*;
+
 pub enum Platform {
     Hyprland,
     X11,
@@ -16,6 +18,21 @@ pub fn get_platform() -> Platform {
     Platform::Unknown
 }
 
+/// Reads the text on the clipboard, using `wl-paste` on Hyprland and `xclip` on X11.
+pub fn read_clipboard() -> Result<String> {
+    let output = match get_platform() {
+        Platform::Hyprland => Command::new("wl-paste").arg("--no-newline").output(),
+        Platform::X11 => Command::new("xclip").args(["-selection", "clipboard", "-o"]).output(),
+        Platform::Unknown => return Err(ApplicationError::UnsupportedPlatform.into()),
+    }
+    .map_err(|err| anyhow!("failed to read the clipboard: {}", err))?;
+
+    if !output.status.success() {
+        return Err(anyhow!("failed to read the clipboard: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
 fn platform_is_hyprland() -> bool {
     Command::new("printenv")
         .arg("HYPRLAND_INSTANCE_SIGNATURE")
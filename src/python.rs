@@ -7,7 +7,7 @@ use signal_hook::{consts::SIGINT, iterator::Signals};
 use tokio::runtime::Runtime;
 
 use crate::virtual_writer::VirtualWriter;
-use crate::window::Window;
+use crate::window::{ActiveWindowInfo, Window};
 use crate::*;
 
 #[pyclass]
@@ -131,6 +131,16 @@ fn __test() -> PyResult<Vec<String>> {
     Ok(global::TEST_PIPE.lock().unwrap().iter().map(|x| serde_json::to_string(x).unwrap()).collect())
 }
 
+#[cfg(feature = "integration")]
+#[pyfunction]
+fn __set_active_window(class: String, title: Option<String>) {
+    global::ACTIVE_WINDOW.send_replace(Some(ActiveWindowInfo {
+        class,
+        instance: "".to_string(),
+        name: title.unwrap_or_default(),
+    }));
+}
+
 #[pymodule]
 fn map2(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(wait, m)?)?;
@@ -139,11 +149,21 @@ fn map2(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(link, m)?)?;
     #[cfg(feature = "integration")]
     m.add_function(wrap_pyfunction!(__test, m)?)?;
+    #[cfg(feature = "integration")]
+    m.add_function(wrap_pyfunction!(__set_active_window, m)?)?;
     m.add_class::<Reader>()?;
     m.add_class::<Mapper>()?;
     m.add_class::<KeyMapperSnapshot>()?;
     m.add_class::<TextMapper>()?;
     m.add_class::<ChordMapper>()?;
+    m.add_class::<ButtonChordMapper>()?;
+    m.add_class::<GestureMapper>()?;
+    m.add_class::<AxisMapper>()?;
+    m.add_class::<AbsRelMapper>()?;
+    m.add_class::<PointerMapper>()?;
+    m.add_class::<ScrollMapper>()?;
+    m.add_class::<TouchpadMapper>()?;
+    m.add_class::<TabletMapper>()?;
     m.add_class::<Writer>()?;
     m.add_class::<VirtualWriter>()?;
     m.add_class::<Window>()?;
@@ -124,7 +124,7 @@ impl Reader {
     }
 
     pub fn send(&mut self, val: String) -> PyResult<()> {
-        let actions = parse_key_sequence(val.as_str(), Some(&self.transformer))
+        let actions = parse_key_sequence_without_toggles(val.as_str(), Some(&self.transformer))
             .map_err(|err| ApplicationError::KeySequenceParse(err.to_string()).into_py())?
             .to_key_actions();
 
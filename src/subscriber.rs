@@ -14,6 +14,30 @@ pub fn node_to_link_dst(target: &PyAny) -> Option<Arc<dyn LinkDst>> {
     if let Ok(target) = target.extract::<PyRefMut<ChordMapper>>() {
         return Some(target.link.clone());
     }
+    if let Ok(target) = target.extract::<PyRefMut<ButtonChordMapper>>() {
+        return Some(target.link.clone());
+    }
+    if let Ok(target) = target.extract::<PyRefMut<GestureMapper>>() {
+        return Some(target.link.clone());
+    }
+    if let Ok(target) = target.extract::<PyRefMut<AxisMapper>>() {
+        return Some(target.link.clone());
+    }
+    if let Ok(target) = target.extract::<PyRefMut<AbsRelMapper>>() {
+        return Some(target.link.clone());
+    }
+    if let Ok(target) = target.extract::<PyRefMut<PointerMapper>>() {
+        return Some(target.link.clone());
+    }
+    if let Ok(target) = target.extract::<PyRefMut<ScrollMapper>>() {
+        return Some(target.link.clone());
+    }
+    if let Ok(target) = target.extract::<PyRefMut<TouchpadMapper>>() {
+        return Some(target.link.clone());
+    }
+    if let Ok(target) = target.extract::<PyRefMut<TabletMapper>>() {
+        return Some(target.link.clone());
+    }
     if let Ok(target) = target.extract::<PyRefMut<Writer>>() {
         return Some(target.link.clone());
     }
@@ -33,6 +57,30 @@ pub fn node_to_link_src(target: &PyAny) -> Option<Arc<dyn LinkSrc>> {
     if let Ok(target) = target.extract::<PyRefMut<ChordMapper>>() {
         return Some(target.link.clone());
     }
+    if let Ok(target) = target.extract::<PyRefMut<ButtonChordMapper>>() {
+        return Some(target.link.clone());
+    }
+    if let Ok(target) = target.extract::<PyRefMut<GestureMapper>>() {
+        return Some(target.link.clone());
+    }
+    if let Ok(target) = target.extract::<PyRefMut<AxisMapper>>() {
+        return Some(target.link.clone());
+    }
+    if let Ok(target) = target.extract::<PyRefMut<AbsRelMapper>>() {
+        return Some(target.link.clone());
+    }
+    if let Ok(target) = target.extract::<PyRefMut<PointerMapper>>() {
+        return Some(target.link.clone());
+    }
+    if let Ok(target) = target.extract::<PyRefMut<ScrollMapper>>() {
+        return Some(target.link.clone());
+    }
+    if let Ok(target) = target.extract::<PyRefMut<TouchpadMapper>>() {
+        return Some(target.link.clone());
+    }
+    if let Ok(target) = target.extract::<PyRefMut<TabletMapper>>() {
+        return Some(target.link.clone());
+    }
     None
 }
 
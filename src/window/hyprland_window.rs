@@ -1,3 +1,4 @@
+use crate::global::ACTIVE_WINDOW;
 use crate::python::*;
 use crate::window::window_base::{ActiveWindowInfo, WindowControlMessage, WindowHandler};
 use crate::*;
@@ -32,6 +33,7 @@ pub fn hyprland_window_handler() -> WindowHandler {
             let handle_window_change = {
                 let subscriptions = subscriptions.clone();
                 move |info: ActiveWindowInfo| {
+                    ACTIVE_WINDOW.send_replace(Some(info.clone()));
                     tokio::task::spawn_blocking(move || {
                         Python::with_gil(|py| {
                             let subscriptions = { subscriptions.lock().unwrap().values().cloned().collect::<Vec<_>>() };
@@ -71,6 +73,16 @@ pub fn hyprland_window_handler() -> WindowHandler {
                 event_listener.start_listener_async().await;
             });
 
+            tokio::task::spawn(async move {
+                if let Ok(Some(info)) = Client::get_active_async().await {
+                    ACTIVE_WINDOW.send_replace(Some(ActiveWindowInfo {
+                        class: info.class,
+                        instance: "".to_string(),
+                        name: info.title,
+                    }));
+                }
+            });
+
             tokio::task::spawn(async move {
                 loop {
                     let msg = match subscription_rx.recv().await {
@@ -1,5 +1,7 @@
 mod hyprland_window;
 mod window_base;
+mod window_condition;
 mod x11_window;
 
-pub use window_base::Window;
+pub use window_base::{watch_active_window, ActiveWindowInfo, Window};
+pub use window_condition::WindowCondition;
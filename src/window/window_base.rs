@@ -1,3 +1,4 @@
+use crate::global::ACTIVE_WINDOW;
 use crate::platform::{get_platform, Platform};
 use crate::python::*;
 use crate::window::hyprland_window::hyprland_window_handler;
@@ -11,6 +12,13 @@ pub struct ActiveWindowInfo {
     pub name: String,
 }
 
+#[cfg(not(feature = "integration"))]
+lazy_static! {
+    // feeds the active window to mappers, started on first use and kept running
+    static ref ACTIVE_WINDOW_LISTENER: Mutex<Option<(tokio::sync::mpsc::Sender<WindowControlMessage>, oneshot::Sender<()>)>> =
+        Mutex::new(None);
+}
+
 pub type WindowHandler =
     Box<dyn Fn(oneshot::Receiver<()>, tokio::sync::mpsc::Receiver<WindowControlMessage>) -> Result<()> + Send + Sync>;
 
@@ -86,3 +94,22 @@ pub fn spawn_listener_thread(
     });
     (subscription_tx, handle, exit_tx)
 }
+
+/// Subscribes to active window changes, starts a window listener if none is running yet.
+pub fn watch_active_window() -> Result<tokio::sync::watch::Receiver<Option<ActiveWindowInfo>>> {
+    // integration tests set the active window manually
+    #[cfg(not(feature = "integration"))]
+    {
+        let mut listener = ACTIVE_WINDOW_LISTENER.lock().unwrap();
+        if listener.is_none() {
+            let handler = match get_platform() {
+                Platform::Hyprland => hyprland_window_handler(),
+                Platform::X11 => x11_window_handler(),
+                Platform::Unknown => return Err(ApplicationError::UnsupportedPlatform.into()),
+            };
+            let (subscription_tx, _, exit_tx) = spawn_listener_thread(handler);
+            *listener = Some((subscription_tx, exit_tx));
+        }
+    }
+    Ok(ACTIVE_WINDOW.subscribe())
+}
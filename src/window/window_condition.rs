@@ -0,0 +1,67 @@
+use crate::python::*;
+use crate::window::ActiveWindowInfo;
+use crate::*;
+use regex::Regex;
+
+/// Regexes the active window has to match, every given property has to match.
+#[derive(Clone, Debug)]
+pub struct WindowCondition {
+    class: Option<Regex>,
+    title: Option<Regex>,
+}
+
+impl WindowCondition {
+    pub fn from_py(value: &PyAny) -> PyResult<Self> {
+        let options = value
+            .extract::<HashMap<String, String>>()
+            .map_err(|_| PyRuntimeError::new_err("'when' must be of type '{ class?: string, title?: string }'"))?;
+
+        let mut condition = WindowCondition { class: None, title: None };
+        for (property, pattern) in options {
+            let regex = Regex::new(&pattern)
+                .map_err(|err| PyRuntimeError::new_err(format!("invalid regex for '{}' in 'when':\n{}", property, err)))?;
+            match property.as_str() {
+                "class" => condition.class = Some(regex),
+                "title" => condition.title = Some(regex),
+                _ => {
+                    return Err(PyRuntimeError::new_err(format!(
+                        "unknown window property '{}' in 'when', expected one of: 'class', 'title'",
+                        property
+                    )))
+                }
+            }
+        }
+
+        if condition.class.is_none() && condition.title.is_none() {
+            return Err(PyRuntimeError::new_err("'when' must contain at least one of: 'class', 'title'"));
+        }
+        Ok(condition)
+    }
+
+    pub fn to_py_dict<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let dict = PyDict::new(py);
+        if let Some(class) = self.class.as_ref() {
+            dict.set_item("class", class.as_str())?;
+        }
+        if let Some(title) = self.title.as_ref() {
+            dict.set_item("title", title.as_str())?;
+        }
+        Ok(dict)
+    }
+
+    pub fn matches(&self, window: Option<&ActiveWindowInfo>) -> bool {
+        let window = match window {
+            Some(window) => window,
+            None => return false,
+        };
+        self.class.as_ref().map_or(true, |class| class.is_match(&window.class))
+            && self.title.as_ref().map_or(true, |title| title.is_match(&window.name))
+    }
+}
+
+impl PartialEq for WindowCondition {
+    fn eq(&self, other: &Self) -> bool {
+        self.class.as_ref().map(Regex::as_str) == other.class.as_ref().map(Regex::as_str)
+            && self.title.as_ref().map(Regex::as_str) == other.title.as_ref().map(Regex::as_str)
+    }
+}
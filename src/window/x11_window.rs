@@ -1,3 +1,4 @@
+use crate::global::ACTIVE_WINDOW;
 use crate::python::*;
 use crate::window::window_base::{ActiveWindowInfo, WindowControlMessage, WindowHandler};
 use crate::*;
@@ -18,6 +19,10 @@ pub fn x11_window_handler() -> WindowHandler {
             let x11_state = Arc::new(x11_initialize().unwrap());
             let subscriptions = Arc::new(Mutex::new(HashMap::new()));
 
+            if let Ok(info) = x11_get_active_window() {
+                ACTIVE_WINDOW.send_replace(Some(info));
+            }
+
             loop {
                 if exit_rx.try_recv().is_ok() {
                     break;
@@ -37,6 +42,7 @@ pub fn x11_window_handler() -> WindowHandler {
                 let info = get_window_info_x11(&x11_state);
 
                 if let Ok(Some(val)) = info {
+                    ACTIVE_WINDOW.send_replace(Some(val.clone()));
                     Python::with_gil(|py| {
                         for callback in subscriptions.lock().unwrap().values() {
                             let is_callable = callback.as_ref(py).is_callable();
@@ -1,5 +1,6 @@
 #[cfg(not(feature = "integration"))]
 use evdev_rs::enums::EventType::EV_SYN;
+use evdev_rs::enums::EV_REL;
 use pythonize::depythonize;
 #[cfg(not(feature = "integration"))]
 use std::sync::mpsc;
@@ -17,6 +18,87 @@ use crate::*;
 struct State {
     ev_tx: tokio::sync::mpsc::Sender<InputEvent>,
     prev: HashMap<Uuid, Arc<dyn LinkSrc>>,
+    // only set if the device supports high-resolution scrolling
+    wheel: Option<WheelPairing>,
+}
+
+impl State {
+    fn send(&mut self, ev: InputEvent) -> Result<()> {
+        let events = match (&mut self.wheel, ev) {
+            (Some(wheel), InputEvent::Raw(ev)) => wheel.pair(ev),
+            (None, InputEvent::Raw(ev)) => vec![ev],
+        };
+        self.send_paired(events)
+    }
+
+    // key sequences sent to the writer don't contain sync events, the frame ends with the sequence
+    fn end_frame(&mut self) -> Result<()> {
+        let events = match &mut self.wheel {
+            Some(wheel) => wheel.flush(),
+            None => vec![],
+        };
+        self.send_paired(events)
+    }
+
+    fn send_paired(&mut self, events: Vec<EvdevInputEvent>) -> Result<()> {
+        for ev in events {
+            self.ev_tx.try_send(InputEvent::Raw(ev)).map_err(|_| ApplicationError::TooManyEvents.into_py())?;
+        }
+        Ok(())
+    }
+}
+
+/// Consumers that understand high-resolution scrolling ignore the legacy wheel events of devices that support it.
+/// Legacy events are held back until the frame ends, if their axis sent no high-resolution event in the same frame,
+/// one is added.
+#[derive(Default)]
+struct WheelPairing {
+    // the high-resolution axes that sent an event in the current frame
+    hi_res: HashSet<EV_REL>,
+    // the legacy wheel events of the current frame
+    legacy: Vec<EvdevInputEvent>,
+}
+
+impl WheelPairing {
+    fn pair(&mut self, ev: EvdevInputEvent) -> Vec<EvdevInputEvent> {
+        match ev.event_code {
+            EventCode::EV_REL(axis @ (REL_WHEEL_HI_RES | REL_HWHEEL_HI_RES)) => {
+                self.hi_res.insert(axis);
+                vec![ev]
+            }
+            EventCode::EV_REL(REL_WHEEL | REL_HWHEEL) => {
+                self.legacy.push(ev);
+                vec![]
+            }
+            EventCode::EV_SYN(evdev_rs::enums::EV_SYN::SYN_REPORT) => {
+                let mut events = self.flush();
+                events.push(ev);
+                events
+            }
+            _ => vec![ev],
+        }
+    }
+
+    /// Ends the frame, returns the held back legacy events preceded by the added high-resolution events.
+    fn flush(&mut self) -> Vec<EvdevInputEvent> {
+        let mut events = vec![];
+        for ev in self.legacy.drain(..) {
+            let hi_res = match ev.event_code {
+                EventCode::EV_REL(REL_HWHEEL) => REL_HWHEEL_HI_RES,
+                _ => REL_WHEEL_HI_RES,
+            };
+            if !self.hi_res.contains(&hi_res) {
+                events.push(EvdevInputEvent {
+                    event_code: EventCode::EV_REL(hi_res),
+                    value: ev.value * HI_RES_PER_DETENT,
+                    time: ev.time,
+                });
+            }
+            events.push(ev);
+        }
+        self.hi_res.clear();
+        events
+    }
 }
 
 #[pyclass]
@@ -61,6 +143,14 @@ impl Writer {
             if _capabilities.rel {
                 capabilities.enable_all_rel();
             }
+            if _capabilities.hi_res_scroll {
+                capabilities.enable_all_rel();
+                capabilities.enable_hi_res_scroll();
+            }
+            // before the abs specification, so it can override the tablet's axes
+            if _capabilities.tablet {
+                capabilities.enable_tablet();
+            }
             match _capabilities.abs {
                 capabilities::Abs::Bool(x) if x => capabilities.enable_all_abs(),
                 capabilities::Abs::Specification(x) => {
@@ -117,7 +207,8 @@ impl Writer {
         let id = Uuid::new_v4();
         let (ev_tx, mut ev_rx) = tokio::sync::mpsc::channel::<InputEvent>(255);
         let (exit_tx, mut exit_rx) = tokio::sync::mpsc::channel::<()>(32);
-        let state = Arc::new(Mutex::new(State { ev_tx, prev: Default::default() }));
+        let wheel = device_init_policy.has_hi_res_scroll().then(WheelPairing::default);
+        let state = Arc::new(Mutex::new(State { ev_tx, prev: Default::default(), wheel }));
         let link = Arc::new(WriterLink { id, state: state.clone() });
 
         #[cfg(not(feature = "integration"))]
@@ -192,17 +283,15 @@ impl Writer {
     }
 
     pub fn send(&mut self, val: String) -> PyResult<()> {
-        let actions = parse_key_sequence(val.as_str(), Some(&self.transformer))
+        let actions = parse_key_sequence_without_toggles(val.as_str(), Some(&self.transformer))
             .map_err(|err| ApplicationError::KeySequenceParse(err.to_string()).into_py())?
             .to_key_actions();
 
-        let state = self.state.lock().unwrap();
+        let mut state = self.state.lock().unwrap();
         for action in actions {
-            state
-                .ev_tx
-                .try_send(InputEvent::Raw(action.to_input_ev()))
-                .expect(&ApplicationError::TooManyEvents.to_string());
+            state.send(InputEvent::Raw(action.to_input_ev())).expect(&ApplicationError::TooManyEvents.to_string());
         }
+        state.end_frame().expect(&ApplicationError::TooManyEvents.to_string());
         Ok(())
     }
 
@@ -251,7 +340,6 @@ impl LinkDst for WriterLink {
         Ok(self.state.lock().unwrap().prev.remove(id).is_some())
     }
     fn send(&self, ev: InputEvent) -> Result<()> {
-        self.state.lock().unwrap().ev_tx.try_send(ev).map_err(|err| ApplicationError::TooManyEvents.into_py())?;
-        Ok(())
+        self.state.lock().unwrap().send(ev)
     }
 }